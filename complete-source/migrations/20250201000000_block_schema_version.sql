@@ -0,0 +1,3 @@
+-- Version of the JSON shape stored in content_blocks.content.
+-- Rows written before versioning existed are the original (v1) shape.
+ALTER TABLE content_blocks ADD COLUMN schema_version INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,9 @@
+-- Replace the free-text block_type column with a closed set of kinds.
+CREATE TYPE block_kind AS ENUM ('text', 'gallery', 'video', 'audio', 'file');
+
+-- The JSON "type" tag is what actually gets rendered, so it wins wherever the
+-- column disagrees with it (e.g. unknown form values that were stored as-is
+-- while the content silently fell back to Text).
+ALTER TABLE content_blocks
+    ALTER COLUMN block_type TYPE block_kind
+    USING lower(content->>'type')::block_kind;
@@ -0,0 +1 @@
+ALTER TYPE block_kind ADD VALUE IF NOT EXISTS 'embed';
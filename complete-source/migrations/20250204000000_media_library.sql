@@ -0,0 +1,15 @@
+-- Every upload, with whatever was learned about it at upload time.
+CREATE TABLE media (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    url TEXT NOT NULL,
+    original_name TEXT NOT NULL,
+    mime_type TEXT NOT NULL,
+    byte_size BIGINT NOT NULL,
+    local_path TEXT, -- Set when stored by the local backend
+    metadata JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_media_mime_type ON media(mime_type, created_at DESC);
+
+ALTER TYPE block_kind ADD VALUE IF NOT EXISTS 'hosted_video';
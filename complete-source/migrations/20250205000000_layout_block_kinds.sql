@@ -0,0 +1,4 @@
+ALTER TYPE block_kind ADD VALUE IF NOT EXISTS 'quote';
+ALTER TYPE block_kind ADD VALUE IF NOT EXISTS 'credits';
+ALTER TYPE block_kind ADD VALUE IF NOT EXISTS 'link_list';
+ALTER TYPE block_kind ADD VALUE IF NOT EXISTS 'columns';
@@ -0,0 +1,6 @@
+ALTER TABLE projects
+    ADD COLUMN latitude DOUBLE PRECISION,
+    ADD COLUMN longitude DOUBLE PRECISION,
+    ADD COLUMN location_name TEXT;
+
+ALTER TYPE block_kind ADD VALUE IF NOT EXISTS 'map';
@@ -0,0 +1,12 @@
+-- Text blocks and pages can be written in HTML or Markdown. Pages keep the
+-- rendered HTML in `content` and what was written in `source`; Text blocks
+-- carry both in their JSON (block schema v4).
+CREATE TYPE text_format AS ENUM ('html', 'markdown');
+
+ALTER TABLE pages
+    ADD COLUMN format text_format NOT NULL DEFAULT 'html',
+    ADD COLUMN source TEXT;
+
+UPDATE pages SET source = content;
+
+ALTER TABLE pages ALTER COLUMN source SET NOT NULL;
@@ -0,0 +1,10 @@
+ALTER TABLE projects
+    ADD COLUMN featured BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN position INTEGER; -- Manual index order; unplaced projects follow by date
+
+-- Site-wide settings edited in the admin, one row per setting.
+CREATE TABLE site_settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,5 @@
+-- Where crops of the thumbnail centre, as fractions from the top left.
+ALTER TABLE projects
+    ADD COLUMN thumbnail_focus_x DOUBLE PRECISION NOT NULL DEFAULT 0.5,
+    ADD COLUMN thumbnail_focus_y DOUBLE PRECISION NOT NULL DEFAULT 0.5,
+    ADD COLUMN social_image_url TEXT; -- 1200x630 crop of the thumbnail, when one could be made
@@ -0,0 +1,13 @@
+-- Old addresses that answer with a 301. Redirects left behind by a slug
+-- change point at the project itself, so they follow later renames; manual
+-- ones point at a fixed path or URL.
+CREATE TABLE redirects (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    from_path TEXT NOT NULL UNIQUE,
+    to_path TEXT,
+    project_id UUID REFERENCES projects(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK ((to_path IS NULL) <> (project_id IS NULL))
+);
+
+CREATE INDEX redirects_project_id ON redirects (project_id);
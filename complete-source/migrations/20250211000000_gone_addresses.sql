@@ -0,0 +1,5 @@
+-- Redirects with neither a project nor a path are addresses that are gone
+-- for good, e.g. those of a deleted project, and answer with a 410.
+ALTER TABLE redirects
+    DROP CONSTRAINT redirects_check,
+    ADD CONSTRAINT redirects_one_target CHECK (to_path IS NULL OR project_id IS NULL);
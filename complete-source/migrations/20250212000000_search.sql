@@ -0,0 +1,69 @@
+-- Full-text search over projects, Text blocks and pages. The app writes the
+-- plain text of blocks and pages to `search_text` (block JSON varies by
+-- schema version, so it isn't read here); triggers keep `search_vector` in
+-- step, stemmed in the language chosen in the site settings.
+
+CREATE FUNCTION search_config() RETURNS regconfig
+    LANGUAGE sql STABLE
+    AS $$
+        SELECT COALESCE(
+            (SELECT value::regconfig FROM site_settings WHERE key = 'search_language'),
+            'english'::regconfig
+        )
+    $$;
+
+ALTER TABLE projects ADD COLUMN search_vector tsvector;
+ALTER TABLE content_blocks
+    ADD COLUMN search_text TEXT, -- NULL for blocks without text
+    ADD COLUMN search_vector tsvector;
+ALTER TABLE pages
+    ADD COLUMN search_text TEXT,
+    ADD COLUMN search_vector tsvector;
+
+CREATE FUNCTION projects_search_update() RETURNS trigger
+    LANGUAGE plpgsql
+    AS $$
+    BEGIN
+        NEW.search_vector :=
+            setweight(to_tsvector(search_config(), COALESCE(NEW.title, '')), 'A') ||
+            setweight(to_tsvector(search_config(), COALESCE(NEW.description, '')), 'B') ||
+            setweight(to_tsvector(search_config(), COALESCE(NEW.location_name, '')), 'C');
+        RETURN NEW;
+    END
+    $$;
+
+CREATE FUNCTION content_blocks_search_update() RETURNS trigger
+    LANGUAGE plpgsql
+    AS $$
+    BEGIN
+        NEW.search_vector := setweight(to_tsvector(search_config(), COALESCE(NEW.search_text, '')), 'B');
+        RETURN NEW;
+    END
+    $$;
+
+CREATE FUNCTION pages_search_update() RETURNS trigger
+    LANGUAGE plpgsql
+    AS $$
+    BEGIN
+        NEW.search_vector :=
+            setweight(to_tsvector(search_config(), COALESCE(NEW.title, '')), 'A') ||
+            setweight(to_tsvector(search_config(), COALESCE(NEW.search_text, '')), 'B');
+        RETURN NEW;
+    END
+    $$;
+
+CREATE TRIGGER projects_search BEFORE INSERT OR UPDATE ON projects
+    FOR EACH ROW EXECUTE FUNCTION projects_search_update();
+CREATE TRIGGER content_blocks_search BEFORE INSERT OR UPDATE ON content_blocks
+    FOR EACH ROW EXECUTE FUNCTION content_blocks_search_update();
+CREATE TRIGGER pages_search BEFORE INSERT OR UPDATE ON pages
+    FOR EACH ROW EXECUTE FUNCTION pages_search_update();
+
+-- Pages get a rough plain text until they're next saved; blocks are filled
+-- in by `cargo run --bin reindex_search`.
+UPDATE projects SET search_vector = NULL;
+UPDATE pages SET search_text = regexp_replace(content, '<[^>]*>', ' ', 'g');
+
+CREATE INDEX idx_projects_search ON projects USING GIN (search_vector);
+CREATE INDEX idx_content_blocks_search ON content_blocks USING GIN (search_vector);
+CREATE INDEX idx_pages_search ON pages USING GIN (search_vector);
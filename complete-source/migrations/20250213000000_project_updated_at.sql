@@ -0,0 +1,21 @@
+-- A project's page changes with its blocks, so adding, editing, moving or
+-- removing one updates the project too (the sitemap's lastmod reads it).
+-- Only columns that change the page count: reindexing search doesn't.
+
+CREATE FUNCTION content_blocks_touch_project() RETURNS trigger
+    LANGUAGE plpgsql
+    AS $$
+    BEGIN
+        IF TG_OP <> 'INSERT' THEN
+            UPDATE projects SET updated_at = NOW() WHERE id = OLD.project_id;
+        END IF;
+        IF TG_OP = 'INSERT' OR NEW.project_id <> OLD.project_id THEN
+            UPDATE projects SET updated_at = NOW() WHERE id = NEW.project_id;
+        END IF;
+        RETURN NULL;
+    END
+    $$;
+
+CREATE TRIGGER content_blocks_touch_project
+    AFTER INSERT OR DELETE OR UPDATE OF content, sort_order, project_id ON content_blocks
+    FOR EACH ROW EXECUTE FUNCTION content_blocks_touch_project();
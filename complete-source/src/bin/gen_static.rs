@@ -1,4 +1,12 @@
+use artist_portfolio::feed;
+use artist_portfolio::redirects;
+use artist_portfolio::robots;
 use artist_portfolio::routes::public;
+use artist_portfolio::search_index::{self, IndexEntry};
+use artist_portfolio::settings::SiteSettings;
+use artist_portfolio::site::Site;
+use artist_portfolio::sitemap;
+use artist_portfolio::templates::SearchTemplate;
 use sqlx::postgres::PgPoolOptions;
 use std::path::Path;
 use tokio::fs;
@@ -58,14 +66,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Projects
     println!("Generating Project pages...");
+    let mut search_entries = Vec::new();
     for (_year, projects) in index_tmpl.grouped_projects.iter() {
         for project in projects {
             println!("  Generating project: {}", project.slug);
-            let tmpl = public::get_project_details_template(&pool, &project.slug).await;
+            let tmpl = public::get_project_details_template(&pool, &project.slug).await?;
             if let Some(t) = tmpl {
                let p_dir = dist.join("project").join(&project.slug);
                fs::create_dir_all(&p_dir).await?;
                write_file(p_dir.join("index.html"), t.render()?).await?;
+               search_entries.push(IndexEntry::project(&t.project, &t.blocks));
             }
         }
     }
@@ -76,6 +86,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let about_dir = dist.join("about");
     fs::create_dir_all(&about_dir).await?;
     write_file(about_dir.join("index.html"), about.render()?).await?;
+    search_entries.push(IndexEntry::page(&about.page));
 
     // Contact
     println!("Generating Contact page...");
@@ -83,7 +94,73 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let contact_dir = dist.join("contact");
     fs::create_dir_all(&contact_dir).await?;
     write_file(contact_dir.join("index.html"), contact.render()?).await?;
+    search_entries.push(IndexEntry::page(&contact.page));
 
+    // Map
+    println!("Generating Map page...");
+    let map = public::get_map_template(&pool).await;
+    let map_dir = dist.join("map");
+    fs::create_dir_all(&map_dir).await?;
+    write_file(map_dir.join("index.html"), map.render()?).await?;
+
+    // Feeds, the same files the server answers with
+    println!("Generating Feeds...");
+    let feed = feed::load(&pool, Site::global()).await?;
+    write_file(dist.join(feed::ATOM_PATH.trim_start_matches('/')), feed::atom(&feed)?).await?;
+    write_file(dist.join(feed::RSS_PATH.trim_start_matches('/')), feed::rss(&feed)?).await?;
+    write_file(dist.join(feed::JSON_PATH.trim_start_matches('/')), feed::json_feed(&feed)).await?;
+
+    // Sitemap and robots.txt, as the server answers them
+    println!("Generating Sitemap and robots.txt...");
+    let urls = sitemap::load(&pool, Site::global()).await?;
+    for (path, xml) in sitemap::files(Site::global(), &urls, sitemap::MAX_URLS)? {
+        let file = dist.join(path.trim_start_matches('/'));
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+        write_file(file, xml).await?;
+    }
+    let robots_rules = SiteSettings::load(&pool).await.robots_rules;
+    write_file(dist.join(robots::PATH.trim_start_matches('/')), robots::render(Site::global(), &robots_rules)).await?;
+
+    // Search: the page searches an index in the browser, as there's no
+    // server to ask.
+    println!("Generating Search page and index...");
+    let index = search_index::build(&search_entries, search_index::CHUNK_BYTES)?;
+    // The manifest is at search_index::MANIFEST_PATH, with its chunks.
+    let search_dir = dist.join("search");
+    fs::create_dir_all(&search_dir).await?;
+    write_file(search_dir.join("index.json"), index.manifest).await?;
+    for (name, json) in index.chunks {
+        write_file(search_dir.join(name), json).await?;
+    }
+    let search = SearchTemplate {
+        index_url: Some(search_index::MANIFEST_PATH.to_string()),
+        ..public::get_search_template(&pool, "").await
+    };
+    write_file(search_dir.join("index.html"), search.render()?).await?;
+
+    // Not found, served by static hosts for any missing address
+    println!("Generating 404 page...");
+    let not_found = public::get_not_found_template(&pool).await;
+    write_file(dist.join("404.html"), not_found.render()?).await?;
+
+    // Redirects: a `_redirects` file for hosts that read one, and a stub
+    // page at each old address for those that don't.
+    println!("Generating Redirects...");
+    let rules = redirects::all(&pool).await?;
+    for rule in &rules {
+        let Some(target) = &rule.target else { continue };
+        let stub_dir = dist.join(rule.from_path.trim_start_matches('/'));
+        let stub = stub_dir.join("index.html");
+        if stub.exists() {
+            println!("  Skipping {}: a page is already there", rule.from_path);
+            continue;
+        }
+        fs::create_dir_all(&stub_dir).await?;
+        write_file(stub, redirects::stub_html(target)).await?;
+    }
+    write_file(dist.join("_redirects"), redirects::redirects_file(&rules)).await?;
 
     // Admin Redirect
     println!("Generating Admin Redirect...");
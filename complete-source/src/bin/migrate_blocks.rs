@@ -0,0 +1,69 @@
+// Rewrites every stored content block to the current block schema version.
+// Run with: cargo run --bin migrate_blocks [-- --dry-run]
+use artist_portfolio::block_schema::{self, CURRENT_VERSION};
+use sqlx::postgres::PgPoolOptions;
+use sqlx::Row;
+use std::env;
+use uuid::Uuid;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenvy::dotenv().ok();
+    let dry_run = env::args().any(|a| a == "--dry-run");
+
+    let db_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    println!("Connecting to database...");
+    let pool = PgPoolOptions::new().connect(&db_url).await?;
+
+    let rows = sqlx::query(
+        "SELECT id, schema_version, content FROM content_blocks WHERE schema_version <> $1 ORDER BY project_id, sort_order"
+    )
+    .bind(CURRENT_VERSION)
+    .fetch_all(&pool)
+    .await?;
+
+    println!("{} block(s) not at schema version {}", rows.len(), CURRENT_VERSION);
+
+    let mut tx = pool.begin().await?;
+    let mut upgraded = 0;
+    let mut failed = 0;
+
+    for row in rows {
+        let id: Uuid = row.try_get("id")?;
+        let version: i32 = row.try_get("schema_version")?;
+        let content: serde_json::Value = row.try_get("content")?;
+
+        // Upgrade and then round-trip through BlockContent, so only JSON that
+        // the current build can actually load gets written back.
+        let block = match block_schema::decode(version, content) {
+            Ok(block) => block,
+            Err(e) => {
+                println!("  ! {} (v{}): {}", id, version, e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        println!("  {} v{} -> v{}", id, version, CURRENT_VERSION);
+        sqlx::query("UPDATE content_blocks SET content = $1, schema_version = $2 WHERE id = $3")
+            .bind(sqlx::types::Json(block))
+            .bind(CURRENT_VERSION)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        upgraded += 1;
+    }
+
+    if dry_run {
+        tx.rollback().await?;
+        println!("Dry run: {} block(s) would be upgraded, {} failed", upgraded, failed);
+    } else {
+        tx.commit().await?;
+        println!("Upgraded {} block(s), {} failed", upgraded, failed);
+    }
+
+    if failed > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,73 @@
+// Writes the plain text of every content block to `search_text`, which the
+// search triggers index. Run once after the search migration, and again if
+// what a block contributes to search changes.
+// Run with: cargo run --bin reindex_search [-- --dry-run]
+use artist_portfolio::block_schema;
+use artist_portfolio::search;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::Row;
+use std::env;
+use uuid::Uuid;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenvy::dotenv().ok();
+    let dry_run = env::args().any(|a| a == "--dry-run");
+
+    let db_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    println!("Connecting to database...");
+    let pool = PgPoolOptions::new().connect(&db_url).await?;
+
+    let rows = sqlx::query(
+        "SELECT id, schema_version, content, search_text FROM content_blocks ORDER BY project_id, sort_order"
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    println!("{} block(s)", rows.len());
+
+    let mut tx = pool.begin().await?;
+    let mut changed = 0;
+    let mut failed = 0;
+
+    for row in rows {
+        let id: Uuid = row.try_get("id")?;
+        let version: i32 = row.try_get("schema_version")?;
+        let content: serde_json::Value = row.try_get("content")?;
+        let stored: Option<String> = row.try_get("search_text")?;
+
+        let block = match block_schema::decode(version, content) {
+            Ok(block) => block,
+            Err(e) => {
+                println!("  ! {} (v{}): {}", id, version, e);
+                failed += 1;
+                continue;
+            }
+        };
+        let text = search::block_text(&block);
+        if text == stored {
+            continue;
+        }
+
+        println!("  {}: {} word(s)", id, text.as_deref().map_or(0, |t| t.split_whitespace().count()));
+        sqlx::query("UPDATE content_blocks SET search_text = $1 WHERE id = $2")
+            .bind(text)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        changed += 1;
+    }
+
+    if dry_run {
+        tx.rollback().await?;
+        println!("Dry run: {} block(s) would be reindexed, {} failed", changed, failed);
+    } else {
+        tx.commit().await?;
+        println!("Reindexed {} block(s), {} failed", changed, failed);
+    }
+
+    if failed > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
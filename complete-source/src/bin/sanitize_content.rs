@@ -0,0 +1,112 @@
+// Re-renders stored rich text (Text blocks, including those inside Columns,
+// and pages) with the current HTML policy and reports what was removed.
+// Run with: cargo run --bin sanitize_content [-- --dry-run]
+use artist_portfolio::block_schema::{self, CURRENT_VERSION};
+use artist_portfolio::models::TextBlock;
+use artist_portfolio::sanitize::{Policy, Stripped};
+use artist_portfolio::search;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::Row;
+use std::env;
+use uuid::Uuid;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenvy::dotenv().ok();
+    let dry_run = env::args().any(|a| a == "--dry-run");
+    let policy = Policy::from_env();
+
+    let db_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    println!("Connecting to database...");
+    let pool = PgPoolOptions::new().connect(&db_url).await?;
+
+    let mut tx = pool.begin().await?;
+    let mut total = Stripped::default();
+    let mut changed = 0;
+    let mut failed = 0;
+
+    let blocks = sqlx::query(
+        "SELECT id, schema_version, content FROM content_blocks WHERE block_type IN ('text', 'columns') ORDER BY project_id, sort_order"
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    for row in blocks {
+        let id: Uuid = row.try_get("id")?;
+        let version: i32 = row.try_get("schema_version")?;
+        let content: serde_json::Value = row.try_get("content")?;
+
+        let mut block = match block_schema::decode(version, content) {
+            Ok(block) => block,
+            Err(e) => {
+                println!("  ! block {} (v{}): {}", id, version, e);
+                failed += 1;
+                continue;
+            }
+        };
+        let before = serde_json::to_value(&block)?;
+        let stripped = block.sanitize(&policy);
+        // Also catches pure normalisation (e.g. unclosed tags), which strips nothing.
+        if serde_json::to_value(&block)? == before && version == CURRENT_VERSION {
+            continue;
+        }
+
+        if !stripped.is_empty() {
+            println!("  block {}: removed {}", id, stripped);
+        }
+        sqlx::query("UPDATE content_blocks SET content = $1, schema_version = $2, search_text = $3 WHERE id = $4")
+            .bind(sqlx::types::Json(&block))
+            .bind(CURRENT_VERSION)
+            .bind(search::block_text(&block))
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        total.merge(stripped);
+        changed += 1;
+    }
+
+    let pages = sqlx::query("SELECT slug, format, source, content FROM pages ORDER BY slug")
+        .fetch_all(&pool)
+        .await?;
+
+    for row in pages {
+        let slug: String = row.try_get("slug")?;
+        let mut text = TextBlock {
+            format: row.try_get("format")?,
+            source: row.try_get("source")?,
+            html: row.try_get("content")?,
+        };
+        let (source, html) = (text.source.clone(), text.html.clone());
+        let stripped = text.sanitize(&policy);
+        if text.source == source && text.html == html {
+            continue;
+        }
+
+        if !stripped.is_empty() {
+            println!("  page {}: removed {}", slug, stripped);
+        }
+        sqlx::query("UPDATE pages SET source = $1, content = $2, search_text = $3, updated_at = NOW() WHERE slug = $4")
+            .bind(&text.source)
+            .bind(&text.html)
+            .bind(text.plain_text())
+            .bind(&slug)
+            .execute(&mut *tx)
+            .await?;
+        total.merge(stripped);
+        changed += 1;
+    }
+
+    let summary = if total.is_empty() { "nothing".to_string() } else { total.to_string() };
+    if dry_run {
+        tx.rollback().await?;
+        println!("Dry run: {} item(s) would be rewritten, {} failed; would remove {}", changed, failed, summary);
+    } else {
+        tx.commit().await?;
+        println!("Rewrote {} item(s), {} failed; removed {}", changed, failed, summary);
+    }
+
+    if failed > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,108 @@
+//! Versioning for the JSON stored in `content_blocks.content`.
+//!
+//! Every block row carries a `schema_version`. When the shape of a
+//! `BlockContent` variant changes, append an upgrade function to `UPGRADES`
+//! that rewrites JSON of the previous version into the new shape, and add a
+//! fixture for the old shape under `fixtures/blocks/`. Rows are upgraded in
+//! memory when they are read, and `cargo run --bin migrate_blocks` rewrites
+//! them in the database.
+
+use serde_json::{json, Value};
+
+use crate::models::BlockContent;
+
+type Upgrade = fn(Value) -> Result<Value, String>;
+
+/// Upgrade steps, in order. `UPGRADES[0]` turns v1 JSON into v2 JSON,
+/// `UPGRADES[1]` turns v2 into v3, and so on.
+static UPGRADES: &[Upgrade] = &[audio_items_v2, file_items_v3, text_source_v4];
+
+/// The version written for every new or updated block.
+pub const CURRENT_VERSION: i32 = UPGRADES.len() as i32 + 1;
+
+/// Run every upgrade step needed to bring `content` from `version` to
+/// `CURRENT_VERSION`.
+pub fn upgrade(version: i32, mut content: Value) -> Result<Value, String> {
+    if version < 1 {
+        return Err(format!("Invalid block schema version {}", version));
+    }
+    if version > CURRENT_VERSION {
+        return Err(format!(
+            "Block schema version {} is newer than this build supports ({})",
+            version, CURRENT_VERSION
+        ));
+    }
+
+    for step in &UPGRADES[(version - 1) as usize..] {
+        content = step(content)?;
+    }
+    Ok(content)
+}
+
+/// Upgrade stored JSON of any known version and deserialize it.
+pub fn decode(version: i32, content: Value) -> Result<BlockContent, String> {
+    let content = upgrade(version, content)?;
+    serde_json::from_value(content).map_err(|e| e.to_string())
+}
+
+fn block_type(content: &Value) -> Option<&str> {
+    content.get("type").and_then(Value::as_str)
+}
+
+/// v1 -> v2: Audio tracks were `[url, title]` pairs and are now objects
+/// that can carry duration, artist, artwork and waveform peaks.
+fn audio_items_v2(mut content: Value) -> Result<Value, String> {
+    if block_type(&content) != Some("Audio") {
+        return Ok(content);
+    }
+    let tracks = content["data"].as_array().ok_or("Audio data is not a list")?;
+    let items = tracks
+        .iter()
+        .map(|track| match track.as_array().map(Vec::as_slice) {
+            Some([url, title]) => Ok(json!({ "url": url, "title": title })),
+            _ => Err(format!("Unexpected v1 audio track: {}", track)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    content["data"] = Value::Array(items);
+    Ok(content)
+}
+
+/// v2 -> v3: File downloads were `[url, description]` pairs and are now
+/// objects that can carry the file's name, type, size and page count.
+fn file_items_v3(mut content: Value) -> Result<Value, String> {
+    if block_type(&content) != Some("File") {
+        return Ok(content);
+    }
+    let files = content["data"].as_array().ok_or("File data is not a list")?;
+    let items = files
+        .iter()
+        .map(|file| match file.as_array().map(Vec::as_slice) {
+            Some([url, description]) => Ok(json!({ "url": url, "description": description })),
+            _ => Err(format!("Unexpected v2 file item: {}", file)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    content["data"] = Value::Array(items);
+    Ok(content)
+}
+
+/// v3 -> v4: Text was an HTML string and is now its source in a given
+/// format plus the rendered HTML. Text blocks inside Columns change too.
+fn text_source_v4(mut content: Value) -> Result<Value, String> {
+    match block_type(&content) {
+        Some("Text") => {
+            let html = content["data"].as_str().ok_or("Text data is not a string")?.to_string();
+            content["data"] = json!({ "format": "html", "source": html, "html": html });
+        }
+        Some("Columns") => {
+            let columns = content["data"]["columns"].as_array_mut().ok_or("Columns data has no columns")?;
+            for column in columns {
+                let children = column.as_array_mut().ok_or("Column is not a list")?;
+                for child in children.iter_mut() {
+                    *child = text_source_v4(child.take())?;
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(content)
+}
@@ -1,6 +1,9 @@
+use axum::http::HeaderMap;
 use tower_sessions::Session;
 use uuid::Uuid;
 
+use crate::error::AppError;
+
 pub async fn get_or_create_csrf_token(session: &Session) -> String {
     if let Ok(Some(token)) = session.get::<String>("csrf_token").await {
         token
@@ -18,3 +21,18 @@ pub async fn verify_csrf_token(session: &Session, token: &str) -> bool {
         false
     }
 }
+
+/// `verify_csrf_token` for handlers returning `AppError`.
+pub async fn require_csrf_token(session: &Session, token: &str) -> Result<(), AppError> {
+    if verify_csrf_token(session, token).await {
+        Ok(())
+    } else {
+        Err(AppError::Forbidden)
+    }
+}
+
+/// The same for API routes, which send the token in an `X-CSRF-Token` header.
+pub async fn require_csrf_header(session: &Session, headers: &HeaderMap) -> Result<(), AppError> {
+    let token = headers.get("X-CSRF-Token").and_then(|v| v.to_str().ok()).unwrap_or("");
+    require_csrf_token(session, token).await
+}
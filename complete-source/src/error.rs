@@ -0,0 +1,111 @@
+//! The error type of the admin and API handlers. Browser routes render it as
+//! an error page and API routes, through [`ApiError`], as `{"error": ...}`.
+//! Either way the underlying cause is logged, and only a message meant for
+//! the editor is shown.
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Json, Response};
+use std::fmt;
+
+use crate::templates::{ErrorTemplate, DEFAULT_FOOTER};
+
+#[derive(Debug)]
+pub enum AppError {
+    /// What couldn't be found, e.g. "Block".
+    NotFound(&'static str),
+    /// Input that can't be saved, with what's wrong with it.
+    Validation(String),
+    /// Input that clashes with something already saved.
+    Conflict(String),
+    /// A missing or stale CSRF token.
+    Forbidden,
+    /// Storing or processing an upload failed.
+    Storage(String),
+    Database(sqlx::Error),
+    /// Anything else that went wrong on our side, e.g. a failed deploy.
+    Internal(String),
+}
+
+impl AppError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Forbidden => StatusCode::FORBIDDEN,
+            AppError::Storage(_) | AppError::Database(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// What the editor is told. Server-side causes stay in the log.
+    pub fn message(&self) -> String {
+        match self {
+            AppError::NotFound(what) => format!("{} not found", what),
+            AppError::Validation(message) | AppError::Conflict(message) => message.clone(),
+            AppError::Forbidden => "Invalid CSRF Token. Reload the page and try again".to_string(),
+            AppError::Storage(_) => "The file couldn't be stored. Try again in a moment".to_string(),
+            AppError::Database(_) | AppError::Internal(_) => "Something went wrong saving your changes. Try again in a moment".to_string(),
+        }
+    }
+
+    fn log(&self) {
+        if self.status().is_server_error() {
+            tracing::error!("{}", self);
+        } else {
+            tracing::debug!("{}", self);
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Storage(cause) => write!(f, "Storage error: {}", cause),
+            AppError::Database(e) => write!(f, "Database error: {}", e),
+            AppError::Internal(cause) => write!(f, "Internal error: {}", cause),
+            _ => write!(f, "{}", self.message()),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<sqlx::Error> for AppError {
+    fn from(e: sqlx::Error) -> Self {
+        match e {
+            sqlx::Error::RowNotFound => AppError::NotFound("Record"),
+            e => AppError::Database(e),
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        self.log();
+        let status = self.status();
+        ErrorTemplate {
+            code: status.as_u16(),
+            title: status.canonical_reason().unwrap_or("Error").to_string(),
+            message: self.message(),
+            footer: DEFAULT_FOOTER.to_string(),
+        }
+        .into_response()
+    }
+}
+
+/// An [`AppError`] from an API route, answered with JSON.
+#[derive(Debug)]
+pub struct ApiError(pub AppError);
+
+impl<E: Into<AppError>> From<E> for ApiError {
+    fn from(e: E) -> Self {
+        ApiError(e.into())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        self.0.log();
+        (self.0.status(), Json(serde_json::json!({ "error": self.0.message() }))).into_response()
+    }
+}
@@ -0,0 +1,233 @@
+//! Atom, RSS and JSON Feed of the newest projects, for followers and
+//! curators who subscribe to new work. The server answers `/feed.xml`,
+//! `/rss.xml` and `/feed.json` and `gen_static` writes the same files, both
+//! from [`load`] and the render functions here, so they can't drift apart.
+
+use std::collections::HashMap;
+
+use askama::Template;
+use serde_json::json;
+use sqlx::PgPool;
+use time::format_description::well_known::{Rfc2822, Rfc3339};
+use time::{Date, OffsetDateTime};
+use uuid::Uuid;
+
+use crate::models::{BlockContent, ContentBlock, Project};
+use crate::site::Site;
+
+pub const ATOM_PATH: &str = "/feed.xml";
+pub const RSS_PATH: &str = "/rss.xml";
+pub const JSON_PATH: &str = "/feed.json";
+
+pub const ATOM_CONTENT_TYPE: &str = "application/atom+xml; charset=utf-8";
+pub const RSS_CONTENT_TYPE: &str = "application/rss+xml; charset=utf-8";
+pub const JSON_CONTENT_TYPE: &str = "application/feed+json; charset=utf-8";
+
+/// How many projects a feed lists, newest first.
+pub const FEED_ITEMS: i64 = 50;
+
+/// An image sent along with an item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enclosure {
+    pub url: String,
+    pub mime_type: String,
+    /// In bytes, when the media library has the image. RSS requires it, so
+    /// the RSS item goes without the image when it's unknown.
+    pub length: Option<i64>,
+}
+
+/// A project in a feed. URLs are absolute.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedItem {
+    pub url: String,
+    pub title: String,
+    pub summary: Option<String>,
+    /// The project's first Text block, as HTML.
+    pub content_html: Option<String>,
+    pub image: Option<Enclosure>,
+    /// The project's start date.
+    pub date: Date,
+}
+
+impl FeedItem {
+    pub fn new(site: &Site, project: &Project, blocks: &[ContentBlock]) -> FeedItem {
+        let content_html = blocks.iter().find_map(|block| match &block.content.0 {
+            BlockContent::Text(text) if !text.html.trim().is_empty() => Some(text.html.clone()),
+            _ => None,
+        });
+        FeedItem {
+            url: site.absolute(&format!("/project/{}", project.slug)),
+            title: project.title.clone(),
+            summary: project.description.clone().filter(|d| !d.trim().is_empty()),
+            content_html,
+            image: project.social_image().map(|url| Enclosure {
+                url: site.absolute(url),
+                mime_type: image_type(url),
+                length: None,
+            }),
+            date: project.start_date,
+        }
+    }
+
+    fn timestamp(&self) -> OffsetDateTime {
+        self.date.midnight().assume_utc()
+    }
+
+    /// For Atom and JSON Feed, e.g. `2024-03-01T00:00:00Z`.
+    pub fn rfc3339(&self) -> String {
+        self.timestamp().format(&Rfc3339).unwrap_or_default()
+    }
+
+    /// For RSS, e.g. `Fri, 01 Mar 2024 00:00:00 +0000`.
+    pub fn rfc2822(&self) -> String {
+        self.timestamp().format(&Rfc2822).unwrap_or_default()
+    }
+}
+
+/// The type of an image by its extension, for enclosures.
+fn image_type(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    mime_guess::from_path(path)
+        .first()
+        .filter(|mime| mime.type_() == mime_guess::mime::IMAGE)
+        .map_or_else(|| "image/jpeg".to_string(), |mime| mime.to_string())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Feed {
+    pub title: String,
+    pub home_url: String,
+    pub atom_url: String,
+    pub rss_url: String,
+    pub json_url: String,
+    pub items: Vec<FeedItem>,
+}
+
+impl Feed {
+    pub fn new(site: &Site, items: Vec<FeedItem>) -> Feed {
+        Feed {
+            title: site.title.clone(),
+            home_url: site.absolute("/"),
+            atom_url: site.absolute(ATOM_PATH),
+            rss_url: site.absolute(RSS_PATH),
+            json_url: site.absolute(JSON_PATH),
+            items,
+        }
+    }
+
+    /// When the feed last changed: the date of its newest item. Taken from
+    /// the content rather than the clock, so the feed only changes with it.
+    pub fn updated(&self) -> OffsetDateTime {
+        self.items
+            .iter()
+            .map(FeedItem::timestamp)
+            .max()
+            .unwrap_or(OffsetDateTime::UNIX_EPOCH)
+    }
+
+    pub fn updated_rfc3339(&self) -> String {
+        self.updated().format(&Rfc3339).unwrap_or_default()
+    }
+
+    pub fn updated_rfc2822(&self) -> String {
+        self.updated().format(&Rfc2822).unwrap_or_default()
+    }
+}
+
+/// The newest projects, each with its first Text block and the size of its
+/// image.
+pub async fn load(pool: &PgPool, site: &Site) -> Result<Feed, sqlx::Error> {
+    let projects = sqlx::query_as::<_, Project>("SELECT * FROM projects ORDER BY start_date DESC, title LIMIT $1")
+        .bind(FEED_ITEMS)
+        .fetch_all(pool)
+        .await?;
+    let ids: Vec<Uuid> = projects.iter().map(|p| p.id).collect();
+    let blocks = sqlx::query_as::<_, ContentBlock>(
+        "SELECT * FROM content_blocks WHERE project_id = ANY($1) AND block_type = 'text' ORDER BY sort_order ASC"
+    )
+    .bind(&ids)
+    .fetch_all(pool)
+    .await?;
+
+    let mut by_project: HashMap<Uuid, Vec<ContentBlock>> = HashMap::new();
+    for block in blocks {
+        by_project.entry(block.project_id).or_default().push(block);
+    }
+
+    let image_urls: Vec<&str> = projects.iter().filter_map(Project::social_image).collect();
+    let sizes: HashMap<String, i64> = sqlx::query_as::<_, (String, i64)>("SELECT url, byte_size FROM media WHERE url = ANY($1)")
+        .bind(&image_urls)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .collect();
+
+    let items = projects
+        .iter()
+        .map(|project| {
+            let mut item = FeedItem::new(site, project, by_project.get(&project.id).map_or(&[], Vec::as_slice));
+            if let Some(image) = item.image.as_mut() {
+                image.length = project.social_image().and_then(|url| sizes.get(url).copied());
+            }
+            item
+        })
+        .collect();
+    Ok(Feed::new(site, items))
+}
+
+#[derive(Template)]
+#[template(path = "feeds/atom.xml")]
+struct AtomTemplate<'a> {
+    feed: &'a Feed,
+}
+
+#[derive(Template)]
+#[template(path = "feeds/rss.xml")]
+struct RssTemplate<'a> {
+    feed: &'a Feed,
+}
+
+pub fn atom(feed: &Feed) -> Result<String, askama::Error> {
+    AtomTemplate { feed }.render()
+}
+
+pub fn rss(feed: &Feed) -> Result<String, askama::Error> {
+    RssTemplate { feed }.render()
+}
+
+/// The feed as JSON Feed 1.1.
+pub fn json_feed(feed: &Feed) -> String {
+    let items: Vec<serde_json::Value> = feed
+        .items
+        .iter()
+        .map(|item| {
+            let mut entry = json!({
+                "id": item.url,
+                "url": item.url,
+                "title": item.title,
+                "date_published": item.rfc3339(),
+            });
+            match (&item.content_html, &item.summary) {
+                (Some(html), _) => entry["content_html"] = json!(html),
+                (None, Some(summary)) => entry["content_text"] = json!(summary),
+                (None, None) => entry["content_text"] = json!(item.title),
+            }
+            if let Some(summary) = &item.summary {
+                entry["summary"] = json!(summary);
+            }
+            if let Some(image) = &item.image {
+                entry["image"] = json!(image.url);
+            }
+            entry
+        })
+        .collect();
+    let feed = json!({
+        "version": "https://jsonfeed.org/version/1.1",
+        "title": feed.title,
+        "home_page_url": feed.home_url,
+        "feed_url": feed.json_url,
+        "authors": [{ "name": feed.title }],
+        "items": items,
+    });
+    serde_json::to_string_pretty(&feed).unwrap_or_default()
+}
@@ -0,0 +1,92 @@
+//! One-off messages for the editor. A handler queues them in the session
+//! before redirecting, and the next admin page shows and clears them. An
+//! HTMX partial has no page around it, so its messages go out in an
+//! `HX-Trigger` header instead.
+
+use axum::http::HeaderValue;
+use serde::{Deserialize, Serialize};
+use tower_sessions::Session;
+
+const SESSION_KEY: &str = "flash";
+
+/// The event `HX-Trigger` raises with the messages.
+pub const TRIGGER_EVENT: &str = "flash";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Success,
+    Info,
+    Warning,
+    Error,
+}
+
+impl Level {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Success => "success",
+            Level::Info => "info",
+            Level::Warning => "warning",
+            Level::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Flash {
+    pub level: Level,
+    pub text: String,
+}
+
+/// Queues a message for the next page. A session that can't be written is
+/// logged and otherwise ignored: the message is a courtesy.
+pub async fn push(session: &Session, level: Level, text: impl Into<String>) {
+    let mut messages = peek(session).await;
+    messages.push(Flash { level, text: text.into() });
+    if let Err(e) = session.insert(SESSION_KEY, messages).await {
+        tracing::warn!("Couldn't queue a flash message: {}", e);
+    }
+}
+
+pub async fn success(session: &Session, text: impl Into<String>) {
+    push(session, Level::Success, text).await
+}
+
+pub async fn info(session: &Session, text: impl Into<String>) {
+    push(session, Level::Info, text).await
+}
+
+pub async fn warning(session: &Session, text: impl Into<String>) {
+    push(session, Level::Warning, text).await
+}
+
+pub async fn error(session: &Session, text: impl Into<String>) {
+    push(session, Level::Error, text).await
+}
+
+async fn peek(session: &Session) -> Vec<Flash> {
+    session.get(SESSION_KEY).await.ok().flatten().unwrap_or_default()
+}
+
+/// The queued messages, oldest first, which are then cleared.
+pub async fn take(session: &Session) -> Vec<Flash> {
+    session.remove(SESSION_KEY).await.ok().flatten().unwrap_or_default()
+}
+
+/// The `HX-Trigger` value raising [`TRIGGER_EVENT`] with `messages`.
+pub fn hx_trigger(messages: &[Flash]) -> Option<HeaderValue> {
+    let trigger = serde_json::json!({ TRIGGER_EVENT: { "messages": messages } }).to_string();
+    // Browsers read header values as Latin-1, so anything outside ASCII is
+    // sent as a JSON escape. It can only occur inside strings.
+    let mut ascii = String::with_capacity(trigger.len());
+    for c in trigger.chars() {
+        if c.is_ascii() {
+            ascii.push(c);
+        } else {
+            for unit in c.encode_utf16(&mut [0; 2]) {
+                ascii.push_str(&format!("\\u{:04x}", unit));
+            }
+        }
+    }
+    HeaderValue::from_str(&ascii).ok()
+}
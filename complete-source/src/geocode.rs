@@ -0,0 +1,108 @@
+//! Optional geocoding for project locations and Map block markers.
+//!
+//! `GEOCODER` picks the provider: `nominatim` uses the public Nominatim API
+//! (or `GEOCODER_URL`, e.g. a self-hosted instance). Anything else leaves
+//! geocoding off and coordinates have to be entered by hand. The stub
+//! provider answers from a fixed table and is meant for tests.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::models::GeoPoint;
+
+#[derive(Clone)]
+pub enum Geocoder {
+    Disabled,
+    Nominatim(Nominatim),
+    Stub(Arc<HashMap<String, GeoPoint>>),
+}
+
+#[derive(Clone)]
+pub struct Nominatim {
+    base_url: String,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct NominatimPlace {
+    lat: String,
+    lon: String,
+}
+
+impl Nominatim {
+    pub const DEFAULT_URL: &'static str = "https://nominatim.openstreetmap.org";
+
+    pub fn new(base_url: &str) -> Self {
+        // Nominatim's usage policy requires an identifying User-Agent.
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .user_agent(concat!("artist-portfolio/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .expect("Failed to build HTTP client");
+        Self { base_url: base_url.trim_end_matches('/').to_string(), client }
+    }
+
+    async fn search(&self, query: &str) -> Result<Option<GeoPoint>, String> {
+        let resp = self
+            .client
+            .get(format!("{}/search", self.base_url))
+            .query(&[("q", query), ("format", "jsonv2"), ("limit", "1")])
+            .send()
+            .await
+            .map_err(|e| format!("Geocoder unreachable: {}", e))?;
+        if !resp.status().is_success() {
+            return Err(format!("Geocoder returned {}", resp.status()));
+        }
+
+        let places: Vec<NominatimPlace> = resp.json().await.map_err(|e| e.to_string())?;
+        let Some(place) = places.into_iter().next() else {
+            return Ok(None);
+        };
+        match (place.lat.parse(), place.lon.parse()) {
+            (Ok(lat), Ok(lng)) => Ok(Some(GeoPoint { lat, lng })),
+            _ => Err(format!("Geocoder returned bad coordinates: {}, {}", place.lat, place.lon)),
+        }
+    }
+}
+
+impl Geocoder {
+    pub fn from_env() -> Self {
+        match std::env::var("GEOCODER").unwrap_or_default().trim().to_lowercase().as_str() {
+            "nominatim" => {
+                let url = std::env::var("GEOCODER_URL").unwrap_or_else(|_| Nominatim::DEFAULT_URL.to_string());
+                Geocoder::Nominatim(Nominatim::new(&url))
+            }
+            _ => Geocoder::Disabled,
+        }
+    }
+
+    /// A geocoder that only knows the given places. Lookups ignore case and
+    /// surrounding whitespace.
+    pub fn stub(places: &[(&str, GeoPoint)]) -> Self {
+        let table = places
+            .iter()
+            .map(|(name, point)| (name.trim().to_lowercase(), *point))
+            .collect();
+        Geocoder::Stub(Arc::new(table))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !matches!(self, Geocoder::Disabled)
+    }
+
+    /// Coordinates for a free-form place or address. `Ok(None)` when the
+    /// place is unknown or geocoding is off.
+    pub async fn geocode(&self, query: &str) -> Result<Option<GeoPoint>, String> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Ok(None);
+        }
+        match self {
+            Geocoder::Disabled => Ok(None),
+            Geocoder::Nominatim(nominatim) => nominatim.search(query).await,
+            Geocoder::Stub(table) => Ok(table.get(&query.to_lowercase()).copied()),
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! schema.org structured data, embedded in the public pages as JSON-LD so
+//! search engines can tell the artist, their works and their shows apart.
+//!
+//! Every page describes the artist as a `Person`, set up under
+//! **Admin → Settings**. A project page adds the work (a `VisualArtwork` when
+//! it has images, a `CreativeWork` otherwise), an `ExhibitionEvent` when the
+//! project has a place, and a `BreadcrumbList` back to the home page.
+
+use serde_json::{json, Value};
+
+use crate::models::{ContentBlock, Page, Project};
+use crate::settings::SiteSettings;
+use crate::site::Site;
+
+/// Who made the work.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Artist {
+    pub name: String,
+    /// Profiles elsewhere, e.g. on Instagram, for `sameAs`.
+    pub links: Vec<String>,
+}
+
+impl Artist {
+    /// From the site settings, named after the site until a name is set.
+    pub fn new(site: &Site, settings: &SiteSettings) -> Artist {
+        let name = settings.artist_name.trim();
+        Artist {
+            name: if name.is_empty() { site.title.clone() } else { name.to_string() },
+            links: settings.artist_links().map(str::to_string).collect(),
+        }
+    }
+}
+
+fn artist_id(site: &Site) -> String {
+    format!("{}#artist", site.absolute("/"))
+}
+
+fn project_url(site: &Site, project: &Project) -> String {
+    site.absolute(&format!("/project/{}", project.slug))
+}
+
+pub fn person(site: &Site, artist: &Artist) -> Value {
+    let mut person = json!({
+        "@type": "Person",
+        "@id": artist_id(site),
+        "name": artist.name,
+        "url": site.absolute("/"),
+    });
+    if !artist.links.is_empty() {
+        person["sameAs"] = json!(artist.links);
+    }
+    person
+}
+
+/// Each crumb's name and path, from the home page down to this page.
+pub fn breadcrumbs(site: &Site, trail: &[(&str, &str)]) -> Value {
+    let items: Vec<Value> = std::iter::once(("Home", "/"))
+        .chain(trail.iter().copied())
+        .enumerate()
+        .map(|(i, (name, path))| {
+            json!({
+                "@type": "ListItem",
+                "position": i + 1,
+                "name": name,
+                "item": site.absolute(path),
+            })
+        })
+        .collect();
+    json!({ "@type": "BreadcrumbList", "itemListElement": items })
+}
+
+/// The project's social image first, then its gallery images, as absolute URLs.
+fn images(site: &Site, project: &Project, blocks: &[ContentBlock]) -> Vec<String> {
+    let images = project.social_image().into_iter().chain(blocks.iter().flat_map(|b| b.content.0.gallery_images()));
+    let mut urls: Vec<String> = Vec::new();
+    for url in images.map(|url| site.absolute(url)) {
+        if !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+    urls
+}
+
+/// The project as a work of the artist.
+pub fn work(site: &Site, project: &Project, blocks: &[ContentBlock]) -> Value {
+    let images = images(site, project, blocks);
+    let url = project_url(site, project);
+    let mut work = json!({
+        "@type": if images.is_empty() { "CreativeWork" } else { "VisualArtwork" },
+        "@id": format!("{}#work", url),
+        "name": project.title,
+        "url": url,
+        "dateCreated": project.start_date.to_string(),
+        "creator": { "@id": artist_id(site) },
+    });
+    if let Some(description) = project.description.as_deref().filter(|d| !d.trim().is_empty()) {
+        work["description"] = json!(description);
+    }
+    if !images.is_empty() {
+        work["image"] = json!(images);
+    }
+    work
+}
+
+/// The project as a show, when it has a place to have been shown at.
+pub fn exhibition(site: &Site, project: &Project) -> Option<Value> {
+    let name = project.location_name.as_deref().map(str::trim).filter(|n| !n.is_empty());
+    let geo = project.location();
+    if name.is_none() && geo.is_none() {
+        return None;
+    }
+
+    let mut place = json!({ "@type": "Place" });
+    if let Some(name) = name {
+        place["name"] = json!(name);
+    }
+    if let Some(geo) = geo {
+        place["geo"] = json!({ "@type": "GeoCoordinates", "latitude": geo.lat, "longitude": geo.lng });
+    }
+
+    let url = project_url(site, project);
+    let mut event = json!({
+        "@type": "ExhibitionEvent",
+        "@id": format!("{}#exhibition", url),
+        "name": project.title,
+        "url": url,
+        "startDate": project.start_date.to_string(),
+        "eventAttendanceMode": "https://schema.org/OfflineEventAttendanceMode",
+        "location": place,
+        "performer": { "@id": artist_id(site) },
+        "workFeatured": { "@id": format!("{}#work", url) },
+    });
+    if let Some(end) = project.end_date {
+        event["endDate"] = json!(end.to_string());
+    }
+    if let Some(image) = project.social_image() {
+        event["image"] = json!(site.absolute(image));
+    }
+    Some(event)
+}
+
+fn graph(nodes: Vec<Value>) -> Value {
+    json!({ "@context": "https://schema.org", "@graph": nodes })
+}
+
+/// The home page: who the artist is.
+pub fn home_page(site: &Site, artist: &Artist) -> Value {
+    graph(vec![person(site, artist)])
+}
+
+/// The about or contact page.
+pub fn page(site: &Site, artist: &Artist, page: &Page) -> Value {
+    graph(vec![person(site, artist), breadcrumbs(site, &[(&page.title, &format!("/{}", page.slug))])])
+}
+
+pub fn project_page(site: &Site, artist: &Artist, project: &Project, blocks: &[ContentBlock]) -> Value {
+    let mut nodes = vec![person(site, artist), work(site, project, blocks)];
+    nodes.extend(exhibition(site, project));
+    nodes.push(breadcrumbs(site, &[(&project.title, &format!("/project/{}", project.slug))]));
+    graph(nodes)
+}
+
+/// `data` for a `<script type="application/ld+json">` element. Markup
+/// characters are escaped, so text can't close the element early.
+pub fn script(data: &Value) -> String {
+    data.to_string().replace('<', "\\u003c").replace('>', "\\u003e").replace('&', "\\u0026")
+}
@@ -1,11 +1,79 @@
+pub mod block_schema;
+pub mod media;
 pub mod models;
+pub mod oembed;
+pub mod redirects;
 pub mod routes;
+pub mod sanitize;
+pub mod settings;
+pub mod slug;
 pub mod templates;
+pub mod thumbnail;
 pub mod upload;
+pub mod validation;
+pub mod geocode;
+pub mod maps;
+pub mod markdown;
+pub mod waveform;
 pub mod csrf;
+pub mod error;
+pub mod flash;
+pub mod search;
+pub mod search_index;
+pub mod site;
+pub mod feed;
+pub mod sitemap;
+pub mod robots;
+pub mod json_ld;
 
+#[cfg(test)]
+mod test_fixtures;
 #[cfg(test)]
 mod test_json;
+#[cfg(test)]
+mod test_block_schema;
+#[cfg(test)]
+mod test_oembed;
+#[cfg(test)]
+mod test_waveform;
+#[cfg(test)]
+mod test_media;
+#[cfg(test)]
+mod test_blocks;
+#[cfg(test)]
+mod test_geo;
+#[cfg(test)]
+mod test_sanitize;
+#[cfg(test)]
+mod test_markdown;
+#[cfg(test)]
+mod test_duplicate;
+#[cfg(test)]
+mod test_reorder;
+#[cfg(test)]
+mod test_settings;
+#[cfg(test)]
+mod test_thumbnail;
+#[cfg(test)]
+mod test_slug;
+#[cfg(test)]
+mod test_redirects;
+#[cfg(test)]
+mod test_errors;
+#[cfg(test)]
+mod test_validation;
+#[cfg(test)]
+mod test_flash;
+#[cfg(test)]
+mod test_search;
+#[cfg(test)]
+mod test_search_index;
+#[cfg(test)]
+mod test_feed;
+#[cfg(test)]
+mod test_sitemap;
+#[cfg(test)]
+mod test_json_ld;
 
 use axum::{
     routing::{get, post},
@@ -20,15 +88,23 @@ use sqlx::PgPool;
 use tower_sessions::{Expiry, Session, SessionManagerLayer};
 use tower_sessions_sqlx_store::PostgresStore;
 use tower_http::set_header::SetResponseHeaderLayer;
-use upload::CloudinaryConfig;
+use tower_http::services::ServeDir;
+use upload::{LocalStorage, Storage};
+use oembed::OEmbedRegistry;
+use geocode::Geocoder;
+use maps::TileConfig;
 use include_dir::{include_dir, Dir};
 
 static STATIC_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/static");
 
+const MAX_UPLOAD_BYTES: usize = 1024 * 1024 * 1024;
+
 #[derive(Clone)]
 pub struct AppState {
     pub pool: PgPool,
-    pub cloudinary: CloudinaryConfig,
+    pub storage: Storage,
+    pub oembed: OEmbedRegistry,
+    pub geocoder: Geocoder,
 }
 
 impl axum::extract::FromRef<AppState> for PgPool {
@@ -37,9 +113,21 @@ impl axum::extract::FromRef<AppState> for PgPool {
     }
 }
 
-impl axum::extract::FromRef<AppState> for CloudinaryConfig {
+impl axum::extract::FromRef<AppState> for Storage {
+    fn from_ref(state: &AppState) -> Self {
+        state.storage.clone()
+    }
+}
+
+impl axum::extract::FromRef<AppState> for OEmbedRegistry {
+    fn from_ref(state: &AppState) -> Self {
+        state.oembed.clone()
+    }
+}
+
+impl axum::extract::FromRef<AppState> for Geocoder {
     fn from_ref(state: &AppState) -> Self {
-        state.cloudinary.clone()
+        state.geocoder.clone()
     }
 }
 
@@ -53,18 +141,42 @@ pub async fn create_router(state: AppState, is_production: bool) -> Router {
         .with_secure(is_production)
         .with_expiry(Expiry::OnInactivity(time::Duration::hours(24)));
 
+    // Embeds can only frame origins of the oEmbed providers that are enabled.
+    let csp = format!(
+        "default-src 'self'; script-src 'self' 'unsafe-inline' 'unsafe-eval' https://cdn.jsdelivr.net https://cdn.quilljs.com https://*.mux.com; style-src 'self' 'unsafe-inline' https://fonts.googleapis.com https://cdn.quilljs.com; img-src 'self' data: https: blob:; font-src 'self' https://fonts.gstatic.com https://fonts.cdnfonts.com; connect-src 'self' https://*.mux.com; media-src 'self' https://stream.mux.com https://res.cloudinary.com blob:; frame-src {}",
+        state.oembed.frame_src()
+    );
 
 
-    Router::new()
+    let mut router = Router::new()
         .route("/", get(routes::public::index))
         .route("/contact", get(routes::public::contact))
         .route("/about", get(routes::public::about))
+        .route("/map", get(routes::public::map_page))
+        .route("/search", get(routes::public::search))
+        .route(feed::ATOM_PATH, get(routes::public::atom_feed))
+        .route(feed::RSS_PATH, get(routes::public::rss_feed))
+        .route(feed::JSON_PATH, get(routes::public::json_feed))
+        .route(sitemap::PATH, get(routes::public::sitemap))
+        .route("/sitemap/{part}", get(routes::public::sitemap_part))
+        .route(robots::PATH, get(routes::public::robots_txt))
         .route("/project/{slug}", get(routes::public::project_details))
+        .route("/download/{id}", get(routes::public::download_media))
         .route("/admin/login", get(routes::auth::login_page).post(routes::auth::login_handler))
         .route("/admin/logout", get(routes::auth::logout_handler))
         // Protected Admin Routes
         .nest("/admin", admin_routes())
         .route("/static/{*path}", get(static_handler))
+        .fallback(routes::public::fallback);
+
+    if let Some(local) = state.storage.local() {
+        router = router.nest_service(LocalStorage::URL_PREFIX, ServeDir::new(&local.root));
+    }
+    if let Some(dir) = &TileConfig::global().dir {
+        router = router.nest_service(TileConfig::TILE_PREFIX, ServeDir::new(dir));
+    }
+
+    router
         .layer(session_layer)
         // Security Headers
         .layer(SetResponseHeaderLayer::overriding(
@@ -77,7 +189,7 @@ pub async fn create_router(state: AppState, is_production: bool) -> Router {
         ))
         .layer(SetResponseHeaderLayer::overriding(
             header::CONTENT_SECURITY_POLICY,
-            header::HeaderValue::from_static("default-src 'self'; script-src 'self' 'unsafe-inline' 'unsafe-eval' https://cdn.jsdelivr.net https://cdn.quilljs.com https://*.mux.com; style-src 'self' 'unsafe-inline' https://fonts.googleapis.com https://cdn.quilljs.com; img-src 'self' data: https: blob:; font-src 'self' https://fonts.gstatic.com https://fonts.cdnfonts.com; connect-src 'self' https://*.mux.com; media-src 'self' https://stream.mux.com blob:; frame-src 'self' https://www.youtube.com"),
+            header::HeaderValue::from_str(&csp).expect("Invalid Content-Security-Policy"),
         ))
         .with_state(state)
 }
@@ -101,22 +213,34 @@ fn admin_routes() -> Router<AppState> {
         .route("/dashboard", get(routes::admin::dashboard))
         .route("/settings", get(routes::admin::settings))
         .route("/settings/update", post(routes::admin::update_credentials))
+        .route("/settings/site", post(routes::admin::update_site_settings))
         .route("/projects", post(routes::admin::create_project))
         .route("/projects/new", get(routes::admin::new_project))
+        .route("/projects/order", get(routes::admin::project_order))
         .route("/projects/edit/{id}", get(routes::admin::edit_project).post(routes::admin::update_project))
         .route("/projects/delete/{id}", post(routes::admin::delete_project))
+        .route("/projects/duplicate/{id}", post(routes::admin::duplicate_project))
         .route("/projects/{id}/blocks/new", get(routes::admin::new_block))
         .route("/blocks/{id}", get(routes::admin::edit_block).post(routes::admin::update_block))
         .route("/blocks/delete/{id}", post(routes::admin::delete_block))
+        .route("/blocks/duplicate/{id}", post(routes::admin::duplicate_block))
+        .route("/blocks/move/{id}", post(routes::admin::move_block))
         .route("/projects/{id}/blocks", get(routes::admin::project_blocks).post(routes::admin::create_block))
         // Pages
         .route("/pages", get(routes::admin::pages_list))
         .route("/pages/edit/{slug}", get(routes::admin::edit_page))
         .route("/pages/update/{slug}", post(routes::admin::update_page))
+        // Redirects
+        .route("/redirects", get(routes::admin::redirects_list).post(routes::admin::create_redirect))
+        .route("/redirects/delete/{id}", post(routes::admin::delete_redirect))
         // API Routes
-        .route("/api/upload", post(routes::api::upload_handler))
+        // Videos are uploaded through here too, so allow well over axum's 2 MB default.
+        .route("/api/upload", post(routes::api::upload_handler).layer(DefaultBodyLimit::max(MAX_UPLOAD_BYTES)))
         .route("/api/reorder", post(routes::api::reorder_handler))
+        .route("/api/projects/order", post(routes::api::project_order_handler))
+        .route("/api/preview", post(routes::api::preview_handler))
         .route("/deploy", post(routes::admin::trigger_deploy))
+        .route_layer(middleware::from_fn(flash_middleware))
         .route_layer(middleware::from_fn(auth_middleware))
         .route_layer(middleware::from_fn(no_cache_middleware))
 }
@@ -130,6 +254,23 @@ async fn no_cache_middleware(req: Request, next: Next) -> Response {
     response
 }
 
+/// Sends the messages queued while answering an HTMX request along with
+/// the partial, which has no page to show them on. A redirect leaves them
+/// queued for the page it leads to.
+async fn flash_middleware(session: Session, req: Request, next: Next) -> Response {
+    let htmx = req.headers().contains_key("HX-Request");
+    let mut response = next.run(req).await;
+    if htmx && !response.status().is_redirection() {
+        let messages = flash::take(&session).await;
+        if !messages.is_empty() {
+            if let Some(trigger) = flash::hx_trigger(&messages) {
+                response.headers_mut().insert("HX-Trigger", trigger);
+            }
+        }
+    }
+    response
+}
+
 async fn auth_middleware(session: Session, req: Request, next: Next) -> Response {
     if session.get::<uuid::Uuid>("user_id").await.unwrap_or(None).is_some() {
         next.run(req).await
@@ -1,5 +1,7 @@
 use artist_portfolio::{create_router, AppState};
-use artist_portfolio::upload::CloudinaryConfig;
+use artist_portfolio::upload::{CloudinaryConfig, LocalStorage, Storage};
+use artist_portfolio::oembed::OEmbedRegistry;
+use artist_portfolio::geocode::Geocoder;
 use dotenvy::dotenv;
 use sqlx::postgres::PgPoolOptions;
 use std::env;
@@ -24,15 +26,23 @@ async fn main() {
         .await
         .expect("Failed to run migrations");
 
-    let cloudinary_config = CloudinaryConfig::new(
-        env::var("CLOUDINARY_CLOUD_NAME").expect("CLOUDINARY_CLOUD_NAME must be set"),
-        env::var("CLOUDINARY_API_KEY").expect("CLOUDINARY_API_KEY must be set"),
-        env::var("CLOUDINARY_API_SECRET").expect("CLOUDINARY_API_SECRET must be set"),
-    );
+    let storage = if env::var("STORAGE_BACKEND").as_deref() == Ok("local") {
+        let root = env::var("MEDIA_ROOT").unwrap_or_else(|_| "media".to_string());
+        std::fs::create_dir_all(&root).expect("Failed to create MEDIA_ROOT");
+        Storage::Local(LocalStorage::new(root))
+    } else {
+        Storage::Cloudinary(CloudinaryConfig::new(
+            env::var("CLOUDINARY_CLOUD_NAME").expect("CLOUDINARY_CLOUD_NAME must be set"),
+            env::var("CLOUDINARY_API_KEY").expect("CLOUDINARY_API_KEY must be set"),
+            env::var("CLOUDINARY_API_SECRET").expect("CLOUDINARY_API_SECRET must be set"),
+        ))
+    };
 
     let state = AppState {
         pool,
-        cloudinary: cloudinary_config,
+        storage,
+        oembed: OEmbedRegistry::from_env(),
+        geocoder: Geocoder::from_env(),
     };
 
     let is_production = env::var("APP_ENVIRONMENT").unwrap_or_else(|_| "development".to_string()) == "production";
@@ -0,0 +1,50 @@
+//! Tile layer settings for the maps drawn by `static/js/map.js`.
+//!
+//! Tiles come from `MAP_TILE_URL` (an `{z}/{x}/{y}` template). When
+//! `MAP_TILE_DIR` is set, that directory is served under `/tiles` and used
+//! by default, so a site can serve its own pre-rendered tiles.
+
+use std::sync::OnceLock;
+
+use crate::models::MapMarker;
+
+#[derive(Debug, Clone)]
+pub struct TileConfig {
+    pub url: String,
+    pub attribution: String,
+    pub max_zoom: u8,
+    /// Local directory served under `TILE_PREFIX`.
+    pub dir: Option<String>,
+}
+
+impl TileConfig {
+    pub const TILE_PREFIX: &'static str = "/tiles";
+    const OSM_URL: &'static str = "https://tile.openstreetmap.org/{z}/{x}/{y}.png";
+    const OSM_ATTRIBUTION: &'static str = "© OpenStreetMap contributors";
+
+    pub fn from_env() -> Self {
+        let dir = std::env::var("MAP_TILE_DIR").ok().filter(|d| !d.is_empty());
+        let default_url = match dir {
+            Some(_) => format!("{}/{{z}}/{{x}}/{{y}}.png", Self::TILE_PREFIX),
+            None => Self::OSM_URL.to_string(),
+        };
+        Self {
+            url: std::env::var("MAP_TILE_URL").unwrap_or(default_url),
+            attribution: std::env::var("MAP_ATTRIBUTION").unwrap_or_else(|_| Self::OSM_ATTRIBUTION.to_string()),
+            max_zoom: std::env::var("MAP_MAX_ZOOM").ok().and_then(|z| z.parse().ok()).unwrap_or(18),
+            dir,
+        }
+    }
+
+    /// Read once from the environment; templates render maps without access
+    /// to the app state.
+    pub fn global() -> &'static TileConfig {
+        static CONFIG: OnceLock<TileConfig> = OnceLock::new();
+        CONFIG.get_or_init(TileConfig::from_env)
+    }
+}
+
+/// Markers as JSON for the map's `data-markers` attribute.
+pub fn markers_json(markers: &[MapMarker]) -> String {
+    serde_json::to_string(markers).unwrap_or_else(|_| "[]".to_string())
+}
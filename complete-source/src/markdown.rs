@@ -0,0 +1,74 @@
+//! Markdown source for Text blocks and pages: CommonMark plus tables,
+//! footnotes and strikethrough.
+//!
+//! Footnotes are numbered in order of first appearance and rendered with
+//! `fn-N`/`fnref-N` ids rather than the author's labels, so the sanitiser
+//! only has to allow those ids.
+
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag, TagEnd};
+use std::collections::{HashMap, HashSet};
+
+fn options() -> Options {
+    Options::ENABLE_TABLES | Options::ENABLE_FOOTNOTES | Options::ENABLE_STRIKETHROUGH
+}
+
+/// Renders Markdown to HTML. Raw HTML in the source passes through, so the
+/// result still has to be sanitised before it's shown.
+pub fn to_html(source: &str) -> String {
+    let mut numbers: HashMap<String, usize> = HashMap::new();
+    let mut number = |label: &str| {
+        let next = numbers.len() + 1;
+        *numbers.entry(label.to_string()).or_insert(next)
+    };
+    let mut referenced = HashSet::new();
+
+    let events = Parser::new_ext(source, options()).map(|event| match event {
+        Event::FootnoteReference(label) => {
+            let n = number(&label);
+            // Only the first reference is the back link's target.
+            let id = if referenced.insert(n) { format!(r#" id="fnref-{n}""#) } else { String::new() };
+            Event::InlineHtml(CowStr::from(format!(
+                r##"<sup class="footnote-ref"><a href="#fn-{n}"{id}>{n}</a></sup>"##
+            )))
+        }
+        Event::Start(Tag::FootnoteDefinition(label)) => {
+            let n = number(&label);
+            Event::Html(CowStr::from(format!(
+                r##"<div class="footnote" id="fn-{n}"><a class="footnote-backref" href="#fnref-{n}">{n}.</a>"##
+            )))
+        }
+        Event::End(TagEnd::FootnoteDefinition) => Event::Html(CowStr::Borrowed("</div>\n")),
+        other => other,
+    });
+
+    let mut out = String::with_capacity(source.len() * 3 / 2);
+    html::push_html(&mut out, events);
+    out
+}
+
+/// The text of a Markdown document without any markup, e.g. for previews.
+pub fn to_plain_text(source: &str) -> String {
+    let mut text = String::new();
+    for event in Parser::new_ext(source, options()) {
+        match event {
+            Event::Text(t) | Event::Code(t) => text.push_str(&t),
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            // Keep words in neighbouring blocks and cells apart.
+            Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Heading(_)
+                | TagEnd::Item
+                | TagEnd::BlockQuote(_)
+                | TagEnd::CodeBlock
+                | TagEnd::TableCell
+                | TagEnd::FootnoteDefinition,
+            ) => text.push(' '),
+            _ => {}
+        }
+    }
+    collapse_whitespace(&text)
+}
+
+pub fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,423 @@
+//! The media library: every upload is recorded in the `media` table along
+//! with whatever could be learned about it when it was uploaded.
+//!
+//! Video processing shells out to `ffprobe`/`ffmpeg`, and PDF thumbnails
+//! of local files to `pdftoppm`, when they are on the PATH. Without them
+//! uploads still work, just without the derived data. Transcoding a video
+//! takes minutes, so the poster and HLS rendition are made after the upload
+//! has been answered; until then players show the original file.
+
+use axum::body::Bytes;
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use uuid::Uuid;
+
+use crate::models::{BlockContent, ContentBlock, MediaItem, MediaMetadata};
+use crate::upload::{LocalStorage, Storage, StoredFile};
+use crate::waveform;
+
+/// Store an upload, derive what we can from it and record it in the library.
+pub async fn ingest(
+    pool: &PgPool,
+    storage: &Storage,
+    file_bytes: Vec<u8>,
+    original_name: &str,
+    mime_type: &str,
+) -> Result<MediaItem, String> {
+    let id = Uuid::new_v4();
+    let byte_size = file_bytes.len() as i64;
+
+    // Files are inspected here rather than after storing so it works the
+    // same for both backends without downloading them back from Cloudinary.
+    let (file_bytes, audio) = if mime_type.starts_with("audio/") {
+        let extension = Path::new(original_name)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase());
+        on_blocking_thread(file_bytes, original_name, move |bytes| {
+            waveform::analyze(bytes, extension.as_deref())
+        })
+        .await
+    } else {
+        (file_bytes, None)
+    };
+    let (file_bytes, page_count) = if mime_type == "application/pdf" {
+        on_blocking_thread(file_bytes, original_name, |bytes| pdf_page_count(&bytes)).await
+    } else {
+        (file_bytes, None)
+    };
+
+    let stored = storage.store(id, file_bytes, original_name).await?;
+
+    let mut metadata = MediaMetadata {
+        duration: stored.duration,
+        width: stored.width,
+        height: stored.height,
+        ..Default::default()
+    };
+
+    if let Some(audio) = audio {
+        metadata.duration = audio.duration.or(metadata.duration);
+        metadata.artist = audio.artist;
+        metadata.peaks = Some(audio.peaks).filter(|p| !p.is_empty());
+    }
+
+    if mime_type == "application/pdf" {
+        metadata.page_count = page_count;
+        metadata.thumbnail_url = pdf_thumbnail(storage, &stored).await;
+    }
+
+    if mime_type.starts_with("video/") {
+        describe_video(storage, &stored, &mut metadata).await;
+    }
+
+    let local_path = stored.path.as_ref().map(|p| p.to_string_lossy().into_owned());
+    let item = sqlx::query_as::<_, MediaItem>(
+        "INSERT INTO media (id, url, original_name, mime_type, byte_size, local_path, metadata) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING *"
+    )
+    .bind(id)
+    .bind(&stored.url)
+    .bind(original_name)
+    .bind(mime_type)
+    .bind(byte_size)
+    .bind(local_path)
+    .bind(sqlx::types::Json(metadata))
+    .fetch_one(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if let (true, Storage::Local(local), Some(path)) = (mime_type.starts_with("video/"), storage, stored.path) {
+        tokio::spawn(render_video(pool.clone(), local.clone(), id, path, item.metadata.duration));
+    }
+    Ok(item)
+}
+
+pub async fn find(pool: &PgPool, id: Uuid) -> Option<MediaItem> {
+    sqlx::query_as::<_, MediaItem>("SELECT * FROM media WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None)
+}
+
+/// The item, if a project offers it for download: a File block, or one
+/// inside a Columns block, lists it.
+pub async fn find_downloadable(pool: &PgPool, id: Uuid) -> Option<MediaItem> {
+    sqlx::query_as::<_, MediaItem>(
+        "SELECT * FROM media WHERE id = $1 AND EXISTS (
+             SELECT 1 FROM content_blocks
+             WHERE jsonb_path_exists(content, '$.** ? (@.type == \"File\").data[*].media_id ? (@ == $id)',
+                                     jsonb_build_object('id', $1::text))
+         )",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None)
+}
+
+pub async fn find_by_url(pool: &PgPool, url: &str) -> Option<MediaItem> {
+    sqlx::query_as::<_, MediaItem>("SELECT * FROM media WHERE url = $1 ORDER BY created_at DESC LIMIT 1")
+        .bind(url)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None)
+}
+
+pub async fn list_by_type(pool: &PgPool, mime_prefix: &str) -> Vec<MediaItem> {
+    sqlx::query_as::<_, MediaItem>(
+        "SELECT * FROM media WHERE mime_type LIKE $1 ORDER BY created_at DESC"
+    )
+    .bind(format!("{}%", mime_prefix))
+    .fetch_all(pool)
+    .await
+    .unwrap_or_default()
+}
+
+/// Run CPU-bound work over an upload on a blocking thread and hand the bytes
+/// back afterwards. Failures are logged; the upload itself still goes ahead.
+async fn on_blocking_thread<T, F>(file_bytes: Vec<u8>, original_name: &str, work: F) -> (Vec<u8>, Option<T>)
+where
+    T: Send + 'static,
+    F: FnOnce(Bytes) -> Result<T, String> + Send + 'static,
+{
+    // `Bytes` clones are cheap, and converting back is free once the worker
+    // has dropped its handle.
+    let shared = Bytes::from(file_bytes);
+    let result = {
+        let shared = shared.clone();
+        tokio::task::spawn_blocking(move || work(shared)).await
+    };
+
+    let output = match result {
+        Ok(Ok(output)) => Some(output),
+        Ok(Err(e)) => {
+            tracing::warn!("Could not analyse {:?}: {}", original_name, e);
+            None
+        }
+        Err(e) => {
+            tracing::warn!("Analysis of {:?} panicked: {}", original_name, e);
+            None
+        }
+    };
+    (Vec::from(shared), output)
+}
+
+/// An `attachment` disposition with an ASCII fallback name and the exact
+/// name in RFC 5987 encoding.
+pub fn content_disposition(filename: &str) -> String {
+    let fallback: String = filename
+        .chars()
+        .map(|c| if (c.is_ascii_graphic() && c != '"' && c != '\\') || c == ' ' { c } else { '_' })
+        .collect();
+    let encoded: String = filename
+        .bytes()
+        .map(|b| {
+            if b.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&b) {
+                (b as char).to_string()
+            } else {
+                format!("%{:02X}", b)
+            }
+        })
+        .collect();
+    format!("attachment; filename=\"{}\"; filename*=UTF-8''{}", fallback, encoded)
+}
+
+pub fn pdf_page_count(bytes: &[u8]) -> Result<u32, String> {
+    let document = lopdf::Document::load_mem(bytes).map_err(|e| e.to_string())?;
+    Ok(document.get_pages().len() as u32)
+}
+
+async fn pdf_thumbnail(storage: &Storage, stored: &StoredFile) -> Option<String> {
+    match (storage, &stored.path) {
+        (Storage::Local(local), Some(path)) => {
+            let prefix = path.parent()?.join("thumbnail");
+            match render_pdf_page(path, &prefix).await {
+                Ok(image) => local.url_for(&image),
+                Err(e) => {
+                    tracing::warn!("PDF thumbnail for {:?} failed: {}", path, e);
+                    None
+                }
+            }
+        }
+        (Storage::Cloudinary(_), _) => cloudinary_pdf_thumbnail_url(&stored.url),
+        _ => None,
+    }
+}
+
+/// Cloudinary rasterises a page of a PDF when asked for an image format.
+pub fn cloudinary_pdf_thumbnail_url(url: &str) -> Option<String> {
+    let (before, after) = url.split_once("/image/upload/")?;
+    let base = after.strip_suffix(".pdf").or_else(|| after.strip_suffix(".PDF"))?;
+    Some(format!("{}/image/upload/pg_1,w_400,c_limit/{}.jpg", before, base))
+}
+
+/// Render the first page to `{prefix}.jpg` with poppler's `pdftoppm`.
+pub async fn render_pdf_page(input: &Path, prefix: &Path) -> Result<PathBuf, String> {
+    let output = Command::new("pdftoppm")
+        .args(["-f", "1", "-l", "1", "-singlefile", "-jpeg", "-scale-to", "400"])
+        .arg(input)
+        .arg(prefix)
+        .output()
+        .await
+        .map_err(|e| format!("pdftoppm not available: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(prefix.with_extension("jpg"))
+}
+
+/// What can be learned about a video right away: its duration and size
+/// from a local file, or the poster Cloudinary makes of any video.
+async fn describe_video(storage: &Storage, stored: &StoredFile, metadata: &mut MediaMetadata) {
+    match (storage, &stored.path) {
+        (Storage::Local(_), Some(path)) => {
+            if let Some(probe) = probe(path).await {
+                metadata.duration = metadata.duration.or(probe.duration);
+                metadata.width = metadata.width.or(probe.width);
+                metadata.height = metadata.height.or(probe.height);
+            }
+        }
+        (Storage::Cloudinary(_), _) => {
+            // Cloudinary serves a frame of any video when asked for a .jpg.
+            metadata.poster_url = cloudinary_poster_url(&stored.url);
+        }
+        _ => {}
+    }
+}
+
+/// Makes the poster frame and HLS rendition of a stored video, then records
+/// them on the media item and the HostedVideo blocks already showing it.
+async fn render_video(pool: PgPool, local: LocalStorage, id: Uuid, path: PathBuf, duration: Option<f64>) {
+    let dir = path.parent().unwrap_or(&local.root).to_path_buf();
+    let mut rendered = MediaMetadata::default();
+    let poster = dir.join("poster.jpg");
+    match extract_poster(&path, &poster, duration).await {
+        Ok(()) => rendered.poster_url = local.url_for(&poster),
+        Err(e) => tracing::warn!("Poster frame for {:?} failed: {}", path, e),
+    }
+    match generate_hls(&path, &dir.join("hls")).await {
+        Ok(playlist) => rendered.hls_url = local.url_for(&playlist),
+        Err(e) => tracing::warn!("HLS rendition for {:?} failed: {}", path, e),
+    }
+    if rendered.poster_url.is_none() && rendered.hls_url.is_none() {
+        return;
+    }
+    if let Err(e) = save_renditions(&pool, id, &rendered).await {
+        tracing::error!("Saving the renditions of media {} failed: {}", id, e);
+    }
+}
+
+async fn save_renditions(pool: &PgPool, id: Uuid, rendered: &MediaMetadata) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    // Only the fields that are set are serialized, so the rest are kept.
+    sqlx::query("UPDATE media SET metadata = metadata || $2 WHERE id = $1")
+        .bind(id)
+        .bind(sqlx::types::Json(rendered))
+        .execute(&mut *tx)
+        .await?;
+
+    let blocks = sqlx::query_as::<_, ContentBlock>("SELECT * FROM content_blocks WHERE content::text LIKE '%' || $1 || '%'")
+        .bind(id.to_string())
+        .fetch_all(&mut *tx)
+        .await?;
+    for mut block in blocks {
+        if with_renditions(&mut block.content.0, id, rendered) {
+            sqlx::query("UPDATE content_blocks SET content = $1 WHERE id = $2")
+                .bind(&block.content)
+                .bind(block.id)
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+    tx.commit().await
+}
+
+/// Copies the poster and HLS URLs into the HostedVideo blocks in `content`
+/// that show media `id`, Columns included. Whether anything changed.
+pub fn with_renditions(content: &mut BlockContent, id: Uuid, rendered: &MediaMetadata) -> bool {
+    match content {
+        BlockContent::HostedVideo(video) if video.media_id == id => {
+            video.poster_url = rendered.poster_url.clone().or(video.poster_url.take());
+            video.hls_url = rendered.hls_url.clone().or(video.hls_url.take());
+            true
+        }
+        BlockContent::Columns(columns) => {
+            // Every child is visited; the video may be in more than one.
+            let mut changed = false;
+            for child in columns.columns.iter_mut().flatten() {
+                changed |= with_renditions(child, id, rendered);
+            }
+            changed
+        }
+        _ => false,
+    }
+}
+
+pub fn cloudinary_poster_url(url: &str) -> Option<String> {
+    let (base, ext) = url.rsplit_once('.')?;
+    if ext.contains('/') || !url.contains("/video/upload/") {
+        return None;
+    }
+    Some(format!("{}.jpg", base))
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Probe {
+    pub duration: Option<f64>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct FfprobeOutput {
+    format: Option<FfprobeFormat>,
+    #[serde(default)]
+    streams: Vec<FfprobeStream>,
+}
+
+#[derive(Deserialize)]
+struct FfprobeFormat {
+    duration: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FfprobeStream {
+    codec_type: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+}
+
+/// Duration and dimensions of a media file, via ffprobe.
+pub async fn probe(path: &Path) -> Option<Probe> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-print_format", "json", "-show_format", "-show_streams"])
+        .arg(path)
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    parse_probe(&output.stdout)
+}
+
+/// Reads ffprobe's `-print_format json` output.
+pub fn parse_probe(json: &[u8]) -> Option<Probe> {
+    let parsed: FfprobeOutput = serde_json::from_slice(json).ok()?;
+    let video = parsed.streams.iter().find(|s| s.codec_type.as_deref() == Some("video"));
+    Some(Probe {
+        duration: parsed.format.and_then(|f| f.duration).and_then(|d| d.parse().ok()),
+        width: video.and_then(|s| s.width),
+        height: video.and_then(|s| s.height),
+    })
+}
+
+pub(crate) async fn run_ffmpeg(args: &[&std::ffi::OsStr]) -> Result<(), String> {
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", "-loglevel", "error", "-y"])
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| format!("ffmpeg not available: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Grab a frame a little way in, so the poster isn't a black fade-in.
+pub async fn extract_poster(input: &Path, output: &Path, duration: Option<f64>) -> Result<(), String> {
+    let at = duration.map(|d| (d * 0.1).min(3.0)).unwrap_or(0.0).to_string();
+    run_ffmpeg(&[
+        "-ss".as_ref(), at.as_ref(),
+        "-i".as_ref(), input.as_os_str(),
+        "-frames:v".as_ref(), "1".as_ref(),
+        "-q:v".as_ref(), "3".as_ref(),
+        output.as_os_str(),
+    ]).await
+}
+
+/// Write a single-rendition VOD HLS playlist into `out_dir` and return the
+/// playlist path.
+pub async fn generate_hls(input: &Path, out_dir: &Path) -> Result<PathBuf, String> {
+    tokio::fs::create_dir_all(out_dir).await.map_err(|e| e.to_string())?;
+    let playlist = out_dir.join("index.m3u8");
+    let segments = out_dir.join("segment_%03d.ts");
+    run_ffmpeg(&[
+        "-i".as_ref(), input.as_os_str(),
+        "-c:v".as_ref(), "libx264".as_ref(),
+        "-preset".as_ref(), "veryfast".as_ref(),
+        "-crf".as_ref(), "23".as_ref(),
+        "-c:a".as_ref(), "aac".as_ref(),
+        "-b:a".as_ref(), "128k".as_ref(),
+        "-hls_time".as_ref(), "6".as_ref(),
+        "-hls_playlist_type".as_ref(), "vod".as_ref(),
+        "-hls_segment_filename".as_ref(), segments.as_os_str(),
+        playlist.as_os_str(),
+    ]).await?;
+    Ok(playlist)
+}
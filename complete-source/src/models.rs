@@ -6,10 +6,13 @@ use argon2::{
     Argon2
 };
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use sqlx::postgres::PgRow;
+use sqlx::{FromRow, Row};
 use time::Date;
 use uuid::Uuid;
 
+use crate::thumbnail::FocalPoint;
+
 #[derive(Debug, FromRow, Serialize, Deserialize)]
 pub struct User {
     pub id: Uuid,
@@ -21,8 +24,11 @@ pub struct User {
 pub struct Page {
     pub slug: String,
     pub title: String,
+    /// Rendered, sanitised HTML of `source`.
     pub content: String,
     pub updated_at: time::OffsetDateTime,
+    pub format: TextFormat,
+    pub source: String,
 }
 
 pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
@@ -50,32 +56,619 @@ pub struct Project {
     pub start_date: Date,
     pub end_date: Option<Date>,
     pub thumbnail_url: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub location_name: Option<String>,
+    pub featured: bool,
+    pub position: Option<i32>, // Manual index order, see `settings::IndexOrder`
+    pub thumbnail_focus_x: f64,
+    pub thumbnail_focus_y: f64,
+    pub social_image_url: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+impl Project {
+    pub fn location(&self) -> Option<GeoPoint> {
+        Some(GeoPoint { lat: self.latitude?, lng: self.longitude? })
+    }
+
+    pub fn thumbnail_focus(&self) -> FocalPoint {
+        FocalPoint::new(self.thumbnail_focus_x, self.thumbnail_focus_y)
+    }
+
+    /// The image for social cards and feeds: the cropped card when one
+    /// could be made, otherwise the thumbnail as uploaded.
+    pub fn social_image(&self) -> Option<&str> {
+        self.social_image_url.as_deref().or(self.thumbnail_url.as_deref())
+    }
+
+    /// The first of `slug-copy`, `slug-copy-2`, ... not in `taken`. Copies of
+    /// copies count up from the original slug instead of growing a suffix.
+    pub fn copy_slug(&self, taken: &[String]) -> String {
+        let base = match self.slug.rsplit_once("-copy") {
+            Some((base, n)) if n.is_empty() || n.strip_prefix('-').is_some_and(|n| n.parse::<u32>().is_ok()) => base,
+            _ => self.slug.as_str(),
+        };
+        let mut slug = format!("{}-copy", base);
+        let mut n = 1;
+        while taken.contains(&slug) {
+            n += 1;
+            slug = format!("{}-copy-{}", base, n);
+        }
+        slug
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GeoPoint {
+    pub lat: f64,
+    pub lng: f64,
+}
+
+impl GeoPoint {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(-90.0..=90.0).contains(&self.lat) || !(-180.0..=180.0).contains(&self.lng) {
+            return Err(format!("Coordinates out of range: {}, {}", self.lat, self.lng));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ContentBlock {
     pub id: Uuid,
     pub project_id: Uuid,
-    pub block_type: String, // text, gallery, video, audio, file
+    pub block_type: BlockKind, // Always matches `content.kind()`
     pub content: sqlx::types::Json<BlockContent>,
     pub sort_order: i32,
 }
 
+// Content is stored with a schema version, so it is upgraded to the current
+// shape before being deserialized rather than decoded straight into `Json<_>`.
+impl<'r> FromRow<'r, PgRow> for ContentBlock {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        let schema_version: i32 = row.try_get("schema_version")?;
+        let raw: serde_json::Value = row.try_get("content")?;
+        let content = crate::block_schema::decode(schema_version, raw)
+            .map_err(|e| sqlx::Error::ColumnDecode {
+                index: "content".to_string(),
+                source: e.into(),
+            })?;
+
+        Ok(ContentBlock {
+            id: row.try_get("id")?,
+            project_id: row.try_get("project_id")?,
+            block_type: row.try_get("block_type")?,
+            content: sqlx::types::Json(content),
+            sort_order: row.try_get("sort_order")?,
+        })
+    }
+}
+
+/// The kind of a content block, stored in the `block_kind` Postgres enum.
+/// The variant names match the `type` tag of the `BlockContent` JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "block_kind", rename_all = "snake_case")]
+pub enum BlockKind {
+    Text,
+    Gallery,
+    Video,
+    Audio,
+    File,
+    Embed,
+    HostedVideo,
+    Quote,
+    Credits,
+    LinkList,
+    Columns,
+    Map,
+}
+
+impl BlockKind {
+    pub const ALL: &'static [BlockKind] = &[
+        BlockKind::Text,
+        BlockKind::Gallery,
+        BlockKind::Video,
+        BlockKind::Audio,
+        BlockKind::File,
+        BlockKind::Embed,
+        BlockKind::HostedVideo,
+        BlockKind::Quote,
+        BlockKind::Credits,
+        BlockKind::LinkList,
+        BlockKind::Columns,
+        BlockKind::Map,
+    ];
+
+    /// The name used in forms, URLs and the JSON `type` tag, e.g. "Gallery".
+    pub fn name(&self) -> &'static str {
+        match self {
+            BlockKind::Text => "Text",
+            BlockKind::Gallery => "Gallery",
+            BlockKind::Video => "Video",
+            BlockKind::Audio => "Audio",
+            BlockKind::File => "File",
+            BlockKind::Embed => "Embed",
+            BlockKind::HostedVideo => "HostedVideo",
+            BlockKind::Quote => "Quote",
+            BlockKind::Credits => "Credits",
+            BlockKind::LinkList => "LinkList",
+            BlockKind::Columns => "Columns",
+            BlockKind::Map => "Map",
+        }
+    }
+
+    /// Parse a kind from form input. Matching is case-insensitive, but
+    /// anything that is not a known kind is rejected.
+    pub fn parse(s: &str) -> Option<BlockKind> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|k| k.name().eq_ignore_ascii_case(s.trim()))
+    }
+}
+
+impl std::fmt::Display for BlockKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// How the source of a Text block or page is written, stored in the
+/// `text_format` Postgres enum.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "text_format", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum TextFormat {
+    #[default]
+    Html,
+    Markdown,
+}
+
+impl TextFormat {
+    pub const ALL: &'static [TextFormat] = &[TextFormat::Html, TextFormat::Markdown];
+
+    /// The value used in forms and JSON, e.g. "markdown".
+    pub fn name(&self) -> &'static str {
+        match self {
+            TextFormat::Html => "html",
+            TextFormat::Markdown => "markdown",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TextFormat::Html => "HTML",
+            TextFormat::Markdown => "Markdown",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<TextFormat> {
+        Self::ALL.iter().copied().find(|f| f.name().eq_ignore_ascii_case(s.trim()))
+    }
+
+    /// `source` as HTML, before sanitising. Markdown may contain raw HTML.
+    pub fn to_html<'a>(&self, source: &'a str) -> std::borrow::Cow<'a, str> {
+        match self {
+            TextFormat::Html => std::borrow::Cow::Borrowed(source),
+            TextFormat::Markdown => std::borrow::Cow::Owned(crate::markdown::to_html(source)),
+        }
+    }
+
+    pub fn to_plain_text(&self, source: &str) -> String {
+        match self {
+            TextFormat::Html => crate::sanitize::to_plain_text(source),
+            TextFormat::Markdown => crate::markdown::to_plain_text(source),
+        }
+    }
+}
+
+/// Rich text kept as written, with the rendered and sanitised HTML cached
+/// next to it so Markdown isn't re-rendered for every visitor.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TextBlock {
+    #[serde(default)]
+    pub format: TextFormat,
+    pub source: String,
+    pub html: String,
+}
+
+impl TextBlock {
+    /// Renders `source` and sanitises it with the site's HTML policy.
+    pub fn new(format: TextFormat, source: &str) -> Self {
+        let mut text = TextBlock { format, source: source.to_string(), html: String::new() };
+        text.sanitize(crate::sanitize::Policy::global());
+        text
+    }
+
+    pub fn plain_text(&self) -> String {
+        self.format.to_plain_text(&self.source)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(tag = "type", content = "data")]
 pub enum BlockContent {
-    Text(String),
+    Text(TextBlock),
     Gallery(Vec<String>), // List of image URLs
     Video(String),        // Embed URL
-    Audio(Vec<(String, String)>), // List of (url, title)
-    File(Vec<(String, String)>), // List of (url, description)
+    Audio(Vec<AudioItem>),
+    File(Vec<FileItem>),
+    Embed(Embed),
+    HostedVideo(HostedVideo),
+    Quote(Quote),
+    Credits(Vec<Credit>),
+    LinkList(Vec<LinkItem>),
+    Columns(Columns),
+    Map(Vec<MapMarker>),
+}
+
+/// A pin on a Map block or the `/map` page.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MapMarker {
+    pub lat: f64,
+    pub lng: f64,
+    pub label: String,
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+impl MapMarker {
+    pub fn point(&self) -> GeoPoint {
+        GeoPoint { lat: self.lat, lng: self.lng }
+    }
+}
+
+/// A pull quote.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Quote {
+    pub text: String,
+    #[serde(default)]
+    pub attribution: Option<String>,
+    /// Where it was said or published, e.g. a review's publication.
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+impl Quote {
+    /// The quote split on blank lines, for one `<p>` each.
+    pub fn paragraphs(&self) -> Vec<&str> {
+        self.text
+            .split("\n\n")
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .collect()
+    }
+}
+
+/// One line of a credits list, e.g. "Sound design" / "A. Person".
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Credit {
+    #[serde(default)]
+    pub role: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct LinkItem {
+    #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Two or three columns side by side, each holding its own blocks. Columns
+/// can't be nested.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Columns {
+    pub columns: Vec<Vec<BlockContent>>,
+}
+
+impl Columns {
+    pub const MIN: usize = 2;
+    pub const MAX: usize = 3;
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(Self::MIN..=Self::MAX).contains(&self.columns.len()) {
+            return Err(format!("Columns need {} or {} columns, got {}", Self::MIN, Self::MAX, self.columns.len()));
+        }
+        if self.columns.iter().flatten().any(|b| matches!(b, BlockContent::Columns(_))) {
+            return Err("Columns can't contain other columns".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// One track of an Audio block. Duration and peaks come from the media
+/// library when the file was uploaded, so the player can show them before
+/// anything is downloaded.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AudioItem {
+    pub url: String,
+    pub title: String,
+    #[serde(default)]
+    pub media_id: Option<Uuid>,
+    #[serde(default)]
+    pub duration: Option<f64>,
+    #[serde(default)]
+    pub artist: Option<String>,
+    #[serde(default)]
+    pub artwork_url: Option<String>,
+    /// Normalised waveform peaks, see `waveform::PEAK_COUNT`.
+    #[serde(default)]
+    pub peaks: Vec<f32>,
+}
+
+impl AudioItem {
+    pub fn duration_label(&self) -> Option<String> {
+        self.duration.map(format_duration)
+    }
+
+    /// SVG `path` data for the waveform in a `viewBox="0 0 {peaks} 100"`,
+    /// one vertical bar per peak, mirrored around the middle.
+    pub fn waveform_path(&self) -> String {
+        let mut d = String::new();
+        for (i, peak) in self.peaks.iter().enumerate() {
+            let half = (peak.clamp(0.0, 1.0) * 48.0).max(1.0);
+            d.push_str(&format!("M{}.5 {:.1}V{:.1}", i, 50.0 - half, 50.0 + half));
+        }
+        d
+    }
+}
+
+/// One download of a File block. The file details are copied from the media
+/// library when the block is saved.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct FileItem {
+    pub url: String,
+    pub description: String,
+    #[serde(default)]
+    pub media_id: Option<Uuid>,
+    #[serde(default)]
+    pub filename: Option<String>,
+    #[serde(default)]
+    pub mime_type: Option<String>,
+    #[serde(default)]
+    pub byte_size: Option<i64>,
+    #[serde(default)]
+    pub page_count: Option<u32>,
+    #[serde(default)]
+    pub thumbnail_url: Option<String>,
+}
+
+impl FileItem {
+    /// Files in local storage go through `/download/{id}` so they are saved
+    /// under their original name.
+    pub fn download_url(&self) -> String {
+        match self.media_id {
+            Some(id) if self.url.starts_with(crate::upload::LocalStorage::URL_PREFIX) => {
+                format!("/download/{}", id)
+            }
+            _ => self.url.clone(),
+        }
+    }
+
+    /// The name offered when saving, falling back to the last URL segment.
+    pub fn display_filename(&self) -> String {
+        self.filename.clone().unwrap_or_else(|| {
+            let name = self.url.rsplit('/').next().unwrap_or("");
+            name.split(['?', '#']).next().unwrap_or("").to_string()
+        })
+    }
+
+    pub fn size_label(&self) -> Option<String> {
+        self.byte_size.map(format_bytes)
+    }
+
+    pub fn pages_label(&self) -> Option<String> {
+        match self.page_count? {
+            1 => Some("1 page".to_string()),
+            n => Some(format!("{} pages", n)),
+        }
+    }
+
+    /// Short type name for the download list, e.g. "PDF" or "ZIP".
+    pub fn type_label(&self) -> Option<String> {
+        let ext = self.display_filename().rsplit_once('.').map(|(_, ext)| ext.to_uppercase());
+        ext.filter(|e| !e.is_empty() && e.len() <= 5)
+    }
+
+    /// Material icon name for the file type.
+    pub fn icon(&self) -> &'static str {
+        let mime = self.mime_type.as_deref().unwrap_or("");
+        match mime {
+            "application/pdf" => "picture_as_pdf",
+            "application/zip" | "application/x-zip-compressed" | "application/x-7z-compressed" | "application/gzip" => "folder_zip",
+            m if m.starts_with("image/") => "image",
+            m if m.starts_with("audio/") => "audio_file",
+            m if m.starts_with("video/") => "video_file",
+            m if m.starts_with("text/") => "article",
+            _ => "description",
+        }
+    }
+}
+
+/// A third-party embed, resolved through oEmbed when the block is saved.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Embed {
+    pub url: String,
+    pub provider: String,
+    pub html: String,
+    pub title: Option<String>,
+    pub thumbnail_url: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// A video from the media library. The media details are copied in when the
+/// block is saved so rendering never has to look the media item up.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HostedVideo {
+    pub media_id: Uuid,
+    pub src: String,
+    pub mime_type: String,
+    pub hls_url: Option<String>,
+    pub poster_url: Option<String>,
+    pub duration: Option<f64>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    #[serde(default)]
+    pub captions: Vec<CaptionTrack>,
+}
+
+/// A WebVTT caption or subtitle file for a hosted video.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CaptionTrack {
+    pub src: String,
+    pub srclang: String,
+    pub label: String,
+}
+
+impl HostedVideo {
+    /// "m:ss" (or "h:mm:ss"), for display next to the player.
+    pub fn duration_label(&self) -> Option<String> {
+        self.duration.map(format_duration)
+    }
+}
+
+/// Human-readable size using binary multiples, e.g. "40.2 MB".
+pub fn format_bytes(bytes: i64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if size >= 100.0 {
+        format!("{:.0} {}", size, UNITS[unit])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+pub fn format_duration(seconds: f64) -> String {
+    let total = seconds.round() as u64;
+    let (h, m, s) = (total / 3600, (total % 3600) / 60, total % 60);
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{}:{:02}", m, s)
+    }
+}
+
+/// An uploaded file in the media library.
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct MediaItem {
+    pub id: Uuid,
+    pub url: String,
+    pub original_name: String,
+    pub mime_type: String,
+    pub byte_size: i64,
+    pub local_path: Option<String>,
+    pub metadata: sqlx::types::Json<MediaMetadata>,
+    pub created_at: time::OffsetDateTime,
+}
+
+/// What was learned about a media item when it was uploaded.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct MediaMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poster_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hls_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peaks: Option<Vec<f32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_count: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail_url: Option<String>,
 }
 
 impl BlockContent {
-    pub fn as_text(&self) -> Option<&str> {
+    pub fn kind(&self) -> BlockKind {
+        match self {
+            BlockContent::Text(_) => BlockKind::Text,
+            BlockContent::Gallery(_) => BlockKind::Gallery,
+            BlockContent::Video(_) => BlockKind::Video,
+            BlockContent::Audio(_) => BlockKind::Audio,
+            BlockContent::File(_) => BlockKind::File,
+            BlockContent::Embed(_) => BlockKind::Embed,
+            BlockContent::HostedVideo(_) => BlockKind::HostedVideo,
+            BlockContent::Quote(_) => BlockKind::Quote,
+            BlockContent::Credits(_) => BlockKind::Credits,
+            BlockContent::LinkList(_) => BlockKind::LinkList,
+            BlockContent::Columns(_) => BlockKind::Columns,
+            BlockContent::Map(_) => BlockKind::Map,
+        }
+    }
+
+    /// One-line summary for the admin blocks list.
+    pub fn preview(&self) -> String {
+        match self {
+            BlockContent::Text(text) => {
+                let plain = text.plain_text();
+                match plain.char_indices().nth(50) {
+                    Some((cut, _)) => format!("{}\u{2026}", plain[..cut].trim_end()),
+                    None => plain,
+                }
+            },
+            BlockContent::Video(url) => format!("Video: {}", url),
+            BlockContent::Gallery(urls) => format!("{} images", urls.len()),
+            BlockContent::Audio(items) => format!("{} audio files", items.len()),
+            BlockContent::File(items) => format!("{} files", items.len()),
+            BlockContent::Embed(embed) => format!("{}: {}", embed.provider, embed.title.as_deref().unwrap_or(&embed.url)),
+            BlockContent::HostedVideo(video) => format!(
+                "Video: {}{}",
+                video.src.rsplit('/').next().unwrap_or(&video.src),
+                video.duration_label().map(|d| format!(" ({})", d)).unwrap_or_default()
+            ),
+            BlockContent::Quote(quote) => {
+                let text: String = quote.text.chars().take(50).collect();
+                match &quote.attribution {
+                    Some(who) => format!("\u{201c}{}\u{201d} \u{2014} {}", text, who),
+                    None => format!("\u{201c}{}\u{201d}", text),
+                }
+            },
+            BlockContent::Credits(credits) => format!("{} credits", credits.len()),
+            BlockContent::LinkList(links) => format!("{} links", links.len()),
+            BlockContent::Map(markers) => {
+                let labels: Vec<&str> = markers.iter().map(|m| m.label.as_str()).collect();
+                format!("Map: {}", labels.join(", "))
+            },
+            BlockContent::Columns(columns) => {
+                let summary: Vec<String> = columns
+                    .columns
+                    .iter()
+                    .map(|column| {
+                        let kinds: Vec<&str> = column.iter().map(|b| b.kind().name()).collect();
+                        if kinds.is_empty() { "empty".to_string() } else { kinds.join(", ") }
+                    })
+                    .collect();
+                format!("{} columns: {}", columns.columns.len(), summary.join(" | "))
+            },
+        }
+    }
+
+    /// The images of Gallery blocks, including those inside Columns.
+    pub fn gallery_images(&self) -> Vec<&str> {
         match self {
-            BlockContent::Text(s) => Some(s),
-            _ => None,
+            BlockContent::Gallery(urls) => urls.iter().map(String::as_str).collect(),
+            BlockContent::Columns(layout) => layout.columns.iter().flatten().flat_map(BlockContent::gallery_images).collect(),
+            _ => Vec::new(),
         }
     }
 }
@@ -0,0 +1,461 @@
+//! oEmbed support for Embed blocks.
+//!
+//! When an Embed block is saved its URL is resolved through the first
+//! enabled provider whose URL scheme matches, and the returned HTML,
+//! thumbnail and title are cached in the block. The public site never calls
+//! the provider itself; it only needs the provider's iframe origins, which
+//! are what the CSP `frame-src` is built from. The HTML is sanitised before
+//! it's stored, keeping only iframes from those origins.
+//!
+//! Bandcamp has no oEmbed endpoint, so its player is read from the page's
+//! `og:video` tag instead; see [`Resolver::PageMeta`].
+
+use serde::Deserialize;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use crate::models::Embed;
+use crate::sanitize::Policy;
+
+/// Origins the legacy Video block frames, whatever providers are enabled.
+pub const VIDEO_BLOCK_ORIGINS: &[&str] = &["https://www.youtube.com", "https://player.vimeo.com"];
+
+/// How a provider's embed is looked up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolver {
+    /// Ask the provider's oEmbed endpoint.
+    OEmbed,
+    /// Fetch the page itself and frame the player its `og:video` meta tag
+    /// names, for providers without an endpoint. Only pages on `domain` or
+    /// its subdomains are fetched, over https; see [`page_url`].
+    PageMeta { domain: &'static str },
+}
+
+#[derive(Debug, Clone)]
+pub struct Provider {
+    pub name: String,
+    /// URL patterns this provider handles. `*` matches any run of characters
+    /// in the path, and one label of letters, digits and `-` in the host.
+    pub schemes: Vec<String>,
+    /// oEmbed endpoint; the page URL is passed as the `url` query parameter.
+    pub endpoint: String,
+    /// Extra query parameters sent to the endpoint (e.g. an access token).
+    pub params: Vec<(String, String)>,
+    /// Origins the returned HTML loads iframes from, for the CSP.
+    pub frame_origins: Vec<String>,
+    pub resolver: Resolver,
+    /// Builds the player's iframe URL from the page URL, for providers whose
+    /// oEmbed HTML needs a script of theirs to turn into a player.
+    pub frame_url: Option<fn(&str) -> Option<String>>,
+}
+
+impl Provider {
+    pub fn new(name: &str, endpoint: &str, schemes: &[&str], frame_origins: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            schemes: schemes.iter().map(|s| s.to_string()).collect(),
+            endpoint: endpoint.to_string(),
+            params: Vec::new(),
+            frame_origins: frame_origins.iter().map(|s| s.to_string()).collect(),
+            resolver: Resolver::OEmbed,
+            frame_url: None,
+        }
+    }
+
+    pub fn matches(&self, url: &str) -> bool {
+        let https = url.replacen("http://", "https://", 1);
+        self.schemes
+            .iter()
+            .any(|pattern| scheme_matches(pattern, url) || scheme_matches(pattern, &https))
+    }
+}
+
+/// Providers known to this build. Instagram is only included when
+/// `INSTAGRAM_OEMBED_TOKEN` is set, since Meta's endpoint requires one.
+pub fn builtin_providers() -> Vec<Provider> {
+    let mut providers = vec![
+        Provider::new(
+            "YouTube",
+            "https://www.youtube.com/oembed",
+            &[
+                "https://www.youtube.com/watch*",
+                "https://youtube.com/watch*",
+                "https://m.youtube.com/watch*",
+                "https://youtu.be/*",
+                "https://www.youtube.com/shorts/*",
+            ],
+            &["https://www.youtube.com", "https://www.youtube-nocookie.com"],
+        ),
+        Provider::new(
+            "Vimeo",
+            "https://vimeo.com/api/oembed.json",
+            &["https://vimeo.com/*", "https://player.vimeo.com/video/*"],
+            &["https://player.vimeo.com"],
+        ),
+        Provider::new(
+            "SoundCloud",
+            "https://soundcloud.com/oembed",
+            &["https://soundcloud.com/*", "https://on.soundcloud.com/*"],
+            &["https://w.soundcloud.com"],
+        ),
+        Provider::new(
+            "Spotify",
+            "https://open.spotify.com/oembed",
+            &["https://open.spotify.com/*"],
+            &["https://open.spotify.com"],
+        ),
+        Provider::new(
+            "Sketchfab",
+            "https://sketchfab.com/oembed",
+            &["https://sketchfab.com/models/*", "https://sketchfab.com/3d-models/*"],
+            &["https://sketchfab.com"],
+        ),
+        Provider {
+            resolver: Resolver::PageMeta { domain: "bandcamp.com" },
+            ..Provider::new(
+                "Bandcamp",
+                "",
+                &["https://*.bandcamp.com/album/*", "https://*.bandcamp.com/track/*"],
+                &["https://bandcamp.com"],
+            )
+        },
+    ];
+
+    if let Ok(token) = std::env::var("INSTAGRAM_OEMBED_TOKEN") {
+        let mut instagram = Provider::new(
+            "Instagram",
+            "https://graph.facebook.com/v19.0/instagram_oembed",
+            &["https://www.instagram.com/p/*", "https://www.instagram.com/reel/*"],
+            &["https://www.instagram.com"],
+        );
+        instagram.params.push(("access_token".to_string(), token));
+        // Instagram's HTML is a blockquote that its embed.js turns into a
+        // player; the post's own embed page works without the script.
+        instagram.frame_url = Some(instagram_frame_url);
+        providers.push(instagram);
+    }
+
+    providers
+}
+
+/// `https://www.instagram.com/p/CODE/embed/` for a post or reel.
+pub fn instagram_frame_url(url: &str) -> Option<String> {
+    let url = url.replacen("http://", "https://", 1);
+    let path = url.split(['?', '#']).next()?.trim_end_matches('/');
+    let (kind, code) = path.strip_prefix("https://www.instagram.com/")?.split_once('/')?;
+    let post = matches!(kind, "p" | "reel") && !code.is_empty() && !code.contains('/');
+    post.then(|| format!("{}/embed/", path))
+}
+
+/// The policy stored embeds are rendered with, allowing the player of any
+/// builtin provider. Each embed is cleaned for its own provider when saved;
+/// this covers those saved before that, like the rich text filter does.
+pub fn render_policy() -> &'static Policy {
+    static POLICY: OnceLock<Policy> = OnceLock::new();
+    POLICY.get_or_init(|| {
+        let origins: Vec<String> = builtin_providers().into_iter().flat_map(|p| p.frame_origins).collect();
+        Policy::global().for_embed(&origins)
+    })
+}
+
+#[derive(Clone)]
+pub struct OEmbedRegistry {
+    providers: Arc<Vec<Provider>>,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct OEmbedResponse {
+    #[serde(rename = "type")]
+    kind: String,
+    html: Option<String>,
+    url: Option<String>,
+    title: Option<String>,
+    provider_name: Option<String>,
+    thumbnail_url: Option<String>,
+    width: Option<serde_json::Value>,
+    height: Option<serde_json::Value>,
+}
+
+impl OEmbedRegistry {
+    pub fn new(providers: Vec<Provider>) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("Failed to build HTTP client");
+        Self {
+            providers: Arc::new(providers),
+            client,
+        }
+    }
+
+    /// Builtin providers, optionally narrowed down by `OEMBED_PROVIDERS`
+    /// (a comma-separated list of provider names).
+    pub fn from_env() -> Self {
+        let mut providers = builtin_providers();
+        if let Ok(enabled) = std::env::var("OEMBED_PROVIDERS") {
+            let enabled: Vec<String> = enabled
+                .split(',')
+                .map(|s| s.trim().to_lowercase())
+                .filter(|s| !s.is_empty())
+                .collect();
+            providers.retain(|p| enabled.contains(&p.name.to_lowercase()));
+        }
+        Self::new(providers)
+    }
+
+    pub fn providers(&self) -> &[Provider] {
+        &self.providers
+    }
+
+    pub fn find(&self, url: &str) -> Option<&Provider> {
+        self.providers.iter().find(|p| p.matches(url))
+    }
+
+    /// Value for the CSP `frame-src` directive: the enabled providers, and
+    /// the players of Video blocks, which don't go through oEmbed.
+    pub fn frame_src(&self) -> String {
+        let mut sources = vec!["'self'".to_string()];
+        let video_block = VIDEO_BLOCK_ORIGINS.iter().map(|o| o.to_string());
+        for origin in self.providers.iter().flat_map(|p| p.frame_origins.iter().cloned()).chain(video_block) {
+            if !sources.contains(&origin) {
+                sources.push(origin);
+            }
+        }
+        sources.join(" ")
+    }
+
+    /// Ask the matching provider for embed HTML for `url`.
+    pub async fn resolve(&self, url: &str) -> Result<Embed, String> {
+        let url = url.trim();
+        let provider = self
+            .find(url)
+            .ok_or_else(|| format!("No enabled oEmbed provider handles {}", url))?;
+
+        let mut embed = match provider.resolver {
+            Resolver::OEmbed => self.oembed(provider, url).await?,
+            Resolver::PageMeta { domain } => {
+                let page = page_url(domain, url)?;
+                // The path is checked on the address that will be fetched.
+                if !provider.matches(page.as_str()) {
+                    return Err(format!("No enabled oEmbed provider handles {}", url));
+                }
+                self.page_meta(provider, page).await?
+            }
+        };
+        if let Some(src) = provider.frame_url.and_then(|frame_url| frame_url(url)) {
+            embed.html = iframe(&src, embed.title.as_deref().unwrap_or(&provider.name), embed.width, embed.height);
+        }
+        // Whatever the provider sent, only its own player is kept.
+        embed.html = Policy::global().for_embed(&provider.frame_origins).clean(&embed.html);
+        if embed.html.trim().is_empty() {
+            return Err(format!("{} has nothing embeddable for {}", provider.name, url));
+        }
+        Ok(embed)
+    }
+
+    async fn oembed(&self, provider: &Provider, url: &str) -> Result<Embed, String> {
+        let mut query: Vec<(&str, &str)> = vec![("url", url), ("format", "json")];
+        query.extend(provider.params.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+
+        let resp = self
+            .client
+            .get(&provider.endpoint)
+            .query(&query)
+            .send()
+            .await
+            .map_err(|e| format!("{} request failed: {}", provider.name, e))?;
+
+        if !resp.status().is_success() {
+            return Err(format!("{} returned {} for {}", provider.name, resp.status(), url));
+        }
+
+        let data: OEmbedResponse = resp
+            .json()
+            .await
+            .map_err(|e| format!("{} returned an invalid oEmbed response: {}", provider.name, e))?;
+
+        let html = match (data.kind.as_str(), data.html, data.url) {
+            (_, Some(html), _) if !html.trim().is_empty() => html,
+            ("photo", _, Some(src)) => format!(
+                r#"<img src="{}" alt="{}" loading="lazy">"#,
+                escape_attr(&src),
+                escape_attr(data.title.as_deref().unwrap_or(""))
+            ),
+            _ => return Err(format!("{} has nothing embeddable for {}", provider.name, url)),
+        };
+
+        Ok(Embed {
+            url: url.to_string(),
+            provider: data.provider_name.unwrap_or_else(|| provider.name.clone()),
+            html,
+            title: data.title,
+            thumbnail_url: data.thumbnail_url,
+            width: data.width.as_ref().and_then(dimension),
+            height: data.height.as_ref().and_then(dimension),
+        })
+    }
+
+    async fn page_meta(&self, provider: &Provider, url: reqwest::Url) -> Result<Embed, String> {
+        let resp = self
+            .client
+            .get(url.clone())
+            .send()
+            .await
+            .map_err(|e| format!("{} request failed: {}", provider.name, e))?;
+        if !resp.status().is_success() {
+            return Err(format!("{} returned {} for {}", provider.name, resp.status(), url));
+        }
+        let page = resp.text().await.map_err(|e| format!("{} page unreadable: {}", provider.name, e))?;
+        page_embed(provider, url.as_str(), &page)
+    }
+}
+
+/// The page at `url`, if a [`Resolver::PageMeta`] provider may fetch it:
+/// https (plain http is upgraded), on `domain` or a subdomain of it, with no
+/// port or credentials.
+pub fn page_url(domain: &str, url: &str) -> Result<reqwest::Url, String> {
+    let refused = || format!("Only https://{} addresses can be embedded, not {}", domain, url);
+    let mut page = reqwest::Url::parse(url.trim()).map_err(|_| refused())?;
+    if page.scheme() == "http" {
+        page.set_scheme("https").map_err(|_| refused())?;
+    }
+    let host = page.domain().unwrap_or("").to_ascii_lowercase();
+    let on_domain = host == domain || host.strip_suffix(domain).is_some_and(|sub| sub.ends_with('.'));
+    if page.scheme() != "https" || page.port().is_some() || !page.username().is_empty() || page.password().is_some() || !on_domain {
+        return Err(refused());
+    }
+    Ok(page)
+}
+
+/// The embed for a page a [`Resolver::PageMeta`] provider fetched.
+pub fn page_embed(provider: &Provider, url: &str, page: &str) -> Result<Embed, String> {
+    let player = meta_content(page, "og:video:secure_url")
+        .or_else(|| meta_content(page, "og:video"))
+        .ok_or_else(|| format!("{} has no player for {}; link to an album or a track", provider.name, url))?;
+    let title = meta_content(page, "og:title");
+    let width = meta_content(page, "og:video:width").and_then(|w| w.parse().ok());
+    let height = meta_content(page, "og:video:height").and_then(|h| h.parse().ok());
+    Ok(Embed {
+        url: url.to_string(),
+        provider: provider.name.clone(),
+        html: iframe(&player, title.as_deref().unwrap_or(&provider.name), width, height),
+        title,
+        thumbnail_url: meta_content(page, "og:image"),
+        width,
+        height,
+    })
+}
+
+fn iframe(src: &str, title: &str, width: Option<u32>, height: Option<u32>) -> String {
+    let size = |name: &str, value: Option<u32>| value.map(|v| format!(r#" {}="{}""#, name, v)).unwrap_or_default();
+    format!(
+        r#"<iframe src="{}" title="{}"{}{} allowfullscreen></iframe>"#,
+        escape_attr(src),
+        escape_attr(title),
+        size("width", width),
+        size("height", height)
+    )
+}
+
+/// The `content` of the page's `<meta property="...">` tag, e.g. Open
+/// Graph's `og:video`. A scan of the tags rather than an HTML parser.
+pub fn meta_content(page: &str, property: &str) -> Option<String> {
+    let mut rest = page;
+    while let Some(start) = rest.find("<meta") {
+        rest = &rest[start + 5..];
+        let tag = &rest[..rest.find('>').unwrap_or(rest.len())];
+        if attribute(tag, "property").or_else(|| attribute(tag, "name")).as_deref() == Some(property) {
+            return attribute(tag, "content").filter(|c| !c.is_empty());
+        }
+    }
+    None
+}
+
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let mut rest = tag;
+    loop {
+        let at = rest.find(name)?;
+        let before = rest[..at].chars().last();
+        rest = &rest[at + name.len()..];
+        if !before.is_some_and(char::is_whitespace) {
+            continue;
+        }
+        let Some(value) = rest.trim_start().strip_prefix('=') else { continue };
+        let value = value.trim_start();
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let body = &value[1..];
+        let end = body.find(quote)?;
+        return Some(unescape_attr(&body[..end]));
+    }
+}
+
+fn unescape_attr(s: &str) -> String {
+    s.replace("&quot;", "\"").replace("&#39;", "'").replace("&lt;", "<").replace("&gt;", ">").replace("&amp;", "&")
+}
+
+// Providers disagree on whether width/height are numbers or strings
+// (and some send "100%"), so only keep plain pixel values.
+fn dimension(value: &serde_json::Value) -> Option<u32> {
+    match value {
+        serde_json::Value::Number(n) => n.as_u64().map(|n| n as u32),
+        serde_json::Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+fn escape_attr(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Splits a URL or pattern after its host, e.g. into `https://a.example.com`
+/// and `/path?query`.
+fn split_host(url: &str) -> (&str, &str) {
+    let start = url.find("://").map_or(0, |i| i + 3);
+    let end = url[start..].find(['/', '?', '#']).map_or(url.len(), |i| start + i);
+    url.split_at(end)
+}
+
+/// `host` against a pattern whose `*`s each stand for one label, so they
+/// can't reach past a `.`, a port or into the path.
+fn host_matches(pattern: &str, host: &str) -> bool {
+    let Some((before, after)) = pattern.split_once('*') else {
+        return pattern.eq_ignore_ascii_case(host);
+    };
+    match host.get(..before.len()) {
+        Some(start) if start.eq_ignore_ascii_case(before) => {
+            let rest = &host[before.len()..];
+            let label = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '-')).unwrap_or(rest.len());
+            label > 0 && host_matches(after, &rest[label..])
+        }
+        _ => false,
+    }
+}
+
+fn scheme_matches(pattern: &str, url: &str) -> bool {
+    let (pattern_host, pattern) = split_host(pattern);
+    let (host, url) = split_host(url);
+    if !host_matches(pattern_host, host) {
+        return false;
+    }
+
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = url.strip_prefix(parts.next().unwrap_or("")) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        if i == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    rest.is_empty()
+}
@@ -0,0 +1,185 @@
+//! Permanent redirects from old addresses. Changing a project's slug leaves
+//! one behind automatically; others are added by hand in the admin. Deleting
+//! a project marks its addresses as gone. They only answer for addresses that
+//! nothing else on the site serves.
+
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use sqlx::{PgConnection, PgPool};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// A redirect with its target resolved: a project's current address, or
+/// the fixed path or URL it was given. No target means the address is gone.
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct RedirectRule {
+    pub id: Uuid,
+    pub from_path: String,
+    pub target: Option<String>,
+    /// Set for redirects left behind by a slug change.
+    pub project_title: Option<String>,
+    pub created_at: OffsetDateTime,
+}
+
+/// Paths the app serves itself, which a redirect must not shadow or break.
+const RESERVED: &[&str] = &["/admin", "/static", crate::upload::LocalStorage::URL_PREFIX];
+
+pub fn project_path(slug: &str) -> String {
+    format!("/project/{}", slug)
+}
+
+/// The form a path is stored and looked up in: without a trailing slash.
+pub fn normalize(path: &str) -> String {
+    let path = path.trim();
+    match path.trim_end_matches('/') {
+        "" => "/".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// Checks the old address of a manual redirect, returning its normal form.
+/// Paths are kept to URL-safe characters so they can double as file names
+/// in the static export.
+pub fn validate_from(path: &str) -> Result<String, String> {
+    let path = normalize(path);
+    if !path.starts_with('/') {
+        return Err("The old address must be a path starting with /, e.g. /old-work".to_string());
+    }
+    if path == "/" {
+        return Err("The home page can't be redirected".to_string());
+    }
+    if RESERVED.iter().any(|r| path == *r || path.starts_with(&format!("{}/", r))) {
+        return Err("Admin, static and media addresses can't be redirected".to_string());
+    }
+    let segment_ok = |s: &str| {
+        !s.is_empty() && s != "." && s != ".." && s.chars().all(|c| c.is_ascii_alphanumeric() || "-_.~%".contains(c))
+    };
+    if !path[1..].split('/').all(segment_ok) {
+        return Err("Use letters, digits, - _ . ~ and single slashes in the old address".to_string());
+    }
+    Ok(path)
+}
+
+/// Checks where a manual redirect goes: a path on this site or an http(s)
+/// URL, and not back to `from`.
+pub fn validate_to(to: &str, from: &str) -> Result<String, String> {
+    let to = to.trim();
+    let is_path = to.starts_with('/') && !to.starts_with("//");
+    let is_url = to.starts_with("https://") || to.starts_with("http://");
+    if !is_path && !is_url {
+        return Err("The new address must be a path starting with / or an http(s) URL".to_string());
+    }
+    // It's sent as a Location header, so it has to be plain ASCII.
+    if to.chars().any(|c| !c.is_ascii_graphic() || "\"<>".contains(c)) {
+        return Err("The new address can't contain spaces, quotes, angle brackets or accented letters (percent-encode them)".to_string());
+    }
+    if is_path && normalize(to) == from {
+        return Err("A redirect can't point at its own address".to_string());
+    }
+    Ok(to.to_string())
+}
+
+const SELECT: &str = "SELECT r.id, r.from_path, COALESCE('/project/' || p.slug, r.to_path) AS target, p.title AS project_title, r.created_at
+    FROM redirects r LEFT JOIN projects p ON p.id = r.project_id";
+
+pub async fn all(pool: &PgPool) -> Result<Vec<RedirectRule>, sqlx::Error> {
+    sqlx::query_as(&format!("{} ORDER BY r.from_path", SELECT)).fetch_all(pool).await
+}
+
+/// What became of an old address.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Destination {
+    Moved(String),
+    Gone,
+}
+
+/// What became of `path`, if it is a known old address.
+pub async fn find(pool: &PgPool, path: &str) -> Option<Destination> {
+    let target: Option<Option<String>> = sqlx::query_scalar(&format!("SELECT target FROM ({}) r WHERE from_path = $1", SELECT))
+        .bind(normalize(path))
+        .fetch_optional(pool)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::error!("Redirect lookup for {} failed: {}", path, e);
+            None
+        });
+    target.map(|target| target.map_or(Destination::Gone, Destination::Moved))
+}
+
+/// Removes any redirect from `path`, now that something lives there.
+pub async fn release(conn: &mut PgConnection, path: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM redirects WHERE from_path = $1")
+        .bind(normalize(path))
+        .execute(conn)
+        .await
+        .map(|_| ())
+}
+
+/// Sends the project's old address on to it. Run in the transaction that
+/// changes the slug.
+pub async fn record_slug_change(conn: &mut PgConnection, project_id: Uuid, old_slug: &str, new_slug: &str) -> Result<(), sqlx::Error> {
+    release(&mut *conn, &project_path(new_slug)).await?;
+    sqlx::query(
+        "INSERT INTO redirects (from_path, project_id) VALUES ($1, $2)
+         ON CONFLICT (from_path) DO UPDATE SET project_id = EXCLUDED.project_id, to_path = NULL"
+    )
+    .bind(project_path(old_slug))
+    .bind(project_id)
+    .execute(conn)
+    .await
+    .map(|_| ())
+}
+
+/// Marks a project's address, and the old ones that led to it, as gone.
+/// Run in the transaction that deletes the project, before the delete.
+pub async fn record_deletion(conn: &mut PgConnection, project_id: Uuid, slug: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE redirects SET project_id = NULL WHERE project_id = $1")
+        .bind(project_id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query(
+        "INSERT INTO redirects (from_path) VALUES ($1)
+         ON CONFLICT (from_path) DO UPDATE SET project_id = NULL, to_path = NULL"
+    )
+    .bind(project_path(slug))
+    .execute(conn)
+    .await
+    .map(|_| ())
+}
+
+/// A 301 to `target`. axum's `Redirect::permanent` is a 308, which older
+/// crawlers and link checkers don't all treat as a move.
+pub fn moved_permanently(target: &str) -> Response {
+    (StatusCode::MOVED_PERMANENTLY, [(header::LOCATION, target.to_string())]).into_response()
+}
+
+/// A Netlify/Cloudflare Pages `_redirects` file. Gone addresses are left
+/// out, so static hosts serve them the 404 page.
+pub fn redirects_file(rules: &[RedirectRule]) -> String {
+    rules
+        .iter()
+        .filter_map(|r| Some(format!("{} {} 301\n", r.from_path, r.target.as_deref()?)))
+        .collect()
+}
+
+/// A page that forwards to `target`, for hosts without a redirects file.
+pub fn stub_html(target: &str) -> String {
+    let target = target.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;").replace('>', "&gt;");
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta http-equiv="refresh" content="0; url={0}">
+<link rel="canonical" href="{0}">
+<meta name="robots" content="noindex">
+<title>Moved</title>
+</head>
+<body>
+<p>This page has moved to <a href="{0}">{0}</a>.</p>
+</body>
+</html>
+"#,
+        target
+    )
+}
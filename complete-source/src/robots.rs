@@ -0,0 +1,68 @@
+//! `/robots.txt`. Crawlers are always kept out of the admin and pointed at
+//! the sitemap; anything more, like keeping one bot away from the whole
+//! site, is typed into the admin settings as extra lines.
+
+use crate::site::Site;
+use crate::sitemap;
+use crate::validation::FieldErrors;
+
+pub const PATH: &str = "/robots.txt";
+
+pub const CONTENT_TYPE: &str = "text/plain; charset=utf-8";
+
+const ADMIN_RULE: &str = "Disallow: /admin";
+
+/// The fields an extra line may start with.
+const FIELDS: &[&str] = &["user-agent", "allow", "disallow", "crawl-delay", "sitemap"];
+
+fn field(line: &str) -> Option<&str> {
+    line.split_once(':').map(|(field, _)| field.trim())
+}
+
+fn is_user_agent(line: &str) -> bool {
+    field(line).is_some_and(|f| f.eq_ignore_ascii_case("user-agent"))
+}
+
+/// The extra lines as they're stored, or an error on `robots_rules` for the
+/// first line that isn't a rule or a `#` comment.
+pub fn validate(errors: &mut FieldErrors, rules: &str) -> String {
+    let lines: Vec<&str> = rules.lines().map(str::trim).collect();
+    for (number, line) in lines.iter().enumerate() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if !field(line).is_some_and(|f| FIELDS.iter().any(|known| known.eq_ignore_ascii_case(f))) {
+            errors.add(
+                "robots_rules",
+                format!("Line {} isn't a rule like “Disallow: /drafts” or a # comment", number + 1),
+            );
+            break;
+        }
+    }
+    lines.join("\n").trim().to_string()
+}
+
+/// The file: every user agent, including the ones named in `rules`, is
+/// disallowed `/admin`. Rules before the first `User-agent` line apply to
+/// all crawlers.
+pub fn render(site: &Site, rules: &str) -> String {
+    let lines: Vec<&str> = rules.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+
+    let mut robots = format!("User-agent: *\n{}\n", ADMIN_RULE);
+    for (i, line) in lines.iter().enumerate() {
+        let agent = is_user_agent(line);
+        // A group starts with its user agents and gets a blank line before
+        // it, which also keeps the first one apart from `*`.
+        if agent && (i == 0 || !is_user_agent(lines[i - 1])) {
+            robots.push('\n');
+        }
+        robots.push_str(line);
+        robots.push('\n');
+        if agent && !lines.get(i + 1).is_some_and(|next| is_user_agent(next)) {
+            robots.push_str(ADMIN_RULE);
+            robots.push('\n');
+        }
+    }
+    robots.push_str(&format!("\nSitemap: {}\n", site.absolute(sitemap::PATH)));
+    robots
+}
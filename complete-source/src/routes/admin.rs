@@ -1,17 +1,30 @@
 use axum::{
     extract::{Path, State, Query},
-    response::{IntoResponse, Redirect, Html, Response},
+    response::{IntoResponse, Redirect, Response},
     Form,
     http::HeaderMap,
 };
 use sqlx::PgPool;
 use uuid::Uuid;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use time::Date;
-use crate::models::{Project, ContentBlock, BlockContent, User};
-use crate::templates::{DashboardTemplate, ProjectFormTemplate, ProjectBlocksTemplate, BlockFormTemplate, SettingsTemplate, PagesListTemplate, PageFormTemplate};
+use crate::models::{Project, ContentBlock, AudioItem, BlockContent, BlockKind, CaptionTrack, Columns, Credit, FileItem, GeoPoint, HostedVideo, LinkItem, MapMarker, MediaItem, Quote, TextBlock, TextFormat, User};
+use crate::templates::{DashboardTemplate, ProjectOrderTemplate, ProjectFormTemplate, ProjectInput, ProjectBlocksTemplate, BlocksListTemplate, BlockFormTemplate, SettingsTemplate, RedirectsTemplate};
 use tower_sessions::Session;
-use crate::csrf::{get_or_create_csrf_token, verify_csrf_token};
+use crate::csrf::{get_or_create_csrf_token, require_csrf_token};
+use crate::error::AppError;
+use crate::flash;
+use crate::oembed::OEmbedRegistry;
+use crate::geocode::Geocoder;
+use crate::settings::{IndexOrder, SearchLanguage, SiteSettings};
+use crate::redirects;
+use crate::robots;
+use crate::search::{self, SearchQuery};
+use crate::site::Site;
+use crate::slug::{self, SlugError};
+use crate::thumbnail::FocalPoint;
+use crate::upload::Storage;
+use crate::validation::{self, FieldErrors};
 
 #[derive(Deserialize)]
 pub struct DeleteForm {
@@ -23,6 +36,17 @@ pub struct DeployForm {
     pub authenticity_token: String,
 }
 
+#[derive(Deserialize)]
+pub struct DuplicateForm {
+    pub authenticity_token: String,
+}
+
+#[derive(Deserialize)]
+pub struct MoveBlockForm {
+    pub project_id: Uuid,
+    pub authenticity_token: String,
+}
+
 
 #[derive(Deserialize)]
 pub struct ProjectForm {
@@ -31,121 +55,513 @@ pub struct ProjectForm {
     pub description: Option<String>,
     pub start_date: String,
     pub end_date: Option<String>,
+    #[serde(default)]
+    pub location_name: Option<String>,
+    #[serde(default)]
+    pub latitude: Option<String>,
+    #[serde(default)]
+    pub longitude: Option<String>,
+    // Checkboxes are only sent when checked.
+    #[serde(default)]
+    pub featured: Option<String>,
+    #[serde(default)]
+    pub thumbnail_url: Option<String>,
+    #[serde(default)]
+    pub thumbnail_focus_x: Option<String>,
+    #[serde(default)]
+    pub thumbnail_focus_y: Option<String>,
     pub authenticity_token: String,
 }
 
+/// The checked values of a submitted project.
+struct ValidProject {
+    start_date: Date,
+    end_date: Option<Date>,
+    location: Option<GeoPoint>,
+    thumbnail: Option<(String, FocalPoint)>,
+}
+
+impl ProjectForm {
+    /// Checks every field but the slug. The coordinates are as entered, or
+    /// looked up from the location name when both are left empty.
+    async fn validate(&self, geocoder: &Geocoder) -> Result<ValidProject, FieldErrors> {
+        let mut errors = FieldErrors::new();
+        validation::required(&mut errors, "title", "Title", &self.title);
+        let (start_date, end_date) = validation::date_range(&mut errors, &self.start_date, self.end_date.as_deref());
+        let entered = validation::coordinates(&mut errors, self.latitude.as_deref(), self.longitude.as_deref());
+        let thumbnail = self.thumbnail().unwrap_or_else(|message| {
+            errors.add("thumbnail_url", message);
+            None
+        });
+        let location = match (entered, self.location_name.as_deref()) {
+            (Some(point), _) => Some(point),
+            (None, Some(name)) if !errors.has("latitude") && !errors.has("longitude") => {
+                geocoder.geocode(name).await.unwrap_or_else(|message| {
+                    errors.add("location_name", message);
+                    None
+                })
+            }
+            _ => None,
+        };
+        match start_date {
+            Some(start_date) => errors.check(ValidProject { start_date, end_date, location, thumbnail }),
+            None => Err(errors),
+        }
+    }
+
+    /// The slug to save: as entered, or made from the title when left empty.
+    fn slug(&self) -> String {
+        match self.slug.trim() {
+            "" => slug::slugify(&self.title),
+            entered => entered.to_string(),
+        }
+    }
+
+    /// The submitted values as a project, to show the form again with them.
+    /// Dates and coordinates that don't parse are shown from `ProjectInput`.
+    fn to_project(&self, id: Uuid, slug: &str) -> Project {
+        let number = |v: &Option<String>| v.as_deref().and_then(|v| v.trim().parse().ok());
+        let focus = FocalPoint::parse(self.thumbnail_focus_x.as_deref(), self.thumbnail_focus_y.as_deref());
+        Project {
+            id,
+            title: self.title.clone(),
+            slug: slug.to_string(),
+            description: self.description.clone(),
+            start_date: validation::parse_date(&self.start_date).unwrap_or(Date::MIN),
+            end_date: self.end_date.as_deref().and_then(validation::parse_date),
+            thumbnail_url: non_empty(self.thumbnail_url.clone()),
+            latitude: number(&self.latitude),
+            longitude: number(&self.longitude),
+            location_name: self.location_name.clone(),
+            featured: self.featured.is_some(),
+            position: None,
+            thumbnail_focus_x: focus.x,
+            thumbnail_focus_y: focus.y,
+            social_image_url: None,
+        }
+    }
+
+    /// What was typed in the fields `Project` can't hold as text.
+    fn input(&self) -> ProjectInput {
+        let text = |v: &Option<String>| v.clone().unwrap_or_default();
+        ProjectInput {
+            start_date: self.start_date.clone(),
+            end_date: text(&self.end_date),
+            latitude: text(&self.latitude),
+            longitude: text(&self.longitude),
+        }
+    }
+
+    /// The thumbnail and its focal point, if one was chosen.
+    fn thumbnail(&self) -> Result<Option<(String, FocalPoint)>, String> {
+        let Some(url) = self.thumbnail_url.as_deref().map(str::trim).filter(|u| !u.is_empty()) else {
+            return Ok(None);
+        };
+        if !is_safe_link(url) || url.starts_with("mailto:") {
+            return Err("The thumbnail must be an uploaded image or a web address".to_string());
+        }
+        let focus = FocalPoint::parse(self.thumbnail_focus_x.as_deref(), self.thumbnail_focus_y.as_deref());
+        Ok(Some((url.to_string(), focus)))
+    }
+}
+
+/// Checks the slug of a project being saved (`id` is `None` for a new one).
+/// Without a title or a slug there's nothing to check yet.
+async fn check_slug(pool: &PgPool, id: Option<Uuid>, payload: &ProjectForm) -> Result<Option<SlugError>, AppError> {
+    if payload.title.trim().is_empty() && payload.slug.trim().is_empty() {
+        return Ok(None);
+    }
+    let taken = slug::taken(pool, id).await?;
+    Ok(slug::check(&payload.slug(), &payload.title, &taken).err())
+}
+
+/// Checks a submitted project, or shows the form again with what was
+/// submitted and what's wrong with it.
+async fn check_project(
+    pool: &PgPool,
+    geocoder: &Geocoder,
+    session: &Session,
+    id: Option<Uuid>,
+    payload: &ProjectForm,
+) -> Result<Result<ValidProject, Response>, AppError> {
+    let slug_error = check_slug(pool, id, payload).await?;
+    match (payload.validate(geocoder).await, slug_error) {
+        (Ok(valid), None) => Ok(Ok(valid)),
+        (result, slug_error) => {
+            let errors = result.err().unwrap_or_default();
+            Ok(Err(invalid_project_form(pool, geocoder, session, id, payload, errors, slug_error).await))
+        }
+    }
+}
+
+async fn invalid_project_form(
+    pool: &PgPool,
+    geocoder: &Geocoder,
+    session: &Session,
+    id: Option<Uuid>,
+    payload: &ProjectForm,
+    errors: FieldErrors,
+    slug_error: Option<SlugError>,
+) -> Response {
+    let csrf_token = get_or_create_csrf_token(session).await;
+    let gallery_images = match id {
+        Some(id) => gallery_images(pool, id).await,
+        None => Vec::new(),
+    };
+    (
+        axum::http::StatusCode::UNPROCESSABLE_ENTITY,
+        ProjectFormTemplate {
+            project: Some(payload.to_project(id.unwrap_or_else(Uuid::nil), &payload.slug())),
+            input: payload.input(),
+            is_new: id.is_none(),
+            errors,
+            slug_error,
+            gallery_images,
+            geocoding: geocoder.is_enabled(),
+            flashes: flash::take(session).await,
+            authenticity_token: csrf_token,
+        },
+    ).into_response()
+}
+
+/// Turns a failed save into the slug error if another project took the
+/// slug in the meantime.
+async fn save_error(
+    pool: &PgPool,
+    geocoder: &Geocoder,
+    session: &Session,
+    id: Option<Uuid>,
+    payload: &ProjectForm,
+    error: sqlx::Error,
+) -> Result<Response, AppError> {
+    if matches!(&error, sqlx::Error::Database(e) if e.is_unique_violation()) {
+        let taken = slug::taken(pool, id).await?;
+        let slug_error = SlugError::taken(&payload.slug(), &taken);
+        return Ok(invalid_project_form(pool, geocoder, session, id, payload, FieldErrors::new(), Some(slug_error)).await);
+    }
+    Err(error.into())
+}
+
+/// Tells the editor the project was saved, and that its location wasn't put
+/// on the map if it couldn't be found.
+async fn flash_saved(session: &Session, geocoder: &Geocoder, payload: &ProjectForm, location: Option<GeoPoint>, done: &str) {
+    flash::success(session, format!("“{}” {}", payload.title.trim(), done)).await;
+    let unplaced = non_empty(payload.location_name.clone()).filter(|_| location.is_none() && geocoder.is_enabled());
+    if let Some(place) = unplaced {
+        flash::warning(session, format!("“{}” wasn't found on the map. Enter its coordinates to place the project", place)).await;
+    }
+}
+
+/// The thumbnail columns of a project: its URL, focal point and social card.
+async fn thumbnail_columns(pool: &PgPool, storage: &Storage, thumbnail: Option<(String, FocalPoint)>) -> (Option<String>, FocalPoint, Option<String>) {
+    match thumbnail {
+        Some((url, focus)) => {
+            let card = crate::thumbnail::social_card(pool, storage, &url, focus).await;
+            (Some(url), focus, card)
+        }
+        None => (None, FocalPoint::default(), None),
+    }
+}
+
 pub async fn dashboard(
     State(pool): State<PgPool>,
+    Query(params): Query<SearchQuery>,
     session: Session,
-) -> impl IntoResponse {
-    let projects = sqlx::query_as::<_, Project>("SELECT * FROM projects ORDER BY start_date DESC")
+) -> Result<DashboardTemplate, AppError> {
+    let order = SiteSettings::load(&pool).await.index_order;
+    let projects = sqlx::query_as::<_, Project>(&format!("SELECT * FROM projects ORDER BY {}", order.order_by()))
         .fetch_all(&pool)
-        .await
-        .unwrap_or_default();
+        .await?;
+
+    let query = params.q.trim().to_string();
+    let results = search::search(&pool, &query, search::MAX_RESULTS).await?;
 
     let csrf_token = get_or_create_csrf_token(&session).await;
-    DashboardTemplate { 
+    Ok(DashboardTemplate { 
         projects,
+        query,
+        results,
+        flashes: flash::take(&session).await,
         authenticity_token: csrf_token,
-    }
+    })
 }
 
-pub async fn new_project(session: Session) -> impl IntoResponse {
+pub async fn new_project(State(geocoder): State<Geocoder>, session: Session) -> impl IntoResponse {
     let csrf_token = get_or_create_csrf_token(&session).await;
     ProjectFormTemplate { 
         project: None,
+        input: ProjectInput::default(),
+        is_new: true,
+        errors: FieldErrors::new(),
+        slug_error: None,
+        gallery_images: Vec::new(),
+        geocoding: geocoder.is_enabled(),
+        flashes: flash::take(&session).await,
         authenticity_token: csrf_token,
     }
 }
 
 pub async fn create_project(
     State(pool): State<PgPool>,
+    State(storage): State<Storage>,
+    State(geocoder): State<Geocoder>,
     session: Session,
     Form(payload): Form<ProjectForm>,
-) -> impl IntoResponse {
-    if !verify_csrf_token(&session, &payload.authenticity_token).await {
-        return (axum::http::StatusCode::FORBIDDEN, "Invalid CSRF Token").into_response();
-    }
-    let start_date = parse_date(&payload.start_date);
-    let end_date = parse_date_option(payload.end_date);
+) -> Result<Response, AppError> {
+    require_csrf_token(&session, &payload.authenticity_token).await?;
+    let ValidProject { start_date, end_date, location, thumbnail } = match check_project(&pool, &geocoder, &session, None, &payload).await? {
+        Ok(valid) => valid,
+        Err(form) => return Ok(form),
+    };
+    let slug = payload.slug();
+    let (thumbnail_url, focus, social_image_url) = thumbnail_columns(&pool, &storage, thumbnail).await;
 
-    sqlx::query(
-        "INSERT INTO projects (title, slug, description, start_date, end_date) VALUES ($1, $2, $3, $4, $5)"
-    )
-    .bind(payload.title)
-    .bind(payload.slug)
-    .bind(payload.description)
-    .bind(start_date)
-    .bind(end_date)
-    .execute(&pool)
-    .await
-    .unwrap();
+    // A redirect from the new project's address would never be reached.
+    let saved = async {
+        let mut tx = pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO projects (title, slug, description, start_date, end_date, location_name, latitude, longitude, featured, thumbnail_url, thumbnail_focus_x, thumbnail_focus_y, social_image_url) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)"
+        )
+        .bind(&payload.title)
+        .bind(&slug)
+        .bind(&payload.description)
+        .bind(start_date)
+        .bind(end_date)
+        .bind(non_empty(payload.location_name.clone()))
+        .bind(location.map(|p| p.lat))
+        .bind(location.map(|p| p.lng))
+        .bind(payload.featured.is_some())
+        .bind(thumbnail_url)
+        .bind(focus.x)
+        .bind(focus.y)
+        .bind(social_image_url)
+        .execute(&mut *tx)
+        .await?;
+        redirects::release(&mut tx, &redirects::project_path(&slug)).await?;
+        tx.commit().await
+    }
+    .await;
 
-    Redirect::to("/admin/dashboard").into_response()
+    match saved {
+        Ok(_) => {
+            flash_saved(&session, &geocoder, &payload, location, "created").await;
+            Ok(Redirect::to("/admin/dashboard").into_response())
+        }
+        Err(e) => save_error(&pool, &geocoder, &session, None, &payload, e).await,
+    }
 }
 
 pub async fn edit_project(
     State(pool): State<PgPool>,
+    State(geocoder): State<Geocoder>,
     Path(id): Path<Uuid>,
     session: Session,
-) -> impl IntoResponse {
+) -> Result<ProjectFormTemplate, AppError> {
     let project = sqlx::query_as::<_, Project>("SELECT * FROM projects WHERE id = $1")
         .bind(id)
-        .fetch_one(&pool)
-        .await
-        .ok();
+        .fetch_optional(&pool)
+        .await?
+        .ok_or(AppError::NotFound("Project"))?;
 
     let csrf_token = get_or_create_csrf_token(&session).await;
-    ProjectFormTemplate { 
-        project,
+    Ok(ProjectFormTemplate { 
+        input: ProjectInput::from(&project),
+        project: Some(project),
+        is_new: false,
+        errors: FieldErrors::new(),
+        slug_error: None,
+        gallery_images: gallery_images(&pool, id).await,
+        geocoding: geocoder.is_enabled(),
+        flashes: flash::take(&session).await,
         authenticity_token: csrf_token,
-    }
+    })
 }
 
 pub async fn update_project(
     State(pool): State<PgPool>,
+    State(storage): State<Storage>,
+    State(geocoder): State<Geocoder>,
     Path(id): Path<Uuid>,
     session: Session,
     Form(payload): Form<ProjectForm>,
-) -> impl IntoResponse {
-    if !verify_csrf_token(&session, &payload.authenticity_token).await {
-        return (axum::http::StatusCode::FORBIDDEN, "Invalid CSRF Token").into_response();
+) -> Result<Response, AppError> {
+    require_csrf_token(&session, &payload.authenticity_token).await?;
+    let ValidProject { start_date, end_date, location, thumbnail } = match check_project(&pool, &geocoder, &session, Some(id), &payload).await? {
+        Ok(valid) => valid,
+        Err(form) => return Ok(form),
+    };
+    let slug = payload.slug();
+    let (thumbnail_url, focus, social_image_url) = thumbnail_columns(&pool, &storage, thumbnail).await;
+
+    // The old slug is read and replaced in one transaction, so the redirect
+    // left behind always matches what was there.
+    let saved = async {
+        let mut tx = pool.begin().await?;
+        let old: Option<(String, Option<String>)> = sqlx::query_as("SELECT slug, social_image_url FROM projects WHERE id = $1 FOR UPDATE")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        let (old_slug, old_card) = old.unzip();
+        sqlx::query(
+            "UPDATE projects SET title = $1, slug = $2, description = $3, start_date = $4, end_date = $5, location_name = $6, latitude = $7, longitude = $8, featured = $9, thumbnail_url = $10, thumbnail_focus_x = $11, thumbnail_focus_y = $12, social_image_url = $13, updated_at = NOW() WHERE id = $14"
+        )
+        .bind(&payload.title)
+        .bind(&slug)
+        .bind(&payload.description)
+        .bind(start_date)
+        .bind(end_date)
+        .bind(non_empty(payload.location_name.clone()))
+        .bind(location.map(|p| p.lat))
+        .bind(location.map(|p| p.lng))
+        .bind(payload.featured.is_some())
+        .bind(thumbnail_url)
+        .bind(focus.x)
+        .bind(focus.y)
+        .bind(&social_image_url)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        if let Some(old_slug) = old_slug.filter(|old| *old != slug) {
+            redirects::record_slug_change(&mut tx, id, &old_slug, &slug).await?;
+        }
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(old_card.flatten())
     }
-    let start_date = parse_date(&payload.start_date);
-    let end_date = parse_date_option(payload.end_date);
-
-    sqlx::query(
-        "UPDATE projects SET title = $1, slug = $2, description = $3, start_date = $4, end_date = $5, updated_at = NOW() WHERE id = $6"
-    )
-    .bind(payload.title)
-    .bind(payload.slug)
-    .bind(payload.description)
-    .bind(start_date)
-    .bind(end_date)
-    .bind(id)
-    .execute(&pool)
-    .await
-    .unwrap();
+    .await;
 
-    Redirect::to("/admin/dashboard").into_response()
+    match saved {
+        Ok(old_card) => {
+            if let Some(old_card) = old_card.filter(|old| Some(old) != social_image_url.as_ref()) {
+                crate::thumbnail::remove_unused_social_card(&pool, &storage, &old_card).await;
+            }
+            flash_saved(&session, &geocoder, &payload, location, "saved").await;
+            Ok(Redirect::to("/admin/dashboard").into_response())
+        }
+        Err(e) => save_error(&pool, &geocoder, &session, Some(id), &payload, e).await,
+    }
 }
 
 pub async fn delete_project(
     State(pool): State<PgPool>,
+    State(storage): State<Storage>,
     Path(id): Path<Uuid>,
     session: Session,
     Form(form): Form<DeleteForm>,
-) -> impl IntoResponse {
-    if !verify_csrf_token(&session, &form.authenticity_token).await {
-        return (axum::http::StatusCode::FORBIDDEN, "Invalid CSRF Token").into_response();
+) -> Result<Redirect, AppError> {
+    require_csrf_token(&session, &form.authenticity_token).await?;
+    if let Some(card) = remove_project(&pool, id).await? {
+        crate::thumbnail::remove_unused_social_card(&pool, &storage, &card).await;
     }
+
+    flash::success(&session, "Project deleted").await;
+    Ok(Redirect::to("/admin/dashboard"))
+}
+
+/// Deletes a project, leaving its addresses answering 410 Gone. Returns the
+/// social card it had.
+async fn remove_project(pool: &PgPool, id: Uuid) -> Result<Option<String>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let project: Option<(String, Option<String>)> = sqlx::query_as("SELECT slug, social_image_url FROM projects WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let Some((slug, card)) = project else {
+        return Ok(None);
+    };
+    redirects::record_deletion(&mut tx, id, &slug).await?;
     sqlx::query("DELETE FROM projects WHERE id = $1")
         .bind(id)
-        .execute(&pool)
-        .await
-        .unwrap();
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(card)
+}
 
-    Redirect::to("/admin/dashboard").into_response()
+pub async fn duplicate_project(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    session: Session,
+    Form(form): Form<DuplicateForm>,
+) -> Result<Redirect, AppError> {
+    require_csrf_token(&session, &form.authenticity_token).await?;
+    let copy_id = copy_project_retrying(&pool, id).await?.ok_or(AppError::NotFound("Project"))?;
+    // Straight to the form, since the copy wants a proper title and slug.
+    flash::info(&session, "This is the copy. Give it its own title and slug").await;
+    Ok(Redirect::to(&format!("/admin/projects/edit/{}", copy_id)))
+}
+
+/// How often a copy is tried again when another save takes its slug first.
+const COPY_ATTEMPTS: usize = 3;
+
+/// [`copy_project`], trying the next free slug when the one picked was taken
+/// between looking and inserting.
+async fn copy_project_retrying(pool: &PgPool, id: Uuid) -> Result<Option<Uuid>, AppError> {
+    for _ in 0..COPY_ATTEMPTS {
+        match copy_project(pool, id).await {
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => continue,
+            result => return Ok(result?),
+        }
+    }
+    Err(AppError::Conflict("Another project took the copy's slug. Try again".to_string()))
+}
+
+/// Copies the project and all of its blocks, returning the copy's id.
+async fn copy_project(pool: &PgPool, id: Uuid) -> Result<Option<Uuid>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let Some(project) = sqlx::query_as::<_, Project>("SELECT * FROM projects WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+    else {
+        return Ok(None);
+    };
+    let taken: Vec<String> = sqlx::query_scalar("SELECT slug FROM projects")
+        .fetch_all(&mut *tx)
+        .await?;
+
+    let copy_id = Uuid::new_v4();
+    let slug = project.copy_slug(&taken);
+    // An old address at the copy's slug would hide it, as for a new project.
+    redirects::release(&mut tx, &redirects::project_path(&slug)).await?;
+    sqlx::query(
+        "INSERT INTO projects (id, title, slug, description, start_date, end_date, thumbnail_url, thumbnail_focus_x, thumbnail_focus_y, social_image_url, location_name, latitude, longitude)
+         SELECT $1, title || ' (copy)', $2, description, start_date, end_date, thumbnail_url, thumbnail_focus_x, thumbnail_focus_y, social_image_url, location_name, latitude, longitude FROM projects WHERE id = $3"
+    )
+    .bind(copy_id)
+    .bind(&slug)
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO content_blocks (id, project_id, block_type, content, sort_order, schema_version, search_text)
+         SELECT gen_random_uuid(), $1, block_type, content, sort_order, schema_version, search_text FROM content_blocks WHERE project_id = $2"
+    )
+    .bind(copy_id)
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(Some(copy_id))
+}
+
+pub async fn project_order(
+    State(pool): State<PgPool>,
+    session: Session,
+) -> Result<ProjectOrderTemplate, AppError> {
+    // Always the manual order, since that's what is edited here.
+    let projects = sqlx::query_as::<_, Project>(&format!("SELECT * FROM projects ORDER BY {}", IndexOrder::Manual.order_by()))
+        .fetch_all(&pool)
+        .await?;
+
+    let csrf_token = get_or_create_csrf_token(&session).await;
+    Ok(ProjectOrderTemplate {
+        projects,
+        index_order: SiteSettings::load(&pool).await.index_order,
+        flashes: flash::take(&session).await,
+        authenticity_token: csrf_token,
+    })
 }
 
 // --- Block CRUD ---
@@ -154,29 +570,30 @@ pub async fn project_blocks(
     State(pool): State<PgPool>,
     Path(project_id): Path<Uuid>,
     session: Session,
-) -> impl IntoResponse {
+) -> Result<ProjectBlocksTemplate, AppError> {
     // Fetch project title
     let project = sqlx::query_as::<_, Project>("SELECT * FROM projects WHERE id = $1")
         .bind(project_id)
-        .fetch_one(&pool)
-        .await
-        .unwrap(); 
+        .fetch_optional(&pool)
+        .await?
+        .ok_or(AppError::NotFound("Project"))?;
 
     let blocks = sqlx::query_as::<_, ContentBlock>(
         "SELECT * FROM content_blocks WHERE project_id = $1 ORDER BY sort_order ASC"
     )
     .bind(project_id)
     .fetch_all(&pool)
-    .await
-    .unwrap(); 
+    .await?;
 
     let csrf_token = get_or_create_csrf_token(&session).await;
-    ProjectBlocksTemplate {
+    Ok(ProjectBlocksTemplate {
         project_id,
         project_title: project.title,
         blocks,
+        move_targets: move_targets(&pool, project_id).await,
+        flashes: flash::take(&session).await,
         authenticity_token: csrf_token,
-    }
+    })
 }
 
 #[derive(Deserialize)]
@@ -186,17 +603,29 @@ pub struct NewBlockQuery {
 }
 
 pub async fn new_block(
+    State(pool): State<PgPool>,
+    State(oembed): State<OEmbedRegistry>,
     Path(project_id): Path<Uuid>,
     session: Session,
     Query(query): Query<NewBlockQuery>,
-) -> impl IntoResponse {
-    let csrf_token = get_or_create_csrf_token(&session).await;
+) -> Result<BlockFormTemplate, AppError> {
+    let block_type = parse_block_type(&query.block_type)?;
+    Ok(block_form(&pool, &oembed, &session, project_id, block_type).await)
+}
+
+/// An empty form for a new block of `block_type`.
+async fn block_form(pool: &PgPool, oembed: &OEmbedRegistry, session: &Session, project_id: Uuid, block_type: BlockKind) -> BlockFormTemplate {
+    let csrf_token = get_or_create_csrf_token(session).await;
     BlockFormTemplate {
         project_id,
         block_id: None,
-        block_type: query.block_type,
-        sort_order: 0,
+        block_type,
+        sort_order: "0".to_string(),
         content: String::new(),
+        errors: FieldErrors::new(),
+        embed_providers: provider_names(oembed),
+        media_videos: crate::media::list_by_type(pool, "video/").await,
+        flashes: flash::take(session).await,
         authenticity_token: csrf_token,
     }
 }
@@ -204,125 +633,161 @@ pub async fn new_block(
 #[derive(Deserialize)]
 pub struct BlockForm {
     pub block_type: String,
-    pub sort_order: i32,
+    pub sort_order: String,
     pub content: String,
     pub authenticity_token: String,
 }
 
+impl BlockForm {
+    /// Checks the submitted block, turning its content into what's stored.
+    async fn validate(&self, pool: &PgPool, oembed: &OEmbedRegistry, geocoder: &Geocoder, kind: BlockKind) -> Result<(i32, BlockContent), FieldErrors> {
+        let mut errors = FieldErrors::new();
+        let sort_order = match validation::required(&mut errors, "sort_order", "Sort order", &self.sort_order).parse() {
+            Ok(sort_order) => Some(sort_order),
+            Err(_) => {
+                errors.add("sort_order", "Sort order must be a whole number");
+                None
+            }
+        };
+        let content = match form_to_block_content(pool, oembed, geocoder, kind, &self.content).await {
+            Ok(content) => Some(content),
+            Err(message) => {
+                errors.add("content", message);
+                None
+            }
+        };
+        match (sort_order, content) {
+            (Some(sort_order), Some(content)) => Ok((sort_order, content)),
+            _ => Err(errors),
+        }
+    }
+
+    /// The block form again, with what was submitted and what's wrong with it.
+    fn refused(&self, form: BlockFormTemplate, errors: FieldErrors) -> Response {
+        (
+            axum::http::StatusCode::UNPROCESSABLE_ENTITY,
+            BlockFormTemplate {
+                sort_order: self.sort_order.clone(),
+                content: self.content.clone(),
+                errors,
+                ..form
+            },
+        ).into_response()
+    }
+}
+
 pub async fn create_block(
     State(pool): State<PgPool>,
+    State(oembed): State<OEmbedRegistry>,
+    State(geocoder): State<Geocoder>,
     Path(project_id): Path<Uuid>,
     headers: HeaderMap,
     session: Session,
     Form(form): Form<BlockForm>,
-) -> Response {
-    if !verify_csrf_token(&session, &form.authenticity_token).await {
-        return (axum::http::StatusCode::FORBIDDEN, "Invalid CSRF Token").into_response();
-    }
-    // Debug logging
-    println!("=== CREATE BLOCK DEBUG ===");
-    println!("Block type: {}", form.block_type);
-    println!("Content length: {}", form.content.len());
-    println!("Content preview: {:?}", &form.content.chars().take(200).collect::<String>());
-    
-    let content_enum = form_to_block_content(&form.block_type, &form.content);
+) -> Result<Response, AppError> {
+    require_csrf_token(&session, &form.authenticity_token).await?;
+    tracing::debug!("Creating a {} block, {} bytes of content", form.block_type, form.content.len());
+
+    let kind = parse_block_type(&form.block_type)?;
+    let (sort_order, content_enum) = match form.validate(&pool, &oembed, &geocoder, kind).await {
+        Ok(valid) => valid,
+        Err(errors) => return Ok(form.refused(block_form(&pool, &oembed, &session, project_id, kind).await, errors)),
+    };
 
     sqlx::query(
-        "INSERT INTO content_blocks (id, project_id, block_type, content, sort_order) VALUES ($1, $2, $3, $4, $5)"
+        "INSERT INTO content_blocks (id, project_id, block_type, content, sort_order, schema_version, search_text) VALUES ($1, $2, $3, $4, $5, $6, $7)"
     )
     .bind(Uuid::new_v4())
     .bind(project_id)
-    .bind(form.block_type)
-    .bind(sqlx::types::Json(content_enum))
-    .bind(form.sort_order)
+    .bind(content_enum.kind())
+    .bind(sqlx::types::Json(&content_enum))
+    .bind(sort_order)
+    .bind(crate::block_schema::CURRENT_VERSION)
+    .bind(search::block_text(&content_enum))
     .execute(&pool)
     .await
-    .unwrap();
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_foreign_key_violation() => AppError::NotFound("Project"),
+        e => e.into(),
+    })?;
+    flash::success(&session, "Block added").await;
 
     // Check if this is an HTMX request
     if headers.get("hx-request").is_some() {
         // Return the blocks list HTML for HTMX to swap
         // We reuse recent token. Ideally for HTMX we might want a new one if rotated.
         let csrf_token = get_or_create_csrf_token(&session).await;
-        render_blocks_list(pool, project_id, &csrf_token).await.into_response()
+        Ok(render_blocks_list(pool, project_id, &csrf_token).await?.into_response())
     } else {
         // Regular form submission, redirect
-        Redirect::to(&format!("/admin/projects/{}/blocks", project_id)).into_response()
+        Ok(Redirect::to(&format!("/admin/projects/{}/blocks", project_id)).into_response())
     }
 }
 
 pub async fn edit_block(
     State(pool): State<PgPool>,
+    State(oembed): State<OEmbedRegistry>,
     Path(block_id): Path<Uuid>,
     session: Session,
-) -> impl IntoResponse {
-    let block = sqlx::query_as::<_, ContentBlock>(
-        "SELECT * FROM content_blocks WHERE id = $1"
-    )
-    .bind(block_id)
-    .fetch_one(&pool)
-    .await
-    .unwrap();
-
-    let (content_str, _extra) = match block.content.0 {
-        BlockContent::Text(s) => (s, String::new()),
-        BlockContent::Video(s) => (s, String::new()),
-        BlockContent::Gallery(urls) => (serde_json::to_string(&urls).unwrap_or_default(), String::new()),
-        BlockContent::Audio(items) => (serde_json::to_string(&items).unwrap_or_default(), String::new()),
-        BlockContent::File(items) => (serde_json::to_string(&items).unwrap_or_default(), String::new()),
-    };
+) -> Result<BlockFormTemplate, AppError> {
+    let block = find_block(&pool, block_id).await?;
 
-    let csrf_token = get_or_create_csrf_token(&session).await;
-    BlockFormTemplate {
-        project_id: block.project_id,
+    Ok(BlockFormTemplate {
         block_id: Some(block.id),
-        block_type: block.block_type,
-        sort_order: block.sort_order,
-        content: content_str,
-        authenticity_token: csrf_token,
-    }
+        sort_order: block.sort_order.to_string(),
+        content: block_to_form_content(block.content.0),
+        ..block_form(&pool, &oembed, &session, block.project_id, block.block_type).await
+    })
 }
 
 pub async fn update_block(
     State(pool): State<PgPool>,
+    State(oembed): State<OEmbedRegistry>,
+    State(geocoder): State<Geocoder>,
     Path(block_id): Path<Uuid>,
     headers: HeaderMap,
     session: Session,
     Form(form): Form<BlockForm>,
-) -> Response {
-    if !verify_csrf_token(&session, &form.authenticity_token).await {
-        return (axum::http::StatusCode::FORBIDDEN, "Invalid CSRF Token").into_response();
-    }
+) -> Result<Response, AppError> {
+    require_csrf_token(&session, &form.authenticity_token).await?;
     // Fetch block to get project_id
-    let block = sqlx::query_as::<_, ContentBlock>(
-        "SELECT * FROM content_blocks WHERE id = $1"
-    )
-    .bind(block_id)
-    .fetch_one(&pool)
-    .await
-    .unwrap();
+    let block = find_block(&pool, block_id).await?;
 
-    let content_enum = form_to_block_content(&form.block_type, &form.content);
+    // A block's kind is fixed when it is created.
+    if BlockKind::parse(&form.block_type) != Some(block.block_type) {
+        return Err(unknown_block_type(&form.block_type));
+    }
+    let (sort_order, content_enum) = match form.validate(&pool, &oembed, &geocoder, block.block_type).await {
+        Ok(valid) => valid,
+        Err(errors) => {
+            let page = BlockFormTemplate {
+                block_id: Some(block.id),
+                ..block_form(&pool, &oembed, &session, block.project_id, block.block_type).await
+            };
+            return Ok(form.refused(page, errors));
+        }
+    };
 
     sqlx::query(
-        "UPDATE content_blocks SET sort_order = $1, content = $2 WHERE id = $3"
+        "UPDATE content_blocks SET sort_order = $1, content = $2, schema_version = $3, search_text = $4 WHERE id = $5"
     )
-    .bind(form.sort_order)
-    .bind(sqlx::types::Json(content_enum))
+    .bind(sort_order)
+    .bind(sqlx::types::Json(&content_enum))
+    .bind(crate::block_schema::CURRENT_VERSION)
+    .bind(search::block_text(&content_enum))
     .bind(block_id)
     .execute(&pool)
-    .await
-    .unwrap();
+    .await?;
+    flash::success(&session, "Block saved").await;
 
     // Check if this is an HTMX request
     if headers.get("hx-request").is_some() {
-        // Return the blocks list HTML for HTMX to swap
         // Return the blocks list HTML for HTMX to swap
         let csrf_token = get_or_create_csrf_token(&session).await;
-        render_blocks_list(pool, block.project_id, &csrf_token).await.into_response()
+        Ok(render_blocks_list(pool, block.project_id, &csrf_token).await?.into_response())
     } else {
         // Regular form submission, redirect
-        Redirect::to(&format!("/admin/projects/{}/blocks", block.project_id)).into_response()
+        Ok(Redirect::to(&format!("/admin/projects/{}/blocks", block.project_id)).into_response())
     }
 }
 
@@ -331,25 +796,124 @@ pub async fn delete_block(
     Path(block_id): Path<Uuid>,
     session: Session,
     Form(form): Form<DeleteForm>,
-) -> impl IntoResponse {
-    if !verify_csrf_token(&session, &form.authenticity_token).await {
-        return (axum::http::StatusCode::FORBIDDEN, "Invalid CSRF Token").into_response();
+) -> Result<Redirect, AppError> {
+    require_csrf_token(&session, &form.authenticity_token).await?;
+    let block = find_block(&pool, block_id).await?;
+
+    sqlx::query("DELETE FROM content_blocks WHERE id = $1")
+        .bind(block_id)
+        .execute(&pool)
+        .await?;
+    flash::success(&session, "Block deleted").await;
+
+    Ok(Redirect::to(&format!("/admin/projects/{}/blocks", block.project_id)))
+}
+
+pub async fn duplicate_block(
+    State(pool): State<PgPool>,
+    Path(block_id): Path<Uuid>,
+    headers: HeaderMap,
+    session: Session,
+    Form(form): Form<DuplicateForm>,
+) -> Result<Response, AppError> {
+    require_csrf_token(&session, &form.authenticity_token).await?;
+    let project_id = copy_block(&pool, block_id).await?.ok_or(AppError::NotFound("Block"))?;
+    flash::success(&session, "Block duplicated").await;
+
+    if headers.get("hx-request").is_some() {
+        let csrf_token = get_or_create_csrf_token(&session).await;
+        Ok(render_blocks_list(pool, project_id, &csrf_token).await?.into_response())
+    } else {
+        Ok(Redirect::to(&format!("/admin/projects/{}/blocks", project_id)).into_response())
     }
-    let block = sqlx::query_as::<_, ContentBlock>(
-        "SELECT * FROM content_blocks WHERE id = $1"
+}
+
+/// Inserts a copy of the block directly below it, returning its project's id.
+async fn copy_block(pool: &PgPool, block_id: Uuid) -> Result<Option<Uuid>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let Some(block) = sqlx::query_as::<_, ContentBlock>("SELECT * FROM content_blocks WHERE id = $1 FOR UPDATE")
+        .bind(block_id)
+        .fetch_optional(&mut *tx)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    sqlx::query("UPDATE content_blocks SET sort_order = sort_order + 1 WHERE project_id = $1 AND sort_order > $2")
+        .bind(block.project_id)
+        .bind(block.sort_order)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "INSERT INTO content_blocks (id, project_id, block_type, content, sort_order, schema_version, search_text)
+         SELECT $1, project_id, block_type, content, sort_order + 1, schema_version, search_text FROM content_blocks WHERE id = $2"
     )
+    .bind(Uuid::new_v4())
     .bind(block_id)
-    .fetch_one(&pool)
-    .await
-    .unwrap();
+    .execute(&mut *tx)
+    .await?;
 
-    sqlx::query("DELETE FROM content_blocks WHERE id = $1")
+    tx.commit().await?;
+    Ok(Some(block.project_id))
+}
+
+pub async fn move_block(
+    State(pool): State<PgPool>,
+    Path(block_id): Path<Uuid>,
+    session: Session,
+    Form(form): Form<MoveBlockForm>,
+) -> Result<Redirect, AppError> {
+    require_csrf_token(&session, &form.authenticity_token).await?;
+    let from = move_block_to(&pool, block_id, form.project_id)
+        .await?
+        .ok_or(AppError::NotFound("Block or project"))?;
+    let target: Option<String> = sqlx::query_scalar("SELECT title FROM projects WHERE id = $1")
+        .bind(form.project_id)
+        .fetch_optional(&pool)
+        .await?;
+    if let Some(title) = target {
+        flash::success(&session, format!("Block moved to “{}”", title)).await;
+    }
+    // Back to the project the block came from.
+    Ok(Redirect::to(&format!("/admin/projects/{}/blocks", from)))
+}
+
+/// Moves the block to the end of another project, returning the project it
+/// was moved out of.
+async fn move_block_to(pool: &PgPool, block_id: Uuid, project_id: Uuid) -> Result<Option<Uuid>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let Some(block) = sqlx::query_as::<_, ContentBlock>("SELECT * FROM content_blocks WHERE id = $1 FOR UPDATE")
         .bind(block_id)
-        .execute(&pool)
-        .await
-        .unwrap();
+        .fetch_optional(&mut *tx)
+        .await?
+    else {
+        return Ok(None);
+    };
+    if block.project_id == project_id {
+        return Ok(Some(project_id));
+    }
+    // Locking the target makes concurrent moves into it take turns, so
+    // each one sees the others' blocks when picking the next position.
+    let target: Option<Uuid> = sqlx::query_scalar("SELECT id FROM projects WHERE id = $1 FOR UPDATE")
+        .bind(project_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    if target.is_none() {
+        return Ok(None);
+    }
+
+    sqlx::query(
+        "UPDATE content_blocks SET project_id = $1,
+             sort_order = (SELECT COALESCE(MAX(sort_order) + 1, 0) FROM content_blocks WHERE project_id = $1)
+         WHERE id = $2"
+    )
+    .bind(project_id)
+    .bind(block_id)
+    .execute(&mut *tx)
+    .await?;
 
-    Redirect::to(&format!("/admin/projects/{}/blocks", block.project_id)).into_response()
+    tx.commit().await?;
+    Ok(Some(block.project_id))
 }
 
 // --- Settings ---
@@ -358,21 +922,162 @@ pub async fn settings(
     State(pool): State<PgPool>,
     session: Session,
 ) -> impl IntoResponse {
+    settings_page(&pool, &session, current_username(&pool).await, FieldErrors::new()).await
+}
+
+async fn current_username(pool: &PgPool) -> String {
     // Assuming single user system, fetch the first user
     let user = sqlx::query_as::<_, User>("SELECT * FROM users LIMIT 1")
-        .fetch_one(&pool)
+        .fetch_one(pool)
         .await
         .ok();
 
-    let current_username = user.map(|u| u.username).unwrap_or_default();
+    user.map(|u| u.username).unwrap_or_default()
+}
 
-    let csrf_token = get_or_create_csrf_token(&session).await;
+async fn settings_page(pool: &PgPool, session: &Session, current_username: String, errors: FieldErrors) -> SettingsTemplate {
+    let csrf_token = get_or_create_csrf_token(session).await;
     SettingsTemplate { 
         current_username,
+        errors,
+        site: SiteSettings::load(pool).await,
+        site_title: Site::global().title.clone(),
+        index_orders: IndexOrder::ALL,
+        search_languages: SearchLanguage::ALL,
+        flashes: flash::take(session).await,
         authenticity_token: csrf_token,
     }
 }
 
+#[derive(Deserialize)]
+pub struct SiteSettingsForm {
+    pub index_order: String,
+    pub search_language: String,
+    #[serde(default)]
+    pub robots_rules: String,
+    #[serde(default)]
+    pub artist_name: String,
+    #[serde(default)]
+    pub artist_links: String,
+    pub authenticity_token: String,
+}
+
+pub async fn update_site_settings(
+    State(pool): State<PgPool>,
+    session: Session,
+    Form(payload): Form<SiteSettingsForm>,
+) -> Result<Response, AppError> {
+    require_csrf_token(&session, &payload.authenticity_token).await?;
+    let index_order = IndexOrder::parse(&payload.index_order)
+        .ok_or_else(|| AppError::Validation(format!("Unknown project order: {:?}", payload.index_order)))?;
+
+    let search_language = SearchLanguage::parse(&payload.search_language)
+        .ok_or_else(|| AppError::Validation(format!("Unknown search language: {:?}", payload.search_language)))?;
+
+    let mut errors = FieldErrors::new();
+    let robots_rules = robots::validate(&mut errors, &payload.robots_rules);
+    let artist_links = validation::web_links(&mut errors, "artist_links", &payload.artist_links);
+
+    let mut site = SiteSettings::load(&pool).await;
+    let reindex = site.search_language != search_language;
+    site.index_order = index_order;
+    site.search_language = search_language;
+    site.robots_rules = robots_rules;
+    site.artist_name = payload.artist_name.trim().to_string();
+    site.artist_links = artist_links;
+    if !errors.is_empty() {
+        let mut page = settings_page(&pool, &session, current_username(&pool).await, errors).await;
+        page.site = SiteSettings { robots_rules: payload.robots_rules, artist_links: payload.artist_links, ..site };
+        return Ok((axum::http::StatusCode::UNPROCESSABLE_ENTITY, page).into_response());
+    }
+    site.save(&pool).await?;
+    // The stored vectors were stemmed in the old language.
+    if reindex {
+        search::reindex(&pool).await?;
+    }
+
+    flash::success(&session, "Settings saved").await;
+    Ok(Redirect::to("/admin/settings").into_response())
+}
+
+// --- Redirects ---
+
+#[derive(Deserialize)]
+pub struct RedirectForm {
+    pub from_path: String,
+    pub to: String,
+    pub authenticity_token: String,
+}
+
+async fn redirects_page(pool: &PgPool, session: &Session, form: Option<&RedirectForm>, error: Option<String>) -> Result<RedirectsTemplate, AppError> {
+    Ok(RedirectsTemplate {
+        redirects: redirects::all(pool).await?,
+        from_path: form.map(|f| f.from_path.clone()).unwrap_or_default(),
+        to: form.map(|f| f.to.clone()).unwrap_or_default(),
+        error,
+        flashes: flash::take(session).await,
+        authenticity_token: get_or_create_csrf_token(session).await,
+    })
+}
+
+pub async fn redirects_list(
+    State(pool): State<PgPool>,
+    session: Session,
+) -> Result<RedirectsTemplate, AppError> {
+    redirects_page(&pool, &session, None, None).await
+}
+
+pub async fn create_redirect(
+    State(pool): State<PgPool>,
+    session: Session,
+    Form(payload): Form<RedirectForm>,
+) -> Result<Response, AppError> {
+    require_csrf_token(&session, &payload.authenticity_token).await?;
+    let checked = redirects::validate_from(&payload.from_path)
+        .and_then(|from| Ok((redirects::validate_to(&payload.to, &from)?, from)));
+    let (to, from) = match checked {
+        Ok(checked) => checked,
+        Err(e) => {
+            let page = redirects_page(&pool, &session, Some(&payload), Some(e)).await?;
+            return Ok((axum::http::StatusCode::UNPROCESSABLE_ENTITY, page).into_response());
+        }
+    };
+
+    let saved = sqlx::query("INSERT INTO redirects (from_path, to_path) VALUES ($1, $2)")
+        .bind(&from)
+        .bind(&to)
+        .execute(&pool)
+        .await;
+    match saved {
+        Ok(_) => {
+            flash::success(&session, format!("Redirect from {} added", from)).await;
+            Ok(Redirect::to("/admin/redirects").into_response())
+        }
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            let error = Some(format!("There is already a redirect from {}", from));
+            let page = redirects_page(&pool, &session, Some(&payload), error).await?;
+            Ok((axum::http::StatusCode::UNPROCESSABLE_ENTITY, page).into_response())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn delete_redirect(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    session: Session,
+    Form(form): Form<DeleteForm>,
+) -> Result<Redirect, AppError> {
+    require_csrf_token(&session, &form.authenticity_token).await?;
+    sqlx::query("DELETE FROM redirects WHERE id = $1")
+        .bind(id)
+        .execute(&pool)
+        .await?;
+    flash::success(&session, "Redirect removed").await;
+
+    Ok(Redirect::to("/admin/redirects"))
+}
+
 #[derive(Deserialize)]
 pub struct CredentialsForm {
     pub username: String,
@@ -380,16 +1085,33 @@ pub struct CredentialsForm {
     pub authenticity_token: String,
 }
 
+impl CredentialsForm {
+    /// The username to save, or what's wrong with the form. The password
+    /// is used as typed.
+    fn validate(&self) -> Result<&str, FieldErrors> {
+        let mut errors = FieldErrors::new();
+        let username = validation::required(&mut errors, "username", "Username", &self.username);
+        validation::password(&mut errors, &self.password);
+        errors.check(username)
+    }
+}
+
 pub async fn update_credentials(
     State(pool): State<PgPool>,
     session: Session,
     Form(payload): Form<CredentialsForm>,
-) -> impl IntoResponse {
-    if !verify_csrf_token(&session, &payload.authenticity_token).await {
-        return (axum::http::StatusCode::FORBIDDEN, "Invalid CSRF Token").into_response();
-    }
+) -> Result<Response, AppError> {
+    require_csrf_token(&session, &payload.authenticity_token).await?;
+    let username = match payload.validate() {
+        Ok(username) => username,
+        Err(errors) => {
+            let page = settings_page(&pool, &session, payload.username.clone(), errors).await;
+            return Ok((axum::http::StatusCode::UNPROCESSABLE_ENTITY, page).into_response());
+        }
+    };
     // Hash password
-    let password_hash = crate::models::hash_password(&payload.password).unwrap();
+    let password_hash = crate::models::hash_password(&payload.password)
+        .map_err(|e| AppError::Internal(format!("Hashing the password failed: {}", e)))?;
 
     // Update the first user found (or specific ID if we had session)
     // For now, we update the single user record
@@ -399,11 +1121,10 @@ pub async fn update_credentials(
     let rows_affected = sqlx::query(
         "UPDATE users SET username = $1, password_hash = $2 WHERE id = (SELECT id FROM users LIMIT 1)"
     )
-    .bind(&payload.username)
+    .bind(username)
     .bind(&password_hash)
     .execute(&pool)
-    .await
-    .unwrap()
+    .await?
     .rows_affected();
 
     if rows_affected == 0 {
@@ -412,14 +1133,14 @@ pub async fn update_credentials(
             "INSERT INTO users (id, username, password_hash) VALUES ($1, $2, $3)"
         )
         .bind(Uuid::new_v4())
-        .bind(&payload.username)
+        .bind(username)
         .bind(&password_hash)
         .execute(&pool)
-        .await
-        .unwrap();
+        .await?;
     }
 
-    Redirect::to("/admin/dashboard").into_response()
+    flash::success(&session, "Credentials updated").await;
+    Ok(Redirect::to("/admin/dashboard").into_response())
 }
 
 // --- Pages ---
@@ -427,40 +1148,49 @@ pub async fn update_credentials(
 pub async fn pages_list(
     State(pool): State<PgPool>,
     session: Session,
-) -> impl IntoResponse {
+) -> Result<crate::templates::PagesListTemplate, AppError> {
     let pages = sqlx::query_as::<_, crate::models::Page>("SELECT * FROM pages ORDER BY slug ASC")
         .fetch_all(&pool)
-        .await
-        .unwrap_or_default();
+        .await?;
 
     let csrf_token = get_or_create_csrf_token(&session).await;
-    crate::templates::PagesListTemplate { 
+    Ok(crate::templates::PagesListTemplate { 
         pages,
+        flashes: flash::take(&session).await,
         authenticity_token: csrf_token,
-    }
+    })
 }
 
 pub async fn edit_page(
     State(pool): State<PgPool>,
     Path(slug): Path<String>,
     session: Session,
-) -> impl IntoResponse {
-    let page = sqlx::query_as::<_, crate::models::Page>("SELECT * FROM pages WHERE slug = $1")
-        .bind(slug)
-        .fetch_one(&pool)
-        .await
-        .unwrap();
+) -> Result<crate::templates::PageFormTemplate, AppError> {
+    let page = find_page(&pool, &slug).await?;
 
     let csrf_token = get_or_create_csrf_token(&session).await;
-    crate::templates::PageFormTemplate { 
+    Ok(crate::templates::PageFormTemplate { 
         page,
+        errors: FieldErrors::new(),
+        flashes: flash::take(&session).await,
         authenticity_token: csrf_token,
-    }
+    })
+}
+
+async fn find_page(pool: &PgPool, slug: &str) -> Result<crate::models::Page, AppError> {
+    sqlx::query_as::<_, crate::models::Page>("SELECT * FROM pages WHERE slug = $1")
+        .bind(slug)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(AppError::NotFound("Page"))
 }
 
 #[derive(Deserialize)]
 pub struct PageForm {
     pub title: String,
+    #[serde(default)]
+    pub format: TextFormat,
+    /// The page source in `format`.
     pub content: String,
     pub authenticity_token: String,
 }
@@ -470,109 +1200,400 @@ pub async fn update_page(
     Path(slug): Path<String>,
     session: Session,
     Form(form): Form<PageForm>,
-) -> impl IntoResponse {
-    if !verify_csrf_token(&session, &form.authenticity_token).await {
-        return (axum::http::StatusCode::FORBIDDEN, "Invalid CSRF Token").into_response();
+) -> Result<Response, AppError> {
+    require_csrf_token(&session, &form.authenticity_token).await?;
+    let text = TextBlock::new(form.format, &form.content);
+    let mut errors = FieldErrors::new();
+    validation::required(&mut errors, "title", "Title", &form.title);
+    if !errors.is_empty() {
+        // The saved page, with what was submitted in place of its text.
+        let page = crate::models::Page {
+            title: form.title,
+            format: text.format,
+            source: text.source,
+            content: text.html,
+            ..find_page(&pool, &slug).await?
+        };
+        let csrf_token = get_or_create_csrf_token(&session).await;
+        let page = crate::templates::PageFormTemplate { page, errors, flashes: flash::take(&session).await, authenticity_token: csrf_token };
+        return Ok((axum::http::StatusCode::UNPROCESSABLE_ENTITY, page).into_response());
     }
-    sqlx::query("UPDATE pages SET title = $1, content = $2, updated_at = NOW() WHERE slug = $3")
+    let search_text = text.plain_text();
+    let updated = sqlx::query("UPDATE pages SET title = $1, format = $2, source = $3, content = $4, search_text = $5, updated_at = NOW() WHERE slug = $6")
         .bind(form.title)
-        .bind(form.content)
+        .bind(text.format)
+        .bind(text.source)
+        .bind(text.html)
+        .bind(search_text)
         .bind(slug)
         .execute(&pool)
-        .await
-        .unwrap();
-    Redirect::to("/admin/pages").into_response()
+        .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::NotFound("Page"));
+    }
+    flash::success(&session, "Page saved").await;
+    Ok(Redirect::to("/admin/pages").into_response())
 }
 
 // --- Helpers ---
 
-fn parse_date(s: &str) -> Date {
-    Date::parse(s, &time::format_description::well_known::Iso8601::DEFAULT).unwrap_or(Date::MIN)
+/// The inverse of `form_to_block_content`: what the editor starts from.
+pub(crate) fn block_to_form_content(content: BlockContent) -> String {
+    match content {
+        BlockContent::Text(text) => serde_json::to_string(&TextForm { format: text.format, source: text.source }).unwrap_or_default(),
+        BlockContent::Video(s) => s,
+        BlockContent::Gallery(urls) => serde_json::to_string(&urls).unwrap_or_default(),
+        BlockContent::Audio(items) => serde_json::to_string(&items).unwrap_or_default(),
+        BlockContent::File(items) => serde_json::to_string(&items).unwrap_or_default(),
+        BlockContent::Embed(embed) => embed.url,
+        BlockContent::HostedVideo(video) => {
+            let form = HostedVideoForm { media_id: Some(video.media_id), captions: video.captions };
+            serde_json::to_string(&form).unwrap_or_default()
+        },
+        BlockContent::Quote(quote) => serde_json::to_string(&quote).unwrap_or_default(),
+        BlockContent::Credits(credits) => serde_json::to_string(&credits).unwrap_or_default(),
+        BlockContent::LinkList(links) => serde_json::to_string(&links).unwrap_or_default(),
+        BlockContent::Map(markers) => serde_json::to_string(&markers).unwrap_or_default(),
+        BlockContent::Columns(layout) => {
+            let columns = layout
+                .columns
+                .into_iter()
+                .map(|column| {
+                    column
+                        .into_iter()
+                        .map(|child| ChildBlockForm { block_type: child.kind().name().to_string(), content: block_to_form_content(child) })
+                        .collect()
+                })
+                .collect();
+            serde_json::to_string(&ColumnsForm { columns }).unwrap_or_default()
+        },
+    }
+}
+
+/// What the block editor submits for a Columns block: every child is a
+/// type and content string, exactly as a top-level block form would send.
+#[derive(Serialize, Deserialize)]
+struct ColumnsForm {
+    columns: Vec<Vec<ChildBlockForm>>,
 }
 
-fn parse_date_option(s: Option<String>) -> Option<Date> {
-    s.and_then(|d| Date::parse(&d, &time::format_description::well_known::Iso8601::DEFAULT).ok())
+#[derive(Serialize, Deserialize)]
+struct ChildBlockForm {
+    block_type: String,
+    content: String,
+}
+
+/// A Map block marker as the editor submits it: coordinates, or an address
+/// to geocode when they are left empty.
+#[derive(Deserialize)]
+struct MapMarkerForm {
+    #[serde(default)]
+    label: String,
+    #[serde(default)]
+    lat: Option<f64>,
+    #[serde(default)]
+    lng: Option<f64>,
+    #[serde(default)]
+    address: Option<String>,
+    #[serde(default)]
+    url: Option<String>,
 }
 
-fn form_to_block_content(block_type: &str, content: &str) -> BlockContent {
-    match block_type.to_lowercase().as_str() {
-        "text" => BlockContent::Text(content.to_string()),
-        "video" => BlockContent::Video(content.to_string()),
-        "gallery" => {
-            let items: Vec<String> = serde_json::from_str(content).unwrap_or_default();
+/// What the block editor submits for a Text block.
+#[derive(Serialize, Deserialize)]
+struct TextForm {
+    #[serde(default)]
+    format: TextFormat,
+    source: String,
+}
+
+/// What the block editor submits for a HostedVideo block.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct HostedVideoForm {
+    pub media_id: Option<Uuid>,
+    #[serde(default)]
+    pub captions: Vec<CaptionTrack>,
+}
+
+/// The block for `form`, with the details of the chosen video copied in.
+pub(crate) fn hosted_video_block(form: HostedVideoForm, item: MediaItem) -> Result<BlockContent, String> {
+    if !item.mime_type.starts_with("video/") {
+        return Err(format!("{} is not a video", item.original_name));
+    }
+    let meta = item.metadata.0;
+    Ok(BlockContent::HostedVideo(HostedVideo {
+        media_id: item.id,
+        src: item.url,
+        mime_type: item.mime_type,
+        hls_url: meta.hls_url,
+        poster_url: meta.poster_url,
+        duration: meta.duration,
+        width: meta.width,
+        height: meta.height,
+        captions: form.captions.into_iter().filter(|c| !c.src.is_empty()).collect(),
+    }))
+}
+
+/// The rows of a list block's editor. Blank means none yet; anything else
+/// that doesn't parse is refused, not saved as an empty list.
+pub(crate) fn form_items<T: serde::de::DeserializeOwned>(content: &str, what: &str) -> Result<Vec<T>, String> {
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(content).map_err(|e| format!("Couldn't read the {}: {}", what, e))
+}
+
+async fn form_to_block_content(pool: &PgPool, oembed: &OEmbedRegistry, geocoder: &Geocoder, kind: BlockKind, content: &str) -> Result<BlockContent, String> {
+    let block = match kind {
+        BlockKind::Text => {
+            // Column children written in the plain-text editor are bare HTML.
+            let form = serde_json::from_str::<TextForm>(content)
+                .unwrap_or_else(|_| TextForm { format: TextFormat::Html, source: content.to_string() });
+            BlockContent::Text(TextBlock::new(form.format, &form.source))
+        },
+        BlockKind::Video => BlockContent::Video(content.to_string()),
+        BlockKind::Gallery => {
+            let items: Vec<String> = form_items(content, "images")?;
             BlockContent::Gallery(items)
         },
-        "audio" => {
-            let items: Vec<(String, String)> = serde_json::from_str(content).unwrap_or_default();
-            BlockContent::Audio(items)
+        BlockKind::Audio => {
+            let items: Vec<AudioItem> = form_items(content, "tracks")?;
+            let mut tracks = Vec::with_capacity(items.len());
+            for item in items.into_iter().filter(|i| !i.url.is_empty()) {
+                tracks.push(with_audio_analysis(pool, item).await);
+            }
+            BlockContent::Audio(tracks)
+        },
+        BlockKind::File => {
+            let items: Vec<FileItem> = form_items(content, "files")?;
+            let mut files = Vec::with_capacity(items.len());
+            for item in items.into_iter().filter(|i| !i.url.is_empty()) {
+                files.push(with_file_details(pool, item).await);
+            }
+            BlockContent::File(files)
+        },
+        // Resolved on every save, which also refreshes the cached HTML.
+        BlockKind::Embed => BlockContent::Embed(oembed.resolve(content).await?),
+        BlockKind::HostedVideo => {
+            let form: HostedVideoForm = serde_json::from_str(content).map_err(|e| e.to_string())?;
+            let media_id = form.media_id.ok_or("Choose or upload a video")?;
+            let item = crate::media::find(pool, media_id).await.ok_or("Video not found in the media library")?;
+            hosted_video_block(form, item)?
+        },
+        BlockKind::Quote => {
+            let quote: Quote = serde_json::from_str(content).map_err(|e| e.to_string())?;
+            if quote.text.trim().is_empty() {
+                return Err("A quote needs some text".to_string());
+            }
+            BlockContent::Quote(Quote {
+                text: quote.text.trim().to_string(),
+                attribution: non_empty(quote.attribution),
+                source: non_empty(quote.source),
+            })
+        },
+        BlockKind::Credits => {
+            let credits: Vec<Credit> = form_items(content, "credits")?;
+            BlockContent::Credits(
+                credits
+                    .into_iter()
+                    .map(|c| Credit { role: c.role.trim().to_string(), name: c.name.trim().to_string() })
+                    .filter(|c| !c.name.is_empty())
+                    .collect(),
+            )
+        },
+        BlockKind::LinkList => {
+            let links: Vec<LinkItem> = form_items(content, "links")?;
+            let mut items = Vec::with_capacity(links.len());
+            for link in links.into_iter().filter(|l| !l.url.trim().is_empty()) {
+                let url = link.url.trim().to_string();
+                if !is_safe_link(&url) {
+                    return Err(format!("Links must be http(s), mailto or site-relative: {}", url));
+                }
+                let label = link.label.trim();
+                items.push(LinkItem {
+                    label: if label.is_empty() { url.clone() } else { label.to_string() },
+                    url,
+                    description: non_empty(link.description),
+                });
+            }
+            BlockContent::LinkList(items)
         },
-        "file" => {
-            let items: Vec<(String, String)> = serde_json::from_str(content).unwrap_or_default();
-            BlockContent::File(items)
+        BlockKind::Columns => {
+            let form: ColumnsForm = serde_json::from_str(content).map_err(|e| e.to_string())?;
+            let mut columns = Vec::with_capacity(form.columns.len());
+            for column in form.columns {
+                let mut blocks = Vec::with_capacity(column.len());
+                for child in column {
+                    let kind = BlockKind::parse(&child.block_type)
+                        .ok_or_else(|| format!("Unknown block type: {:?}", child.block_type))?;
+                    if kind == BlockKind::Columns {
+                        return Err("Columns can't contain other columns".to_string());
+                    }
+                    blocks.push(Box::pin(form_to_block_content(pool, oembed, geocoder, kind, &child.content)).await?);
+                }
+                columns.push(blocks);
+            }
+            let layout = Columns { columns };
+            layout.validate()?;
+            BlockContent::Columns(layout)
         },
-        _ => BlockContent::Text(content.to_string()),
+        BlockKind::Map => {
+            let rows: Vec<MapMarkerForm> = serde_json::from_str(content).map_err(|e| e.to_string())?;
+            let mut markers = Vec::with_capacity(rows.len());
+            for row in rows {
+                let address = row.address.as_deref().unwrap_or("").trim();
+                let point = match (row.lat, row.lng) {
+                    (Some(lat), Some(lng)) => GeoPoint { lat, lng },
+                    _ if address.is_empty() => continue,
+                    _ => geocoder
+                        .geocode(address)
+                        .await?
+                        .ok_or_else(|| format!("Couldn't find {:?}; enter coordinates instead", address))?,
+                };
+                point.validate()?;
+                let url = non_empty(row.url);
+                if let Some(url) = url.as_deref().filter(|u| !is_safe_link(u)) {
+                    return Err(format!("Links must be http(s), mailto or site-relative: {}", url));
+                }
+                let label = row.label.trim();
+                markers.push(MapMarker {
+                    lat: point.lat,
+                    lng: point.lng,
+                    label: if label.is_empty() { address.to_string() } else { label.to_string() },
+                    url,
+                });
+            }
+            if markers.is_empty() {
+                return Err("Add at least one marker".to_string());
+            }
+            BlockContent::Map(markers)
+        },
+    };
+    Ok(block)
+}
+
+/// Fill in what the media library knows about a track (duration, peaks,
+/// artist) without overriding anything entered in the editor.
+async fn with_audio_analysis(pool: &PgPool, mut item: AudioItem) -> AudioItem {
+    if let Some(media) = library_item(pool, item.media_id, &item.url).await {
+        let meta = media.metadata.0;
+        item.media_id = Some(media.id);
+        item.duration = item.duration.or(meta.duration);
+        item.artist = item.artist.filter(|a| !a.trim().is_empty()).or(meta.artist);
+        if item.peaks.is_empty() {
+            item.peaks = meta.peaks.unwrap_or_default();
+        }
+    } else {
+        item.media_id = None;
+    }
+    item.artwork_url = item.artwork_url.filter(|u| !u.trim().is_empty());
+    item
+}
+
+/// Copy name, type, size and page details from the media library. The
+/// description is the only field the editor controls.
+async fn with_file_details(pool: &PgPool, item: FileItem) -> FileItem {
+    match library_item(pool, item.media_id, &item.url).await {
+        Some(media) => {
+            let meta = media.metadata.0;
+            FileItem {
+                url: item.url,
+                description: item.description,
+                media_id: Some(media.id),
+                filename: Some(media.original_name),
+                mime_type: Some(media.mime_type),
+                byte_size: Some(media.byte_size),
+                page_count: meta.page_count,
+                thumbnail_url: meta.thumbnail_url,
+            }
+        }
+        None => FileItem { url: item.url, description: item.description, ..Default::default() },
     }
 }
 
+/// The media item a block item points at, if it still has the same URL.
+async fn library_item(pool: &PgPool, media_id: Option<Uuid>, url: &str) -> Option<MediaItem> {
+    let media = match media_id {
+        Some(id) => crate::media::find(pool, id).await,
+        None => crate::media::find_by_url(pool, url).await,
+    };
+    media.filter(|m| m.url == url)
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+fn is_safe_link(url: &str) -> bool {
+    let lower = url.to_ascii_lowercase();
+    ["http://", "https://", "mailto:"].iter().any(|scheme| lower.starts_with(scheme))
+        || (url.starts_with('/') && !url.starts_with("//"))
+}
+
+fn provider_names(oembed: &OEmbedRegistry) -> Vec<String> {
+    oembed.providers().iter().map(|p| p.name.clone()).collect()
+}
+
+fn unknown_block_type(block_type: &str) -> AppError {
+    AppError::Validation(format!("Unknown block type: {:?}", block_type))
+}
+
+fn parse_block_type(block_type: &str) -> Result<BlockKind, AppError> {
+    BlockKind::parse(block_type).ok_or_else(|| unknown_block_type(block_type))
+}
+
+async fn find_block(pool: &PgPool, block_id: Uuid) -> Result<ContentBlock, AppError> {
+    sqlx::query_as::<_, ContentBlock>("SELECT * FROM content_blocks WHERE id = $1")
+        .bind(block_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(AppError::NotFound("Block"))
+}
+
 // Helper function to render the blocks list for HTMX responses
-async fn render_blocks_list(pool: PgPool, project_id: Uuid, csrf_token: &str) -> impl IntoResponse {
+async fn render_blocks_list(pool: PgPool, project_id: Uuid, csrf_token: &str) -> Result<BlocksListTemplate, AppError> {
     let blocks = sqlx::query_as::<_, ContentBlock>(
         "SELECT * FROM content_blocks WHERE project_id = $1 ORDER BY sort_order ASC"
     )
     .bind(project_id)
     .fetch_all(&pool)
+    .await?;
+
+    Ok(BlocksListTemplate {
+        blocks,
+        move_targets: move_targets(&pool, project_id).await,
+        authenticity_token: csrf_token.to_string(),
+    })
+}
+
+/// Every gallery image of a project, for picking its thumbnail.
+async fn gallery_images(pool: &PgPool, project_id: Uuid) -> Vec<String> {
+    let blocks = sqlx::query_as::<_, ContentBlock>(
+        "SELECT * FROM content_blocks WHERE project_id = $1 ORDER BY sort_order ASC"
+    )
+    .bind(project_id)
+    .fetch_all(pool)
     .await
-    .unwrap();
-
-    // Render just the blocks list partial matching the template structure
-    let mut html = String::new();
-    for block in blocks {
-        let preview = match &block.content.0 {
-            BlockContent::Text(content) => {
-                // Truncate HTML for preview, removing tags
-                let text_only: String = content
-                    .chars()
-                    .filter(|c| !matches!(c, '<' | '>'))
-                    .take(50)
-                    .collect();
-                text_only
-            },
-            BlockContent::Video(url) => format!("Video: {}", url),
-            BlockContent::Gallery(urls) => format!("{} images", urls.len()),
-            BlockContent::Audio(items) => format!("{} audio files", items.len()),
-            BlockContent::File(items) => format!("{} files", items.len()),
-        };
+    .unwrap_or_default();
+
+    let mut images: Vec<String> = Vec::new();
+    for block in &blocks {
+        for url in block.content.0.gallery_images() {
+            if !images.iter().any(|i| i == url) {
+                images.push(url.to_string());
+            }
+        }
+    }
+    images
+}
 
-        html.push_str(&format!(
-            r#"<div class="block-item" data-id="{}">
-            <div class="drag-handle material-icons">drag_indicator</div>
-            <div class="block-info">
-                <span class="block-type">{}</span>
-                <span class="block-preview">{}</span>
-            </div>
-            <div class="block-actions">
-                <a href="/admin/blocks/{}" class="icon-btn" title="Edit">
-                    <span class="material-icons">edit</span>
-                </a>
-                <form method="POST" action="/admin/blocks/delete/{}" class="inline-form confirm-delete">
-                    <input type="hidden" name="authenticity_token" value="{}">
-                    <button type="submit" class="icon-btn delete" title="Delete">
-                        <span class="material-icons">delete</span>
-                    </button>
-                </form>
-            </div>
-        </div>
-"#,
-            block.id,
-            block.block_type,
-            preview,
-            block.id,
-            block.id,
-            csrf_token
-        ));
-    }
-
-    Html(html)
+/// The projects a block of `project_id` can be moved to.
+async fn move_targets(pool: &PgPool, project_id: Uuid) -> Vec<Project> {
+    sqlx::query_as::<_, Project>("SELECT * FROM projects WHERE id <> $1 ORDER BY title")
+        .bind(project_id)
+        .fetch_all(pool)
+        .await
+        .unwrap_or_default()
 }
 
 
@@ -582,19 +1603,24 @@ pub async fn trigger_deploy(
     State(_pool): State<PgPool>,
     session: Session,
     Form(form): Form<DeployForm>,
-) -> impl IntoResponse {
-    if !verify_csrf_token(&session, &form.authenticity_token).await {
-        return (axum::http::StatusCode::FORBIDDEN, "Invalid CSRF Token").into_response();
+) -> Result<Redirect, AppError> {
+    require_csrf_token(&session, &form.authenticity_token).await?;
+    match dispatch_deploy().await {
+        Ok(()) => flash::success(&session, "Deployment started. The live site updates in a few minutes").await,
+        Err(message) => flash::error(&session, message).await,
     }
+    Ok(Redirect::to("/admin/dashboard"))
+}
+
+/// Asks GitHub to build and publish the static site. The error is what the
+/// editor is told; the details are logged.
+async fn dispatch_deploy() -> Result<(), String> {
     let github_token = std::env::var("GITHUB_TOKEN").unwrap_or_default();
     let owner = std::env::var("GITHUB_OWNER").unwrap_or_default();
     let repo = std::env::var("GITHUB_REPO").unwrap_or_default();
 
     if github_token.is_empty() || owner.is_empty() || repo.is_empty() {
-        return (
-            axum::http::StatusCode::BAD_REQUEST,
-            Html("Missing GitHub configuration (GITHUB_TOKEN, GITHUB_OWNER, GITHUB_REPO)".to_string()),
-        ).into_response();
+        return Err("Publishing isn't set up: GITHUB_TOKEN, GITHUB_OWNER and GITHUB_REPO are needed".to_string());
     }
 
     let client = reqwest::Client::new();
@@ -611,22 +1637,15 @@ pub async fn trigger_deploy(
         .send()
         .await;
 
-    match res {
-        Ok(response) => {
-            if response.status().is_success() {
-                Redirect::to("/admin/dashboard?deploy=success").into_response()
-            } else {
-                let status = response.status();
-                let text = response.text().await.unwrap_or_default();
-                (
-                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                    Html(format!("GitHub API Error: {} - {}", status, text)),
-                ).into_response()
-            }
-        }
-        Err(e) => (
-            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-            Html(format!("Failed to send request: {}", e)),
-        ).into_response(),
+    let response = res.map_err(|e| {
+        tracing::error!("Deploy request failed: {}", e);
+        "GitHub couldn't be reached, so nothing was published. Try again in a moment".to_string()
+    })?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        tracing::error!("GitHub API error: {} - {}", status, text);
+        return Err(format!("GitHub refused the deployment ({}), so nothing was published", status));
     }
+    Ok(())
 }
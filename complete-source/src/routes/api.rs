@@ -1,19 +1,31 @@
 use axum::{
     extract::{Multipart, State},
-    response::{IntoResponse, Json},
-    http::{StatusCode, HeaderMap},
+    response::Json,
+    http::HeaderMap,
 };
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 use sqlx::PgPool;
 use tower_sessions::Session;
-use crate::csrf::verify_csrf_token;
+use crate::csrf::require_csrf_header;
+use crate::error::{ApiError, AppError};
 use crate::AppState;
+use crate::models::{TextBlock, TextFormat};
+use std::collections::HashSet;
+use uuid::Uuid;
 
 #[derive(Serialize)]
 pub struct UploadResponse {
     pub url: String,
     pub original_name: String,
+    pub media_id: uuid::Uuid,
+    pub mime_type: String,
+    pub poster_url: Option<String>,
+    pub duration: Option<f64>,
+    pub artist: Option<String>,
+    pub peaks: Option<Vec<f32>>,
+    pub byte_size: i64,
+    pub page_count: Option<u32>,
+    pub thumbnail_url: Option<String>,
 }
 
 pub async fn upload_handler(
@@ -21,47 +33,76 @@ pub async fn upload_handler(
     session: Session,
     headers: HeaderMap,
     mut multipart: Multipart,
-) -> impl IntoResponse {
-    // CSRF Check
-    let csrf_header = headers.get("X-CSRF-Token").and_then(|v| v.to_str().ok()).unwrap_or("");
-    if !verify_csrf_token(&session, csrf_header).await {
-         return (StatusCode::FORBIDDEN, "Invalid CSRF Token").into_response();
-    }
+) -> Result<Json<UploadResponse>, ApiError> {
+    require_csrf_header(&session, &headers).await?;
 
     let mut uploaded_files = Vec::new();
+    let mut failures = Vec::new();
 
     while let Some(field) = multipart.next_field().await.unwrap_or(None) {
         let file_name = field.file_name().unwrap_or("unknown").to_string();
+        let mime_type = mime_guess::from_path(&file_name)
+            .first()
+            .map(|m| m.to_string())
+            .or_else(|| field.content_type().map(str::to_string))
+            .unwrap_or_else(|| "application/octet-stream".to_string());
         let data = field.bytes().await.unwrap_or_default();
         
         if data.is_empty() { continue; }
 
-        let url = crate::upload::upload_file(&state.cloudinary, data.to_vec(), &file_name, "auto").await;
-        if let Ok(url) = url {
-            uploaded_files.push(json!({
-                "original_name": file_name,
-                "url": url
-            }));
+        match crate::media::ingest(&state.pool, &state.storage, data.to_vec(), &file_name, &mime_type).await {
+            Ok(item) => {
+                let meta = item.metadata.0;
+                uploaded_files.push(UploadResponse {
+                    url: item.url,
+                    original_name: item.original_name,
+                    media_id: item.id,
+                    mime_type: item.mime_type,
+                    poster_url: meta.poster_url,
+                    duration: meta.duration,
+                    artist: meta.artist,
+                    peaks: meta.peaks,
+                    byte_size: item.byte_size,
+                    page_count: meta.page_count,
+                    thumbnail_url: meta.thumbnail_url,
+                })
+            }
+            Err(e) => failures.push(format!("Upload of {} failed: {}", file_name, e)),
         }
     }
 
     if uploaded_files.is_empty() {
-        return (StatusCode::BAD_REQUEST, "No files uploaded").into_response();
+        return Err(if failures.is_empty() {
+            AppError::Validation("No files uploaded".to_string())
+        } else {
+            AppError::Storage(failures.join("; "))
+        }.into());
+    }
+    for failure in &failures {
+        tracing::error!("{}", failure);
     }
 
     // Return the first file for simplicity in this specific JS impl, or list
-    Json(uploaded_files[0].clone()).into_response()
+    Ok(Json(uploaded_files.swap_remove(0)))
 }
 
 #[derive(Deserialize)]
-pub struct ReorderUpdate {
-    pub id: i32,
+pub struct ReorderRequest {
+    pub project_id: Uuid,
+    /// Every block of the project, in the new order.
+    pub block_ids: Vec<Uuid>,
+}
+
+#[derive(Serialize)]
+pub struct BlockOrder {
+    pub id: Uuid,
     pub sort_order: i32,
 }
 
-#[derive(Deserialize)]
-pub struct ReorderRequest {
-    pub updates: Vec<ReorderUpdate>,
+#[derive(Serialize)]
+pub struct ReorderResponse {
+    pub project_id: Uuid,
+    pub blocks: Vec<BlockOrder>,
 }
 
 pub async fn reorder_handler(
@@ -69,21 +110,172 @@ pub async fn reorder_handler(
     session: Session,
     headers: HeaderMap,
     Json(payload): Json<ReorderRequest>,
-) -> impl IntoResponse {
-    // CSRF Check
-    let csrf_header = headers.get("X-CSRF-Token").and_then(|v| v.to_str().ok()).unwrap_or("");
-    if !verify_csrf_token(&session, csrf_header).await {
-         return (StatusCode::FORBIDDEN, "Invalid CSRF Token").into_response();
+) -> Result<Json<ReorderResponse>, ApiError> {
+    require_csrf_header(&session, &headers).await?;
+
+    let blocks = reorder_blocks(&pool, payload.project_id, &payload.block_ids).await?;
+    Ok(Json(ReorderResponse { project_id: payload.project_id, blocks }))
+}
+
+/// Rewrites the project's `sort_order`s as 0, 1, 2, ... in the given order,
+/// provided the ids are exactly the project's blocks.
+async fn reorder_blocks(
+    pool: &PgPool,
+    project_id: Uuid,
+    block_ids: &[Uuid],
+) -> Result<Vec<BlockOrder>, AppError> {
+    let mut tx = pool.begin().await?;
+    // Moving a block in locks the project too, so that waits for this.
+    let project: Option<Uuid> = sqlx::query_scalar("SELECT id FROM projects WHERE id = $1 FOR UPDATE")
+        .bind(project_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    if project.is_none() {
+        return Err(AppError::NotFound("Project"));
     }
+    let current: Vec<Uuid> = sqlx::query_scalar("SELECT id FROM content_blocks WHERE project_id = $1 FOR UPDATE")
+        .bind(project_id)
+        .fetch_all(&mut *tx)
+        .await?;
+    check_block_order(&current, block_ids).map_err(AppError::Validation)?;
+
+    sqlx::query(
+        "UPDATE content_blocks SET sort_order = (o.position - 1)::int
+         FROM unnest($1::uuid[]) WITH ORDINALITY AS o(id, position)
+         WHERE content_blocks.id = o.id AND content_blocks.project_id = $2"
+    )
+    .bind(block_ids)
+    .bind(project_id)
+    .execute(&mut *tx)
+    .await?;
+    let blocks = sqlx::query_as::<_, (Uuid, i32)>(
+        "SELECT id, sort_order FROM content_blocks WHERE project_id = $1 ORDER BY sort_order"
+    )
+    .bind(project_id)
+    .fetch_all(&mut *tx)
+    .await?;
 
-    for update in payload.updates {
-        sqlx::query("UPDATE blocks SET sort_order = $1 WHERE id = $2")
-            .bind(update.sort_order)
-            .bind(update.id)
-            .execute(&pool)
-            .await
-            .ok();
+    tx.commit().await?;
+    Ok(blocks.into_iter().map(|(id, sort_order)| BlockOrder { id, sort_order }).collect())
+}
+
+/// Why a submitted order of blocks or projects was refused.
+#[derive(Debug, PartialEq)]
+pub enum OrderError {
+    Repeated(Uuid),
+    Unknown(Uuid),
+    Missing(Vec<Uuid>),
+}
+
+/// Checks that `requested` lists each of the `current` ids exactly once,
+/// and nothing else.
+pub fn check_order(current: &[Uuid], requested: &[Uuid]) -> Result<(), OrderError> {
+    let mut seen = HashSet::new();
+    if let Some(id) = requested.iter().find(|id| !seen.insert(**id)) {
+        return Err(OrderError::Repeated(*id));
     }
+    if let Some(id) = requested.iter().find(|id| !current.contains(id)) {
+        return Err(OrderError::Unknown(*id));
+    }
+    let missing: Vec<Uuid> = current.iter().filter(|id| !seen.contains(*id)).copied().collect();
+    if !missing.is_empty() {
+        return Err(OrderError::Missing(missing));
+    }
+    Ok(())
+}
+
+fn join_ids(ids: &[Uuid]) -> String {
+    ids.iter().map(Uuid::to_string).collect::<Vec<_>>().join(", ")
+}
+
+/// `check_order` for the blocks of one project.
+pub fn check_block_order(current: &[Uuid], requested: &[Uuid]) -> Result<(), String> {
+    check_order(current, requested).map_err(|e| match e {
+        OrderError::Repeated(id) => format!("Block {} is listed more than once", id),
+        OrderError::Unknown(id) => format!("Block {} does not belong to this project", id),
+        OrderError::Missing(ids) => format!("The order is missing block(s) {}", join_ids(&ids)),
+    })
+}
+
+/// `check_order` for all projects.
+pub fn check_project_order(current: &[Uuid], requested: &[Uuid]) -> Result<(), String> {
+    check_order(current, requested).map_err(|e| match e {
+        OrderError::Repeated(id) => format!("Project {} is listed more than once", id),
+        OrderError::Unknown(id) => format!("Project {} does not exist", id),
+        OrderError::Missing(ids) => format!("The order is missing project(s) {}", join_ids(&ids)),
+    })
+}
+
+#[derive(Deserialize)]
+pub struct ProjectOrderRequest {
+    /// Every project, in the new order.
+    pub project_ids: Vec<Uuid>,
+}
+
+#[derive(Serialize)]
+pub struct ProjectPosition {
+    pub id: Uuid,
+    pub position: i32,
+}
+
+pub async fn project_order_handler(
+    State(pool): State<PgPool>,
+    session: Session,
+    headers: HeaderMap,
+    Json(payload): Json<ProjectOrderRequest>,
+) -> Result<Json<Vec<ProjectPosition>>, ApiError> {
+    require_csrf_header(&session, &headers).await?;
+
+    Ok(Json(order_projects(&pool, &payload.project_ids).await?))
+}
+
+/// Sets every project's manual `position` to 0, 1, 2, ... in the given
+/// order, provided the ids are exactly the existing projects.
+async fn order_projects(pool: &PgPool, project_ids: &[Uuid]) -> Result<Vec<ProjectPosition>, AppError> {
+    let mut tx = pool.begin().await?;
+    let current: Vec<Uuid> = sqlx::query_scalar("SELECT id FROM projects FOR UPDATE")
+        .fetch_all(&mut *tx)
+        .await?;
+    check_project_order(&current, project_ids).map_err(AppError::Validation)?;
+
+    sqlx::query(
+        "UPDATE projects SET position = (o.position - 1)::int
+         FROM unnest($1::uuid[]) WITH ORDINALITY AS o(id, position)
+         WHERE projects.id = o.id"
+    )
+    .bind(project_ids)
+    .execute(&mut *tx)
+    .await?;
+    let projects = sqlx::query_as::<_, (Uuid, i32)>("SELECT id, position FROM projects ORDER BY position")
+        .fetch_all(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(projects.into_iter().map(|(id, position)| ProjectPosition { id, position }).collect())
+}
+
+#[derive(Deserialize)]
+pub struct PreviewRequest {
+    #[serde(default)]
+    pub format: TextFormat,
+    pub source: String,
+}
+
+#[derive(Serialize)]
+pub struct PreviewResponse {
+    pub html: String,
+    pub text: String,
+}
+
+/// Renders a Text block or page source the way it will be saved, for the
+/// editors' live preview.
+pub async fn preview_handler(
+    session: Session,
+    headers: HeaderMap,
+    Json(payload): Json<PreviewRequest>,
+) -> Result<Json<PreviewResponse>, ApiError> {
+    require_csrf_header(&session, &headers).await?;
 
-    StatusCode::OK.into_response()
+    let text = TextBlock::new(payload.format, &payload.source);
+    Ok(Json(PreviewResponse { text: text.plain_text(), html: text.html }))
 }
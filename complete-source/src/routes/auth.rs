@@ -8,6 +8,8 @@ use sqlx::PgPool;
 use crate::models::User;
 use tower_sessions::Session;
 use crate::csrf::{get_or_create_csrf_token, verify_csrf_token};
+use crate::error::AppError;
+use crate::flash;
 
 use crate::templates::LoginTemplate;
 
@@ -27,6 +29,7 @@ pub async fn login_page(session: Session, Query(params): Query<LoginQuery>) -> i
     let csrf_token = get_or_create_csrf_token(&session).await;
     LoginTemplate { 
         error: params.error, 
+        flashes: flash::take(&session).await,
         authenticity_token: csrf_token 
     }
 }
@@ -35,28 +38,34 @@ pub async fn login_handler(
     State(pool): State<PgPool>,
     session: Session,
     Form(payload): Form<LoginRequest>,
-) -> impl IntoResponse {
+) -> Result<Redirect, AppError> {
     if !verify_csrf_token(&session, &payload.authenticity_token).await {
-        return Redirect::to("/admin/login?error=Session expired (CSRF)").into_response();
+        return Ok(Redirect::to("/admin/login?error=Session expired (CSRF)"));
     }
 
     let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE username = $1")
         .bind(&payload.username)
         .fetch_optional(&pool)
-        .await
-        .unwrap_or(None);
+        .await?;
 
     if let Some(user) = user {
         if crate::models::verify_password(&payload.password, &user.password_hash) {
-            session.insert("user_id", user.id).await.unwrap();
-            return Redirect::to("/admin/dashboard").into_response();
+            session
+                .insert("user_id", user.id)
+                .await
+                .map_err(|e| AppError::Internal(format!("Saving the session failed: {}", e)))?;
+            return Ok(Redirect::to("/admin/dashboard"));
         }
     }
 
-    Redirect::to("/admin/login?error=Invalid credentials").into_response()
+    Ok(Redirect::to("/admin/login?error=Invalid credentials"))
 }
 
-pub async fn logout_handler(session: Session) -> impl IntoResponse {
-    session.flush().await.unwrap();
-    Redirect::to("/admin/login")
+pub async fn logout_handler(session: Session) -> Result<Redirect, AppError> {
+    session
+        .flush()
+        .await
+        .map_err(|e| AppError::Internal(format!("Ending the session failed: {}", e)))?;
+    flash::info(&session, "You're logged out").await;
+    Ok(Redirect::to("/admin/login"))
 }
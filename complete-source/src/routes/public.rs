@@ -1,10 +1,24 @@
 use axum::{
-    extract::{Path, State},
-    response::IntoResponse,
+    body::Body,
+    extract::{Path, Query, Request, State},
+    http::{header, HeaderValue, Uri},
+    response::{IntoResponse, Redirect, Response},
 };
 use sqlx::PgPool;
-use crate::templates::{IndexTemplate, ProjectTemplate, ContactTemplate, AboutTemplate};
-use crate::models::{Project, ContentBlock, Page};
+use tower::ServiceExt;
+use tower_http::services::ServeFile;
+use uuid::Uuid;
+use crate::upload::Storage;
+use crate::templates::{IndexTemplate, ProjectTemplate, ContactTemplate, AboutTemplate, MapTemplate, SearchTemplate, NotFoundTemplate, GoneTemplate, ServerErrorTemplate, DEFAULT_FOOTER};
+use crate::models::{Project, ContentBlock, MapMarker, Page, TextFormat};
+use crate::feed::{self, Feed};
+use crate::json_ld::{self, Artist};
+use crate::redirects::{self, Destination};
+use crate::robots;
+use crate::search::{self, SearchQuery};
+use crate::settings::SiteSettings;
+use crate::site::Site;
+use crate::sitemap;
 
 async fn get_footer(pool: &PgPool) -> String {
     sqlx::query_as::<_, Page>("SELECT * FROM pages WHERE slug = 'footer'")
@@ -12,43 +26,85 @@ async fn get_footer(pool: &PgPool) -> String {
         .await
         .unwrap_or(None)
         .map(|p| p.content)
-        .unwrap_or_else(|| "<p>&copy; 2024</p>".to_string())
+        .unwrap_or_else(|| DEFAULT_FOOTER.to_string())
 }
 
-pub async fn get_index_template(pool: &PgPool) -> IndexTemplate {
-    let projects = sqlx::query_as::<_, Project>("SELECT * FROM projects ORDER BY start_date DESC")
-        .fetch_all(pool)
-        .await
-        .unwrap_or_default();
+async fn get_artist(pool: &PgPool) -> Artist {
+    Artist::new(Site::global(), &SiteSettings::load(pool).await)
+}
+
+pub async fn get_not_found_template(pool: &PgPool) -> NotFoundTemplate {
+    NotFoundTemplate { footer: get_footer(pool).await }
+}
+
+/// The themed 404 page.
+pub async fn not_found(pool: &PgPool) -> Response {
+    get_not_found_template(pool).await.into_response()
+}
+
+/// The page for an old address: a 301 to where it went, or a 410 if it's gone.
+async fn moved_or_gone(pool: &PgPool, destination: Destination) -> Response {
+    match destination {
+        Destination::Moved(target) => redirects::moved_permanently(&target),
+        Destination::Gone => GoneTemplate { footer: get_footer(pool).await }.into_response(),
+    }
+}
 
-    // Group projects by year
+/// The index timeline: `projects` grouped by year, most recent year first,
+/// keeping their order within each year.
+pub fn by_year(projects: Vec<Project>) -> Vec<(i32, Vec<Project>)> {
     let mut years_map: std::collections::BTreeMap<i32, Vec<Project>> = std::collections::BTreeMap::new();
     for project in projects {
         let year = project.start_date.year();
-        years_map.entry(year).or_insert_with(Vec::new).push(project);
+        years_map.entry(year).or_default().push(project);
     }
+    years_map.into_iter().rev().collect()
+}
 
-    // Convert to vec of (year, projects) tuples, sorted by year descending
-    let mut grouped_projects: Vec<(i32, Vec<Project>)> = years_map
-        .into_iter()
-        .collect();
-    grouped_projects.reverse(); // Most recent year first
+/// The projects before and after `id` on the index timeline, for the
+/// previous and next buttons of its page.
+pub fn neighbours(timeline: Vec<(i32, Vec<Project>)>, id: Uuid) -> (Option<Project>, Option<Project>) {
+    let mut projects: Vec<Project> = timeline.into_iter().flat_map(|(_, projects)| projects).collect();
+    let Some(i) = projects.iter().position(|p| p.id == id) else {
+        return (None, None);
+    };
+    let next = (i + 1 < projects.len()).then(|| projects.remove(i + 1));
+    let prev = i.checked_sub(1).map(|i| projects.remove(i));
+    (prev, next)
+}
 
+pub async fn get_index_template(pool: &PgPool) -> IndexTemplate {
+    let settings = SiteSettings::load(pool).await;
+    let order = settings.index_order;
+    let projects = sqlx::query_as::<_, Project>(&format!("SELECT * FROM projects ORDER BY {}", order.order_by()))
+        .fetch_all(pool)
+        .await
+        .unwrap_or_default();
+    let featured = sqlx::query_as::<_, Project>(&format!("SELECT * FROM projects WHERE featured ORDER BY {}", order.order_by()))
+        .fetch_all(pool)
+        .await
+        .unwrap_or_default();
+
+    let grouped_projects = by_year(projects);
+    let json_ld = json_ld::script(&json_ld::home_page(Site::global(), &Artist::new(Site::global(), &settings)));
     let footer = get_footer(pool).await;
 
-    IndexTemplate { grouped_projects, footer }
+    IndexTemplate { featured, grouped_projects, json_ld, footer }
 }
 
 pub async fn index(State(pool): State<PgPool>) -> impl IntoResponse {
     get_index_template(&pool).await
 }
 
-pub async fn get_project_details_template(pool: &PgPool, slug: &str) -> Option<ProjectTemplate> {
-    let project = sqlx::query_as::<_, Project>("SELECT * FROM projects WHERE slug = $1")
+/// The project page, or `None` if no project has `slug`.
+pub async fn get_project_details_template(pool: &PgPool, slug: &str) -> Result<Option<ProjectTemplate>, sqlx::Error> {
+    let Some(project) = sqlx::query_as::<_, Project>("SELECT * FROM projects WHERE slug = $1")
         .bind(slug)
-        .fetch_one(pool)
-        .await
-        .ok()?;
+        .fetch_optional(pool)
+        .await?
+    else {
+        return Ok(None);
+    };
 
     let blocks = sqlx::query_as::<_, ContentBlock>(
         "SELECT * FROM content_blocks WHERE project_id = $1 ORDER BY sort_order ASC"
@@ -58,44 +114,110 @@ pub async fn get_project_details_template(pool: &PgPool, slug: &str) -> Option<P
     .await
     .unwrap_or_default();
 
-    // Get next project (older date)
-    let next_project = sqlx::query_as::<_, Project>(
-        "SELECT * FROM projects WHERE start_date < $1 ORDER BY start_date DESC LIMIT 1"
-    )
-    .bind(project.start_date)
-    .fetch_optional(pool)
-    .await
-    .unwrap_or(None);
-
-    // Get previous project (newer date)
-    let prev_project = sqlx::query_as::<_, Project>(
-        "SELECT * FROM projects WHERE start_date > $1 ORDER BY start_date ASC LIMIT 1"
-    )
-    .bind(project.start_date)
-    .fetch_optional(pool)
-    .await
-    .unwrap_or(None);
+    // Previous and next as they're shown on the index, in its order.
+    let order = SiteSettings::load(pool).await.index_order;
+    let projects = sqlx::query_as::<_, Project>(&format!("SELECT * FROM projects ORDER BY {}", order.order_by()))
+        .fetch_all(pool)
+        .await
+        .unwrap_or_default();
+    let (prev_project, next_project) = neighbours(by_year(projects), project.id);
 
+    let artist = get_artist(pool).await;
+    let json_ld = json_ld::script(&json_ld::project_page(Site::global(), &artist, &project, &blocks));
     let footer = get_footer(pool).await;
 
-    Some(ProjectTemplate { 
+    Ok(Some(ProjectTemplate { 
         project, 
         blocks,
         next_project,
         prev_project,
+        json_ld,
         footer,
-    })
+    }))
 }
 
 pub async fn project_details(
     State(pool): State<PgPool>,
     Path(slug): Path<String>,
     ) -> impl IntoResponse {
-    if let Some(template) = get_project_details_template(&pool, &slug).await {
-        template.into_response()
-    } else {
-        // TODO: 404 Page
-        "Project not found".into_response()
+    match get_project_details_template(&pool, &slug).await {
+        Ok(Some(template)) => template.into_response(),
+        Ok(None) => match redirects::find(&pool, &redirects::project_path(&slug)).await {
+            Some(destination) => moved_or_gone(&pool, destination).await,
+            None => not_found(&pool).await,
+        },
+        Err(e) => {
+            tracing::error!("Failed to load project {}: {}", slug, e);
+            ServerErrorTemplate { footer: get_footer(&pool).await }.into_response()
+        }
+    }
+}
+
+/// The newest projects as a feed, written by `render`.
+async fn feed_response(pool: &PgPool, content_type: &'static str, render: fn(&Feed) -> Result<String, askama::Error>) -> Response {
+    let body = match feed::load(pool, Site::global()).await {
+        Ok(feed) => render(&feed).map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    match body {
+        Ok(body) => ([(header::CONTENT_TYPE, HeaderValue::from_static(content_type))], body).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to build the feed: {}", e);
+            ServerErrorTemplate { footer: get_footer(pool).await }.into_response()
+        }
+    }
+}
+
+pub async fn atom_feed(State(pool): State<PgPool>) -> Response {
+    feed_response(&pool, feed::ATOM_CONTENT_TYPE, feed::atom).await
+}
+
+pub async fn rss_feed(State(pool): State<PgPool>) -> Response {
+    feed_response(&pool, feed::RSS_CONTENT_TYPE, feed::rss).await
+}
+
+pub async fn json_feed(State(pool): State<PgPool>) -> Response {
+    feed_response(&pool, feed::JSON_CONTENT_TYPE, |feed| Ok(feed::json_feed(feed))).await
+}
+
+async fn sitemap_response(pool: &PgPool, path: &str) -> Response {
+    let site = Site::global();
+    let files = match sitemap::load(pool, site).await {
+        Ok(urls) => sitemap::files(site, &urls, sitemap::MAX_URLS).map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    match files {
+        Ok(files) => match files.into_iter().find(|(file, _)| file == path) {
+            Some((_, xml)) => ([(header::CONTENT_TYPE, HeaderValue::from_static(sitemap::CONTENT_TYPE))], xml).into_response(),
+            None => not_found(pool).await,
+        },
+        Err(e) => {
+            tracing::error!("Failed to build the sitemap: {}", e);
+            ServerErrorTemplate { footer: get_footer(pool).await }.into_response()
+        }
+    }
+}
+
+pub async fn sitemap(State(pool): State<PgPool>) -> Response {
+    sitemap_response(&pool, sitemap::PATH).await
+}
+
+/// A part of a sitemap too big for one file, e.g. `/sitemap/2.xml`.
+pub async fn sitemap_part(State(pool): State<PgPool>, Path(part): Path<String>) -> Response {
+    sitemap_response(&pool, &format!("/sitemap/{}", part)).await
+}
+
+pub async fn robots_txt(State(pool): State<PgPool>) -> Response {
+    let rules = SiteSettings::load(&pool).await.robots_rules;
+    let body = robots::render(Site::global(), &rules);
+    ([(header::CONTENT_TYPE, HeaderValue::from_static(robots::CONTENT_TYPE))], body).into_response()
+}
+
+/// Anything no route matched: an old address, or not found.
+pub async fn fallback(State(pool): State<PgPool>, uri: Uri) -> Response {
+    match redirects::find(&pool, uri.path()).await {
+        Some(destination) => moved_or_gone(&pool, destination).await,
+        None => not_found(&pool).await,
     }
 }
 
@@ -109,11 +231,14 @@ pub async fn get_contact_template(pool: &PgPool) -> ContactTemplate {
             title: "Contact".to_string(),
             content: "<p>Contact info missing.</p>".to_string(),
             updated_at: time::OffsetDateTime::now_utc(),
+            format: TextFormat::Html,
+            source: "<p>Contact info missing.</p>".to_string(),
         });
 
+    let json_ld = json_ld::script(&json_ld::page(Site::global(), &get_artist(pool).await, &page));
     let footer = get_footer(pool).await;
 
-    ContactTemplate { page, footer }
+    ContactTemplate { page, json_ld, footer }
 }
 
 pub async fn contact(State(pool): State<PgPool>) -> impl IntoResponse {
@@ -130,13 +255,92 @@ pub async fn get_about_template(pool: &PgPool) -> AboutTemplate {
             title: "About".to_string(),
             content: "<p>About info missing.</p>".to_string(),
             updated_at: time::OffsetDateTime::now_utc(),
+            format: TextFormat::Html,
+            source: "<p>About info missing.</p>".to_string(),
         });
 
+    let json_ld = json_ld::script(&json_ld::page(Site::global(), &get_artist(pool).await, &page));
     let footer = get_footer(pool).await;
 
-    AboutTemplate { page, footer }
+    AboutTemplate { page, json_ld, footer }
 }
 
 pub async fn about(State(pool): State<PgPool>) -> impl IntoResponse {
     get_about_template(&pool).await
 }
+
+/// Every project with coordinates, newest first.
+pub async fn get_map_template(pool: &PgPool) -> MapTemplate {
+    let projects = sqlx::query_as::<_, Project>(
+        "SELECT * FROM projects WHERE latitude IS NOT NULL AND longitude IS NOT NULL ORDER BY start_date DESC"
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap_or_default();
+
+    let markers = projects
+        .iter()
+        .filter_map(|project| {
+            let point = project.location()?;
+            let label = match &project.location_name {
+                Some(place) => format!("{} ({})", project.title, place),
+                None => project.title.clone(),
+            };
+            Some(MapMarker { lat: point.lat, lng: point.lng, label, url: Some(format!("/project/{}", project.slug)) })
+        })
+        .collect();
+
+    MapTemplate {
+        markers,
+        projects,
+        footer: get_footer(pool).await,
+    }
+}
+
+pub async fn map_page(State(pool): State<PgPool>) -> impl IntoResponse {
+    get_map_template(&pool).await
+}
+
+/// Projects and pages matching `query`. A failed search shows no results.
+pub async fn get_search_template(pool: &PgPool, query: &str) -> SearchTemplate {
+    let query = query.trim().to_string();
+    let results = search::search(pool, &query, search::MAX_RESULTS)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::error!("Search for {:?} failed: {}", query, e);
+            Vec::new()
+        });
+    SearchTemplate { query, results, index_url: None, footer: get_footer(pool).await }
+}
+
+pub async fn search(State(pool): State<PgPool>, Query(params): Query<SearchQuery>) -> impl IntoResponse {
+    get_search_template(&pool, &params.q).await
+}
+
+/// Serve a locally stored upload as an attachment under its original name.
+/// Anything else is redirected to wherever it is hosted. Only files a File
+/// block offers can be downloaded; the rest of the library is not found.
+pub async fn download_media(
+    State(pool): State<PgPool>,
+    State(storage): State<Storage>,
+    Path(id): Path<Uuid>,
+    request: Request,
+) -> Response {
+    let Some(item) = crate::media::find_downloadable(&pool, id).await else {
+        return not_found(&pool).await;
+    };
+    let (Some(_), Some(path)) = (storage.local(), item.local_path.as_deref()) else {
+        return Redirect::temporary(&item.url).into_response();
+    };
+
+    let mut response = match ServeFile::new(path).oneshot(request).await {
+        Ok(response) => response.map(Body::new),
+        Err(never) => match never {},
+    };
+    if response.status().is_success() {
+        if let Ok(value) = HeaderValue::from_str(&crate::media::content_disposition(&item.original_name)) {
+            response.headers_mut().insert(header::CONTENT_DISPOSITION, value);
+        }
+    }
+    response
+}
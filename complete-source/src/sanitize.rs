@@ -0,0 +1,409 @@
+//! Allow-list HTML sanitising for rich text: Text blocks, pages and the footer.
+//!
+//! The default policy covers what the Quill editor and the page forms
+//! produce. `HTML_ALLOWED_TAGS` (`figure,table`), `HTML_ALLOWED_ATTRIBUTES`
+//! (`img:loading,*:lang`) and `HTML_DENIED_TAGS` (`img`) adjust it. Content
+//! is cleaned when it's saved and again when it's rendered, so tightening the
+//! policy takes effect without rewriting the database; `sanitize_content`
+//! rewrites it anyway and reports what was removed.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::models::{BlockContent, TextBlock, TextFormat};
+
+const DEFAULT_TAGS: &[&str] = &[
+    "a", "b", "blockquote", "br", "code", "del", "div", "em", "figcaption", "figure", "h1", "h2", "h3", "h4", "h5",
+    "h6", "hr", "i", "img", "li", "ol", "p", "pre", "s", "small", "span", "strong", "sub", "sup", "table", "tbody",
+    "td", "th", "thead", "tr", "u", "ul",
+];
+const DEFAULT_TAG_ATTRIBUTES: &[(&str, &str)] = &[
+    ("a", "href"),
+    ("a", "title"),
+    ("a", "target"),
+    ("img", "src"),
+    ("img", "alt"),
+    ("img", "width"),
+    ("img", "height"),
+    ("ol", "start"),
+    // Footnote anchors from Markdown; see `markdown::to_html`.
+    ("a", "id"),
+    ("div", "id"),
+    // Markdown table column alignment, limited to `text-align` below.
+    ("th", "style"),
+    ("td", "style"),
+];
+const URL_SCHEMES: &[&str] = &["http", "https", "mailto", "tel"];
+/// Quill marks alignment, indentation and code blocks with `ql-*` classes
+/// and Markdown footnotes use `footnote*`; other classes could hook into the
+/// site's own CSS and are dropped.
+const CLASS_PREFIXES: &[&str] = &["ql-", "footnote"];
+const ID_PREFIXES: &[&str] = &["fn-", "fnref-"];
+const STYLE_PROPERTIES: &[&str] = &["text-align"];
+/// What an embedded player's iframe may keep, see [`Policy::for_embed`].
+const IFRAME_ATTRIBUTES: &[&str] = &["src", "allow", "allowfullscreen", "title", "width", "height"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Policy {
+    pub tags: BTreeSet<String>,
+    /// Attributes allowed on any allowed tag.
+    pub generic_attributes: BTreeSet<String>,
+    pub tag_attributes: BTreeMap<String, BTreeSet<String>>,
+    /// Origins an `iframe` may load, e.g. `https://player.vimeo.com`.
+    pub frame_origins: Vec<String>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        let mut tag_attributes: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for (tag, attr) in DEFAULT_TAG_ATTRIBUTES {
+            tag_attributes.entry(tag.to_string()).or_default().insert(attr.to_string());
+        }
+        Self {
+            tags: DEFAULT_TAGS.iter().map(|t| t.to_string()).collect(),
+            generic_attributes: ["class", "title"].iter().map(|a| a.to_string()).collect(),
+            tag_attributes,
+            frame_origins: Vec::new(),
+        }
+    }
+}
+
+fn env_list(name: &str) -> Vec<String> {
+    std::env::var(name)
+        .unwrap_or_default()
+        .split(',')
+        .map(|s| s.trim().to_lowercase())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+impl Policy {
+    pub fn from_env() -> Self {
+        let mut policy = Self::default();
+        policy.allow_tags(&env_list("HTML_ALLOWED_TAGS"));
+        policy.deny_tags(&env_list("HTML_DENIED_TAGS"));
+        for entry in env_list("HTML_ALLOWED_ATTRIBUTES") {
+            match entry.split_once(':') {
+                Some(("*", attr)) => policy.allow_attribute(None, attr),
+                Some((tag, attr)) => policy.allow_attribute(Some(tag), attr),
+                None => policy.allow_attribute(None, &entry),
+            }
+        }
+        policy
+    }
+
+    /// Read once from the environment, like the map tile settings.
+    pub fn global() -> &'static Policy {
+        static POLICY: OnceLock<Policy> = OnceLock::new();
+        POLICY.get_or_init(Policy::from_env)
+    }
+
+    /// For the HTML an oEmbed provider returns: this policy plus iframes
+    /// loaded from `frame_origins`, the provider's player. Any other iframe
+    /// loses its `src`, and scripts go as they always do.
+    pub fn for_embed(&self, frame_origins: &[String]) -> Policy {
+        let mut policy = self.clone();
+        policy.tags.insert("iframe".to_string());
+        policy
+            .tag_attributes
+            .insert("iframe".to_string(), IFRAME_ATTRIBUTES.iter().map(|a| a.to_string()).collect());
+        policy.frame_origins = frame_origins.to_vec();
+        policy
+    }
+
+    /// Script and style contents are always removed along with the tag, so
+    /// they can't be allowed; neither can event handlers or `style`.
+    pub fn allow_tags(&mut self, tags: &[String]) {
+        for tag in tags.iter().filter(|t| !matches!(t.as_str(), "script" | "style")) {
+            self.tags.insert(tag.clone());
+        }
+    }
+
+    pub fn deny_tags(&mut self, tags: &[String]) {
+        for tag in tags {
+            self.tags.remove(tag);
+            self.tag_attributes.remove(tag);
+        }
+    }
+
+    pub fn allow_attribute(&mut self, tag: Option<&str>, attr: &str) {
+        // `rel` is set on every link by the sanitiser itself.
+        if attr.starts_with("on") || matches!(attr, "style" | "rel") {
+            return;
+        }
+        match tag {
+            Some(tag) => {
+                self.tag_attributes.entry(tag.to_string()).or_default().insert(attr.to_string());
+            }
+            None => {
+                self.generic_attributes.insert(attr.to_string());
+            }
+        }
+    }
+
+    fn builder(&self) -> ammonia::Builder<'_> {
+        let frame_origins = self.frame_origins.clone();
+        let mut builder = ammonia::Builder::default();
+        builder
+            .tags(self.tags.iter().map(String::as_str).collect())
+            .generic_attributes(self.generic_attributes.iter().map(String::as_str).collect())
+            .tag_attributes(
+                self.tag_attributes
+                    .iter()
+                    .map(|(tag, attrs)| (tag.as_str(), attrs.iter().map(String::as_str).collect::<HashSet<_>>()))
+                    .collect::<HashMap<_, _>>(),
+            )
+            .url_schemes(URL_SCHEMES.iter().copied().collect())
+            .link_rel(Some("noopener noreferrer"))
+            .filter_style_properties(STYLE_PROPERTIES.iter().copied().collect())
+            .attribute_filter(move |element, attr, value| match attr {
+                "src" if element == "iframe" => frame_src(&frame_origins, value),
+                "class" => {
+                    let classes: Vec<&str> = value
+                        .split_whitespace()
+                        .filter(|c| CLASS_PREFIXES.iter().any(|p| c.starts_with(p)))
+                        .collect();
+                    (!classes.is_empty()).then(|| Cow::Owned(classes.join(" ")))
+                }
+                "id" => ID_PREFIXES.iter().any(|p| value.starts_with(p)).then_some(Cow::Borrowed(value)),
+                "target" => (value == "_blank").then_some(Cow::Borrowed(value)),
+                _ => Some(Cow::Borrowed(value)),
+            });
+        builder
+    }
+
+    pub fn clean(&self, html: &str) -> String {
+        self.builder().clean(html).to_string()
+    }
+
+    /// Like `clean`, but also says which tags and attributes were removed.
+    pub fn clean_with_report(&self, html: &str) -> (String, Stripped) {
+        let cleaned = self.clean(html);
+        let stripped = Stripped::between(html, &cleaned);
+        (cleaned, stripped)
+    }
+}
+
+/// An iframe `src` on one of `origins`, made absolute, or `None`.
+fn frame_src<'u>(origins: &[String], value: &'u str) -> Option<Cow<'u, str>> {
+    let src = match value.strip_prefix("//") {
+        Some(rest) => Cow::Owned(format!("https://{}", rest)),
+        None => Cow::Borrowed(value),
+    };
+    let allowed = origins.iter().any(|origin| {
+        src.strip_prefix(origin.as_str()).is_some_and(|rest| rest.is_empty() || rest.starts_with(['/', '?']))
+    });
+    allowed.then_some(src)
+}
+
+/// Cleans `html` with the global policy.
+pub fn clean(html: &str) -> String {
+    Policy::global().clean(html)
+}
+
+/// The text of an HTML fragment without tags, e.g. for previews.
+pub fn to_plain_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find('>').map_or(rest.len(), |end| end + 1);
+        let tag = rest[..end].to_ascii_lowercase();
+        rest = &rest[end..];
+        if tag.starts_with("<script") || tag.starts_with("<style") {
+            let close = if tag.starts_with("<script") { "</script" } else { "</style" };
+            rest = rest.to_ascii_lowercase().find(close).map_or("", |at| &rest[at..]);
+        }
+        // Keep words in neighbouring blocks apart.
+        text.push(' ');
+    }
+    text.push_str(rest);
+    crate::markdown::collapse_whitespace(&decode_entities(&text))
+}
+
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end + 1))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Tags and attributes (`tag[attr]`) that sanitising removed, with counts.
+/// An attribute with an unsafe value, e.g. a `javascript:` href, is removed
+/// as a whole and so shows up here too.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Stripped {
+    pub tags: BTreeMap<String, usize>,
+    pub attributes: BTreeMap<String, usize>,
+}
+
+impl Stripped {
+    pub fn between(before: &str, after: &str) -> Self {
+        let (tags_before, attrs_before) = inventory(before);
+        let (tags_after, attrs_after) = inventory(after);
+        Self { tags: difference(tags_before, &tags_after), attributes: difference(attrs_before, &attrs_after) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.attributes.is_empty()
+    }
+
+    pub fn merge(&mut self, other: Stripped) {
+        for (tag, n) in other.tags {
+            *self.tags.entry(tag).or_default() += n;
+        }
+        for (attr, n) in other.attributes {
+            *self.attributes.entry(attr).or_default() += n;
+        }
+    }
+}
+
+impl fmt::Display for Stripped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tags = self.tags.iter().map(|(tag, n)| (format!("<{}>", tag), *n));
+        let attrs = self.attributes.iter().map(|(attr, n)| (attr.clone(), *n));
+        let parts: Vec<String> = tags
+            .chain(attrs)
+            .map(|(name, n)| if n == 1 { name } else { format!("{} ×{}", name, n) })
+            .collect();
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+fn difference(before: BTreeMap<String, usize>, after: &BTreeMap<String, usize>) -> BTreeMap<String, usize> {
+    before
+        .into_iter()
+        .filter_map(|(key, n)| {
+            let removed = n.saturating_sub(after.get(&key).copied().unwrap_or(0));
+            (removed > 0).then_some((key, removed))
+        })
+        .collect()
+}
+
+/// Counts opening tags and `tag[attr]` pairs. Good enough to compare
+/// input with the sanitiser's output; not a general HTML parser.
+fn inventory(html: &str) -> (BTreeMap<String, usize>, BTreeMap<String, usize>) {
+    let mut tags = BTreeMap::new();
+    let mut attrs = BTreeMap::new();
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        if let Some(comment) = rest.strip_prefix("!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let name_len = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '-').unwrap_or(rest.len());
+        if name_len == 0 || !rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            continue;
+        }
+        let name = rest[..name_len].to_ascii_lowercase();
+        rest = &rest[name_len..];
+        *tags.entry(name.clone()).or_default() += 1;
+
+        // Attributes up to the closing '>', skipping over quoted values.
+        loop {
+            rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+            if rest.is_empty() || rest.starts_with('>') {
+                break;
+            }
+            let attr_len = rest
+                .find(|c: char| c.is_whitespace() || matches!(c, '=' | '>' | '/'))
+                .unwrap_or(rest.len())
+                .max(1);
+            let attr = rest[..attr_len].to_ascii_lowercase();
+            rest = rest[attr_len..].trim_start();
+            if let Some(after_eq) = rest.strip_prefix('=') {
+                let after_eq = after_eq.trim_start();
+                rest = match after_eq.chars().next() {
+                    Some(q @ ('"' | '\'')) => {
+                        let body = &after_eq[1..];
+                        body.find(q).map_or("", |end| &body[end + 1..])
+                    }
+                    _ => {
+                        let end = after_eq.find(|c: char| c.is_whitespace() || c == '>').unwrap_or(after_eq.len());
+                        &after_eq[end..]
+                    }
+                };
+            }
+            *attrs.entry(format!("{}[{}]", name, attr)).or_default() += 1;
+        }
+
+        // Script and style bodies are raw text, not markup.
+        if name == "script" || name == "style" {
+            let close = format!("</{}", name);
+            rest = rest.to_ascii_lowercase().find(&close).map_or("", |end| &rest[end..]);
+        }
+    }
+
+    (tags, attrs)
+}
+
+impl BlockContent {
+    /// Cleans the rich text in this block, including Text blocks nested in
+    /// Columns. Other kinds are structured data and escaped on render.
+    pub fn sanitize(&mut self, policy: &Policy) -> Stripped {
+        match self {
+            BlockContent::Text(text) => text.sanitize(policy),
+            BlockContent::Columns(layout) => {
+                let mut stripped = Stripped::default();
+                for child in layout.columns.iter_mut().flatten() {
+                    stripped.merge(child.sanitize(policy));
+                }
+                stripped
+            }
+            _ => Stripped::default(),
+        }
+    }
+}
+
+impl TextBlock {
+    /// Re-renders the cached HTML from the source. HTML sources are cleaned
+    /// too, so what the editor reopens is what visitors see.
+    pub fn sanitize(&mut self, policy: &Policy) -> Stripped {
+        let (html, stripped) = policy.clean_with_report(&self.format.to_html(&self.source));
+        if self.format == TextFormat::Html {
+            self.source = html.clone();
+        }
+        self.html = html;
+        stripped
+    }
+}
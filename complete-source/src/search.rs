@@ -0,0 +1,192 @@
+//! Full-text search over projects, their Text blocks and the public pages.
+//!
+//! Postgres does the work: triggers keep a `search_vector` on each table,
+//! stemmed in the site's [`SearchLanguage`](crate::settings::SearchLanguage),
+//! and a query ranks the matches and cuts a snippet around them. Blocks and
+//! pages hold their text as HTML or Markdown, so the app writes the plain
+//! text to `search_text` whenever one is saved.
+
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::BlockContent;
+
+/// Pages a visitor can reach, and so the only ones searched.
+pub const PUBLIC_PAGES: &[&str] = &["about", "contact"];
+
+/// The most results a search shows.
+pub const MAX_RESULTS: i64 = 50;
+
+/// The `?q=` of a search page.
+#[derive(Debug, Default, Deserialize)]
+pub struct SearchQuery {
+    #[serde(default)]
+    pub q: String,
+}
+
+/// Marks the matched words in a snippet from Postgres. Private-use
+/// characters can't clash with anything in the text, and are swapped for
+/// `<mark>` once the snippet is escaped.
+const START_MATCH: char = '\u{E000}';
+const STOP_MATCH: char = '\u{E001}';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HitKind {
+    Project,
+    Page,
+}
+
+/// A project or page matching a search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub kind: HitKind,
+    /// The project's id; pages are known by their slug.
+    pub id: Option<Uuid>,
+    pub slug: String,
+    pub title: String,
+    /// HTML: the text around the matches, with each one in a `<mark>`.
+    pub snippet: String,
+}
+
+impl SearchHit {
+    pub fn public_path(&self) -> String {
+        match self.kind {
+            HitKind::Project => format!("/project/{}", self.slug),
+            HitKind::Page => format!("/{}", self.slug),
+        }
+    }
+
+    pub fn admin_path(&self) -> String {
+        match (self.kind, self.id) {
+            (HitKind::Project, Some(id)) => format!("/admin/projects/edit/{}", id),
+            _ => format!("/admin/pages/edit/{}", self.slug),
+        }
+    }
+
+    pub fn kind_label(&self) -> &'static str {
+        match self.kind {
+            HitKind::Project => "Project",
+            HitKind::Page => "Page",
+        }
+    }
+}
+
+/// The words a block adds to the search, or `None` if it has no text.
+pub fn block_text(content: &BlockContent) -> Option<String> {
+    let text = match content {
+        BlockContent::Text(text) => text.plain_text(),
+        BlockContent::Columns(columns) => columns
+            .columns
+            .iter()
+            .flatten()
+            .filter_map(block_text)
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        _ => return None,
+    };
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Escapes a snippet from Postgres and turns its match markers into
+/// `<mark>` elements.
+pub fn highlight(snippet: &str) -> String {
+    let mut html = String::with_capacity(snippet.len() + 32);
+    let mut open = false;
+    for c in snippet.chars() {
+        match c {
+            START_MATCH if !open => {
+                html.push_str("<mark>");
+                open = true;
+            }
+            STOP_MATCH if open => {
+                html.push_str("</mark>");
+                open = false;
+            }
+            START_MATCH | STOP_MATCH => {}
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+    if open {
+        html.push_str("</mark>");
+    }
+    html
+}
+
+#[derive(sqlx::FromRow)]
+struct HitRow {
+    kind: String,
+    id: Option<Uuid>,
+    slug: String,
+    title: String,
+    snippet: String,
+}
+
+/// Projects and pages matching `query`, best first. A project is listed
+/// once, with the snippet from its best-matching text. The query is read
+/// the way search engines do: words, `"quoted phrases"`, `or` and `-word`.
+pub async fn search(pool: &PgPool, query: &str, limit: i64) -> Result<Vec<SearchHit>, sqlx::Error> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let options = format!("StartSel={}, StopSel={}, MaxWords=35, MinWords=15", START_MATCH, STOP_MATCH);
+    let rows = sqlx::query_as::<_, HitRow>(
+        "WITH q AS (SELECT websearch_to_tsquery(search_config(), $1) AS query),
+         hits AS (
+             SELECT 'project' AS kind, p.id, p.slug, p.title,
+                    ts_rank(p.search_vector, q.query) AS rank,
+                    ts_headline(search_config(), COALESCE(p.description, ''), q.query, $2) AS snippet
+             FROM projects p, q WHERE p.search_vector @@ q.query
+             UNION ALL
+             SELECT 'project', p.id, p.slug, p.title,
+                    ts_rank(b.search_vector, q.query),
+                    ts_headline(search_config(), b.search_text, q.query, $2)
+             FROM content_blocks b JOIN projects p ON p.id = b.project_id, q
+             WHERE b.search_vector @@ q.query
+             UNION ALL
+             SELECT 'page', NULL, pg.slug, pg.title,
+                    ts_rank(pg.search_vector, q.query),
+                    ts_headline(search_config(), COALESCE(pg.search_text, ''), q.query, $2)
+             FROM pages pg, q WHERE pg.slug = ANY($3) AND pg.search_vector @@ q.query
+         )
+         SELECT kind, id, slug, title, snippet FROM (
+             SELECT DISTINCT ON (kind, slug) * FROM hits ORDER BY kind, slug, rank DESC
+         ) best
+         ORDER BY rank DESC, title
+         LIMIT $4"
+    )
+    .bind(query)
+    .bind(options)
+    .bind(PUBLIC_PAGES)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| SearchHit {
+            kind: if row.kind == "page" { HitKind::Page } else { HitKind::Project },
+            id: row.id,
+            slug: row.slug,
+            title: row.title,
+            snippet: highlight(&row.snippet),
+        })
+        .collect())
+}
+
+/// Recomputes every search vector, after the search language changes.
+/// Saving a row is enough: the triggers rebuild its vector.
+pub async fn reindex(pool: &PgPool) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("UPDATE projects SET search_vector = NULL").execute(&mut *tx).await?;
+    sqlx::query("UPDATE content_blocks SET search_vector = NULL WHERE search_text IS NOT NULL").execute(&mut *tx).await?;
+    sqlx::query("UPDATE pages SET search_vector = NULL").execute(&mut *tx).await?;
+    tx.commit().await
+}
@@ -0,0 +1,145 @@
+//! The search index `gen_static` writes, so the static site can search
+//! without a server. It covers what [`search`](crate::search) does: project
+//! titles, descriptions, places and Text blocks, and the public pages.
+//!
+//! The index is a small manifest listing JSON chunks of entries. Chunks are
+//! kept under a size limit, so a large portfolio is fetched in pieces that
+//! can be searched as they arrive rather than as one huge file.
+
+use serde::Serialize;
+
+use crate::models::{BlockContent, ContentBlock, Page, Project};
+use crate::search;
+
+/// Where the manifest is published, relative to the site root.
+pub const MANIFEST_PATH: &str = "/search/index.json";
+
+/// The most text an entry carries, in characters. Enough to find a work by
+/// what's said about it without shipping every word of every project.
+pub const EXCERPT_CHARS: usize = 1000;
+
+/// The size a chunk is kept under, in bytes.
+pub const CHUNK_BYTES: usize = 64 * 1024;
+
+/// One project or page in the index.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IndexEntry {
+    pub title: String,
+    pub slug: String,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub year: Option<i32>,
+    /// The place, and the kinds of media in the project, e.g. "video".
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Plain text, cut to [`EXCERPT_CHARS`].
+    pub text: String,
+}
+
+impl IndexEntry {
+    pub fn project(project: &Project, blocks: &[ContentBlock]) -> IndexEntry {
+        let mut tags: Vec<String> = project.location_name.iter().map(|place| place.trim().to_string()).collect();
+        for block in blocks {
+            if let Some(tag) = media_tag(&block.content) {
+                if !tags.iter().any(|t| t == tag) {
+                    tags.push(tag.to_string());
+                }
+            }
+        }
+        tags.retain(|t| !t.is_empty());
+
+        let mut text: Vec<String> = project.description.iter().cloned().collect();
+        text.extend(blocks.iter().filter_map(|block| search::block_text(&block.content)));
+
+        IndexEntry {
+            title: project.title.clone(),
+            slug: project.slug.clone(),
+            url: format!("/project/{}", project.slug),
+            year: Some(project.start_date.year()),
+            tags,
+            text: excerpt(&text.join(" "), EXCERPT_CHARS),
+        }
+    }
+
+    pub fn page(page: &Page) -> IndexEntry {
+        IndexEntry {
+            title: page.title.clone(),
+            slug: page.slug.clone(),
+            url: format!("/{}", page.slug),
+            year: None,
+            tags: Vec::new(),
+            text: excerpt(&page.format.to_plain_text(&page.source), EXCERPT_CHARS),
+        }
+    }
+}
+
+/// The tag a block adds for the kind of media it shows.
+fn media_tag(content: &BlockContent) -> Option<&'static str> {
+    match content {
+        BlockContent::Video(_) | BlockContent::HostedVideo(_) => Some("video"),
+        BlockContent::Audio(_) => Some("audio"),
+        BlockContent::Gallery(_) => Some("images"),
+        BlockContent::Map(_) => Some("map"),
+        _ => None,
+    }
+}
+
+/// `text` on one line, cut at a word boundary to at most `max_chars`.
+pub fn excerpt(text: &str, max_chars: usize) -> String {
+    let mut excerpt = String::new();
+    let mut chars = 0;
+    for word in text.split_whitespace() {
+        let needed = word.chars().count() + usize::from(!excerpt.is_empty());
+        if chars + needed > max_chars {
+            break;
+        }
+        if !excerpt.is_empty() {
+            excerpt.push(' ');
+        }
+        excerpt.push_str(word);
+        chars += needed;
+    }
+    excerpt
+}
+
+/// What `gen_static` writes: the manifest, and each chunk's file name and
+/// JSON. Chunk files sit next to the manifest.
+#[derive(Debug)]
+pub struct StaticIndex {
+    pub manifest: String,
+    pub chunks: Vec<(String, String)>,
+}
+
+#[derive(Serialize)]
+struct Manifest<'a> {
+    entries: usize,
+    chunks: &'a [String],
+}
+
+/// Splits `entries` into JSON arrays of at most `max_bytes` each. An entry
+/// bigger than that on its own gets a chunk to itself.
+pub fn build(entries: &[IndexEntry], max_bytes: usize) -> Result<StaticIndex, serde_json::Error> {
+    let mut chunks: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for entry in entries {
+        let json = serde_json::to_string(entry)?;
+        // Room for the comma before it and both brackets.
+        if !current.is_empty() && current.len() + json.len() + 3 > max_bytes {
+            chunks.push(format!("[{}]", current));
+            current.clear();
+        }
+        if !current.is_empty() {
+            current.push(',');
+        }
+        current.push_str(&json);
+    }
+    if !current.is_empty() {
+        chunks.push(format!("[{}]", current));
+    }
+
+    let base = MANIFEST_PATH.rsplit_once('/').map_or("", |(dir, _)| dir);
+    let names: Vec<String> = (0..chunks.len()).map(|i| format!("chunk-{}.json", i)).collect();
+    let urls: Vec<String> = names.iter().map(|name| format!("{}/{}", base, name)).collect();
+    let manifest = serde_json::to_string(&Manifest { entries: entries.len(), chunks: &urls })?;
+    Ok(StaticIndex { manifest, chunks: names.into_iter().zip(chunks).collect() })
+}
@@ -0,0 +1,168 @@
+//! Site-wide settings edited in the admin, stored as key/value rows in
+//! `site_settings`. Missing or unreadable values fall back to the defaults.
+
+use sqlx::PgPool;
+
+/// How projects are ordered on the index and the dashboard.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IndexOrder {
+    #[default]
+    Chronological,
+    Manual,
+}
+
+impl IndexOrder {
+    pub const ALL: &'static [IndexOrder] = &[IndexOrder::Chronological, IndexOrder::Manual];
+
+    /// The value stored and used in forms, e.g. "manual".
+    pub fn name(&self) -> &'static str {
+        match self {
+            IndexOrder::Chronological => "chronological",
+            IndexOrder::Manual => "manual",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            IndexOrder::Chronological => "Newest first",
+            IndexOrder::Manual => "Manual order",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<IndexOrder> {
+        Self::ALL.iter().copied().find(|o| o.name().eq_ignore_ascii_case(s.trim()))
+    }
+
+    /// The `ORDER BY` clause for `projects`. In manual order, projects that
+    /// were never placed come after the placed ones, newest first.
+    pub fn order_by(&self) -> &'static str {
+        match self {
+            IndexOrder::Chronological => "start_date DESC",
+            IndexOrder::Manual => "position ASC NULLS LAST, start_date DESC",
+        }
+    }
+}
+
+/// The language search stems words in, so "paintings" finds "painting".
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchLanguage {
+    #[default]
+    English,
+    Dutch,
+    French,
+    German,
+    /// Matches words exactly as written, for mixed-language sites.
+    Simple,
+}
+
+impl SearchLanguage {
+    pub const ALL: &'static [SearchLanguage] = &[
+        SearchLanguage::English,
+        SearchLanguage::Dutch,
+        SearchLanguage::French,
+        SearchLanguage::German,
+        SearchLanguage::Simple,
+    ];
+
+    /// The value stored, which is also the Postgres text search
+    /// configuration, e.g. "dutch".
+    pub fn name(&self) -> &'static str {
+        match self {
+            SearchLanguage::English => "english",
+            SearchLanguage::Dutch => "dutch",
+            SearchLanguage::French => "french",
+            SearchLanguage::German => "german",
+            SearchLanguage::Simple => "simple",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SearchLanguage::English => "English",
+            SearchLanguage::Dutch => "Dutch",
+            SearchLanguage::French => "French",
+            SearchLanguage::German => "German",
+            SearchLanguage::Simple => "No stemming",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<SearchLanguage> {
+        Self::ALL.iter().copied().find(|l| l.name().eq_ignore_ascii_case(s.trim()))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SiteSettings {
+    pub index_order: IndexOrder,
+    pub search_language: SearchLanguage,
+    /// Extra lines for `/robots.txt`, see [`robots`](crate::robots).
+    pub robots_rules: String,
+    /// The artist's name in structured data, see [`json_ld`](crate::json_ld).
+    /// Empty for the site's title.
+    pub artist_name: String,
+    /// The artist's profiles elsewhere, one URL per line.
+    pub artist_links: String,
+}
+
+impl SiteSettings {
+    pub async fn load(pool: &PgPool) -> SiteSettings {
+        let rows: Vec<(String, String)> = sqlx::query_as("SELECT key, value FROM site_settings")
+            .fetch_all(pool)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::error!("Failed to load site settings: {}", e);
+                Vec::new()
+            });
+        SiteSettings::from_rows(rows)
+    }
+
+    pub fn from_rows(rows: impl IntoIterator<Item = (String, String)>) -> SiteSettings {
+        let mut settings = SiteSettings::default();
+        for (key, value) in rows {
+            match key.as_str() {
+                "index_order" => match IndexOrder::parse(&value) {
+                    Some(order) => settings.index_order = order,
+                    None => tracing::warn!("Ignoring unknown index_order {:?}", value),
+                },
+                "search_language" => match SearchLanguage::parse(&value) {
+                    Some(language) => settings.search_language = language,
+                    None => tracing::warn!("Ignoring unknown search_language {:?}", value),
+                },
+                "robots_rules" => settings.robots_rules = value,
+                "artist_name" => settings.artist_name = value,
+                "artist_links" => settings.artist_links = value,
+                _ => {}
+            }
+        }
+        settings
+    }
+
+    pub fn to_rows(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("index_order", self.index_order.name().to_string()),
+            ("search_language", self.search_language.name().to_string()),
+            ("robots_rules", self.robots_rules.clone()),
+            ("artist_name", self.artist_name.clone()),
+            ("artist_links", self.artist_links.clone()),
+        ]
+    }
+
+    pub fn artist_links(&self) -> impl Iterator<Item = &str> {
+        self.artist_links.lines().map(str::trim).filter(|l| !l.is_empty())
+    }
+
+    pub async fn save(&self, pool: &PgPool) -> Result<(), sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        for (key, value) in self.to_rows() {
+            sqlx::query(
+                "INSERT INTO site_settings (key, value) VALUES ($1, $2)
+                 ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value, updated_at = NOW()"
+            )
+            .bind(key)
+            .bind(value)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await
+    }
+}
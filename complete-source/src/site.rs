@@ -0,0 +1,46 @@
+//! Where the public site lives, for the absolute URLs that feeds need.
+//!
+//! `SITE_URL` is the address visitors use, e.g. `https://stefmeul.net`, and
+//! `SITE_TITLE` the name the site goes by. The live server and `gen_static`
+//! read the same variables, so what they publish agrees.
+
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Site {
+    /// Without a trailing slash.
+    pub url: String,
+    pub title: String,
+}
+
+impl Site {
+    const DEFAULT_URL: &'static str = "https://stefmeul.net";
+    const DEFAULT_TITLE: &'static str = "Stef Meul";
+
+    pub fn new(url: &str, title: &str) -> Self {
+        Self { url: url.trim().trim_end_matches('/').to_string(), title: title.trim().to_string() }
+    }
+
+    pub fn from_env() -> Self {
+        let var = |name: &str, default: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty()).unwrap_or_else(|| default.to_string());
+        Self::new(&var("SITE_URL", Self::DEFAULT_URL), &var("SITE_TITLE", Self::DEFAULT_TITLE))
+    }
+
+    /// Read once from the environment.
+    pub fn global() -> &'static Site {
+        static SITE: OnceLock<Site> = OnceLock::new();
+        SITE.get_or_init(Site::from_env)
+    }
+
+    /// `url` as an absolute URL. Paths are taken from the site root, and
+    /// URLs that already have a host, like Cloudinary's, are kept.
+    pub fn absolute(&self, url: &str) -> String {
+        if url.starts_with("https://") || url.starts_with("http://") {
+            url.to_string()
+        } else if let Some(rest) = url.strip_prefix("//") {
+            format!("https://{}", rest)
+        } else {
+            format!("{}/{}", self.url, url.trim_start_matches('/'))
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! `/sitemap.xml`, listing every public page with when it last changed so
+//! search engines can find new work and skip what they've already seen.
+//!
+//! A sitemap holds at most [`MAX_URLS`] addresses. A site with more gets a
+//! sitemap index at `/sitemap.xml` instead, pointing at parts under
+//! `/sitemap/`. The server and `gen_static` publish the same [`files`].
+
+use askama::Template;
+use sqlx::PgPool;
+use time::format_description::well_known::Rfc3339;
+use time::{OffsetDateTime, UtcOffset};
+
+use crate::search;
+use crate::site::Site;
+
+pub const PATH: &str = "/sitemap.xml";
+
+pub const CONTENT_TYPE: &str = "application/xml; charset=utf-8";
+
+/// The most addresses one sitemap may list, by the sitemaps protocol.
+pub const MAX_URLS: usize = 50_000;
+
+/// One address in a sitemap, or one part in a sitemap index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SitemapUrl {
+    /// Absolute.
+    pub loc: String,
+    pub lastmod: Option<OffsetDateTime>,
+}
+
+impl SitemapUrl {
+    pub fn new(site: &Site, path: &str, lastmod: Option<OffsetDateTime>) -> SitemapUrl {
+        SitemapUrl { loc: site.absolute(path), lastmod }
+    }
+
+    /// In UTC to the second, e.g. `2024-03-01T12:30:00Z`.
+    pub fn lastmod_w3c(&self) -> Option<String> {
+        let lastmod = self.lastmod?.to_offset(UtcOffset::UTC).replace_nanosecond(0).ok()?;
+        lastmod.format(&Rfc3339).ok()
+    }
+}
+
+/// The home page, the map, every project and the public pages. The home
+/// page and the map show the projects, so they change when one does.
+pub async fn load(pool: &PgPool, site: &Site) -> Result<Vec<SitemapUrl>, sqlx::Error> {
+    let projects: Vec<(String, OffsetDateTime)> =
+        sqlx::query_as("SELECT slug, updated_at FROM projects ORDER BY start_date DESC, title")
+            .fetch_all(pool)
+            .await?;
+    let pages: Vec<(String, OffsetDateTime)> =
+        sqlx::query_as("SELECT slug, updated_at FROM pages WHERE slug = ANY($1) ORDER BY slug")
+            .bind(search::PUBLIC_PAGES)
+            .fetch_all(pool)
+            .await?;
+
+    let newest = projects.iter().map(|(_, updated_at)| *updated_at).max();
+    let mut urls = vec![SitemapUrl::new(site, "/", newest), SitemapUrl::new(site, "/map", newest)];
+    urls.extend(pages.iter().map(|(slug, updated_at)| SitemapUrl::new(site, &format!("/{}", slug), Some(*updated_at))));
+    urls.extend(
+        projects
+            .iter()
+            .map(|(slug, updated_at)| SitemapUrl::new(site, &format!("/project/{}", slug), Some(*updated_at))),
+    );
+    Ok(urls)
+}
+
+/// Where part `n` of a split sitemap is published, counting from 1.
+pub fn part_path(n: usize) -> String {
+    format!("/sitemap/{}.xml", n)
+}
+
+#[derive(Template)]
+#[template(path = "sitemaps/urlset.xml")]
+struct UrlsetTemplate<'a> {
+    urls: &'a [SitemapUrl],
+}
+
+#[derive(Template)]
+#[template(path = "sitemaps/index.xml")]
+struct IndexTemplate<'a> {
+    parts: &'a [SitemapUrl],
+}
+
+/// Each file's path and XML, `/sitemap.xml` first. That's the sitemap
+/// itself while `urls` fit in one, and an index of the parts after that.
+pub fn files(site: &Site, urls: &[SitemapUrl], max_urls: usize) -> Result<Vec<(String, String)>, askama::Error> {
+    if urls.len() <= max_urls {
+        return Ok(vec![(PATH.to_string(), UrlsetTemplate { urls }.render()?)]);
+    }
+
+    let chunks: Vec<&[SitemapUrl]> = urls.chunks(max_urls.max(1)).collect();
+    let parts: Vec<SitemapUrl> = chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| SitemapUrl::new(site, &part_path(i + 1), chunk.iter().filter_map(|u| u.lastmod).max()))
+        .collect();
+
+    let mut files = vec![(PATH.to_string(), IndexTemplate { parts: &parts }.render()?)];
+    for (i, chunk) in chunks.iter().enumerate() {
+        files.push((part_path(i + 1), UrlsetTemplate { urls: chunk }.render()?));
+    }
+    Ok(files)
+}
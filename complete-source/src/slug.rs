@@ -0,0 +1,98 @@
+//! Project slugs: generated from titles, validated, and kept unique.
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub const MAX_LEN: usize = 80;
+
+/// Lowercase ASCII letters and digits in hyphen-separated words, with
+/// accented and other non-Latin characters transliterated ("Café Müller"
+/// becomes "cafe-muller"). Empty if nothing usable is left.
+pub fn slugify(text: &str) -> String {
+    let ascii = deunicode::deunicode(text).to_ascii_lowercase();
+    let mut slug = String::with_capacity(ascii.len());
+    for word in ascii.split(|c: char| !c.is_ascii_alphanumeric()).filter(|w| !w.is_empty()) {
+        if slug.len() + word.len() + 1 > MAX_LEN {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(word);
+    }
+    // A single word longer than the limit.
+    if slug.is_empty() {
+        slug = ascii.chars().filter(char::is_ascii_alphanumeric).take(MAX_LEN).collect();
+    }
+    slug
+}
+
+/// Why a slug can't be used, phrased for the project form.
+pub fn validate(slug: &str) -> Result<(), String> {
+    if slug.is_empty() {
+        return Err("Enter a slug, or a title to make one from".to_string());
+    }
+    if slug.len() > MAX_LEN {
+        return Err(format!("Keep the slug to {} characters or fewer", MAX_LEN));
+    }
+    let words_ok = slug.split('-').all(|w| !w.is_empty() && w.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit()));
+    if !words_ok {
+        return Err("Use lowercase letters, digits and single hyphens between words".to_string());
+    }
+    Ok(())
+}
+
+/// The first of `slug`, `slug-2`, `slug-3`, ... not in `taken`.
+pub fn first_free(slug: &str, taken: &[String]) -> String {
+    if !taken.iter().any(|t| t == slug) {
+        return slug.to_string();
+    }
+    (2..)
+        .map(|n| {
+            let suffix = format!("-{}", n);
+            let mut base = slug.to_string();
+            base.truncate(MAX_LEN - suffix.len());
+            format!("{}{}", base.trim_end_matches('-'), suffix)
+        })
+        .find(|candidate| !taken.contains(candidate))
+        .expect("unbounded")
+}
+
+/// A slug that can't be saved, with a free one to offer instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlugError {
+    pub message: String,
+    pub suggestion: Option<String>,
+}
+
+impl SlugError {
+    pub fn taken(slug: &str, taken: &[String]) -> SlugError {
+        SlugError {
+            message: "Another project already uses this slug".to_string(),
+            suggestion: Some(first_free(slug, taken)),
+        }
+    }
+}
+
+/// Checks a slug against the pattern and the `taken` ones. Invalid slugs
+/// get a suggestion made from themselves, or else from the `title`.
+pub fn check(slug: &str, title: &str, taken: &[String]) -> Result<(), SlugError> {
+    if let Err(message) = validate(slug) {
+        let base = Some(slugify(slug)).filter(|s| !s.is_empty()).unwrap_or_else(|| slugify(title));
+        let suggestion = Some(base).filter(|s| !s.is_empty()).map(|s| first_free(&s, taken));
+        return Err(SlugError { message, suggestion });
+    }
+    if taken.iter().any(|t| t == slug) {
+        return Err(SlugError::taken(slug, taken));
+    }
+    Ok(())
+}
+
+/// The slugs of every project except `except`, i.e. the ones a project
+/// being saved can't take.
+pub async fn taken(pool: &PgPool, except: Option<Uuid>) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar("SELECT slug FROM projects WHERE id IS DISTINCT FROM $1")
+        .bind(except)
+        .fetch_all(pool)
+        .await
+}
@@ -1,13 +1,68 @@
 use askama::Template;
 use axum::response::{Html, IntoResponse, Response};
 use axum::http::StatusCode;
-use crate::models::{Project, ContentBlock, BlockContent, Page};
+use crate::flash::Flash;
+use crate::models::{Project, ContentBlock, BlockContent, BlockKind, MapMarker, MediaItem, Page};
+use crate::redirects::RedirectRule;
+use crate::search::SearchHit;
+use crate::settings::{IndexOrder, SearchLanguage, SiteSettings};
+use crate::slug::SlugError;
+use crate::validation::FieldErrors;
 use uuid::Uuid;
 
+/// Custom askama filters, picked up by every template in this module.
+mod filters {
+    /// Runs stored rich text through the HTML policy before it's marked
+    /// `|safe`, so content saved before a policy change is covered too.
+    pub fn sanitize<T: std::fmt::Display>(html: T) -> askama::Result<String> {
+        Ok(crate::sanitize::clean(&html.to_string()))
+    }
+
+    /// The same for an Embed block's cached HTML, which may also keep the
+    /// iframe of a known provider's player.
+    pub fn sanitize_embed<T: std::fmt::Display>(html: T) -> askama::Result<String> {
+        Ok(crate::oembed::render_policy().clean(&html.to_string()))
+    }
+}
+
 #[derive(Template)]
 #[template(path = "index.html")]
 pub struct IndexTemplate {
+    pub featured: Vec<Project>,
     pub grouped_projects: Vec<(i32, Vec<Project>)>,
+    /// schema.org JSON-LD, see [`json_ld`](crate::json_ld).
+    pub json_ld: String,
+    pub footer: String,
+}
+
+/// The footer shown when none is saved, or it couldn't be loaded.
+pub const DEFAULT_FOOTER: &str = "<p>&copy; 2024</p>";
+
+#[derive(Template)]
+#[template(path = "errors/404.html")]
+pub struct NotFoundTemplate {
+    pub footer: String,
+}
+
+#[derive(Template)]
+#[template(path = "errors/410.html")]
+pub struct GoneTemplate {
+    pub footer: String,
+}
+
+#[derive(Template)]
+#[template(path = "errors/500.html")]
+pub struct ServerErrorTemplate {
+    pub footer: String,
+}
+
+/// Any other error, e.g. from an admin handler.
+#[derive(Template)]
+#[template(path = "errors/error.html")]
+pub struct ErrorTemplate {
+    pub code: u16,
+    pub title: String,
+    pub message: String,
     pub footer: String,
 }
 
@@ -15,13 +70,17 @@ pub struct IndexTemplate {
 #[template(path = "admin/pages_list.html")]
 pub struct PagesListTemplate {
     pub pages: Vec<Page>,
+    pub flashes: Vec<Flash>,
     pub authenticity_token: String,
 }
 
 #[derive(Template)]
 #[template(path = "admin/page_form.html")]
 pub struct PageFormTemplate {
+    /// The saved page, or what was submitted when the form comes back.
     pub page: Page,
+    pub errors: FieldErrors,
+    pub flashes: Vec<Flash>,
     pub authenticity_token: String,
 }
 
@@ -32,22 +91,81 @@ pub struct ProjectTemplate {
     pub blocks: Vec<ContentBlock>,
     pub next_project: Option<Project>,
     pub prev_project: Option<Project>,
+    pub json_ld: String,
     pub footer: String,
 }
 
+/// The markup for a single block. Kept out of `project.html` so Columns can
+/// render their child blocks with it.
+#[derive(Template)]
+#[template(path = "partials/block.html")]
+pub struct BlockTemplate<'a> {
+    pub content: &'a BlockContent,
+}
+
+impl BlockContent {
+    pub fn render_html(&self) -> String {
+        BlockTemplate { content: self }.render().unwrap_or_else(|e| {
+            tracing::error!("Failed to render {} block: {}", self.kind(), e);
+            String::new()
+        })
+    }
+}
+
 #[derive(Template)]
 #[template(path = "admin/project_form.html")]
 pub struct ProjectFormTemplate {
+    /// The saved project, or what was submitted when the form comes back.
     pub project: Option<Project>,
+    pub input: ProjectInput,
+    pub is_new: bool,
+    pub errors: FieldErrors,
+    pub slug_error: Option<SlugError>,
+    pub gallery_images: Vec<String>,
+    pub geocoding: bool,
+    pub flashes: Vec<Flash>,
     pub authenticity_token: String,
 }
 
+/// The project form fields shown as typed rather than from `Project`, as a
+/// refused form may bring back a date or coordinate that doesn't parse.
+#[derive(Debug, Default)]
+pub struct ProjectInput {
+    pub start_date: String,
+    pub end_date: String,
+    pub latitude: String,
+    pub longitude: String,
+}
+
+impl From<&Project> for ProjectInput {
+    fn from(project: &Project) -> Self {
+        let text = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
+        ProjectInput {
+            start_date: project.start_date.to_string(),
+            end_date: project.end_date.map(|d| d.to_string()).unwrap_or_default(),
+            latitude: text(project.latitude),
+            longitude: text(project.longitude),
+        }
+    }
+}
+
 #[derive(Template)]
 #[template(path = "admin/project_blocks.html")]
 pub struct ProjectBlocksTemplate {
     pub project_id: Uuid,
     pub project_title: String,
     pub blocks: Vec<ContentBlock>,
+    pub move_targets: Vec<Project>,
+    pub flashes: Vec<Flash>,
+    pub authenticity_token: String,
+}
+
+/// Just the block rows of `project_blocks.html`, for HTMX swaps.
+#[derive(Template)]
+#[template(path = "admin/partials/blocks_list.html")]
+pub struct BlocksListTemplate {
+    pub blocks: Vec<ContentBlock>,
+    pub move_targets: Vec<Project>,
     pub authenticity_token: String,
 }
 
@@ -56,9 +174,15 @@ pub struct ProjectBlocksTemplate {
 pub struct BlockFormTemplate {
     pub project_id: Uuid,
     pub block_id: Option<Uuid>,
-    pub block_type: String,
-    pub sort_order: i32,
+    pub block_type: BlockKind,
+    /// As typed, so a refused value comes back unchanged.
+    pub sort_order: String,
+    /// The block as the editor script reads and writes it.
     pub content: String,
+    pub errors: FieldErrors,
+    pub embed_providers: Vec<String>,
+    pub media_videos: Vec<MediaItem>,
+    pub flashes: Vec<Flash>,
     pub authenticity_token: String,
 }
 
@@ -66,13 +190,35 @@ pub struct BlockFormTemplate {
 #[template(path = "admin/dashboard.html")]
 pub struct DashboardTemplate {
     pub projects: Vec<Project>,
+    /// What was typed in the search box, and what it found.
+    pub query: String,
+    pub results: Vec<SearchHit>,
+    pub flashes: Vec<Flash>,
+    pub authenticity_token: String,
+}
+
+#[derive(Template)]
+#[template(path = "admin/project_order.html")]
+pub struct ProjectOrderTemplate {
+    pub projects: Vec<Project>,
+    pub index_order: IndexOrder,
+    pub flashes: Vec<Flash>,
     pub authenticity_token: String,
 }
 
+#[derive(Template)]
+#[template(path = "map.html")]
+pub struct MapTemplate {
+    pub markers: Vec<MapMarker>,
+    pub projects: Vec<Project>,
+    pub footer: String,
+}
+
 #[derive(Template)]
 #[template(path = "contact.html")]
 pub struct ContactTemplate {
     pub page: Page,
+    pub json_ld: String,
     pub footer: String,
 }
 
@@ -80,6 +226,18 @@ pub struct ContactTemplate {
 #[template(path = "about.html")]
 pub struct AboutTemplate {
     pub page: Page,
+    pub json_ld: String,
+    pub footer: String,
+}
+
+#[derive(Template)]
+#[template(path = "search.html")]
+pub struct SearchTemplate {
+    pub query: String,
+    pub results: Vec<SearchHit>,
+    /// Set on the static site, which searches this index in the browser
+    /// instead of rendering `results`.
+    pub index_url: Option<String>,
     pub footer: String,
 }
 
@@ -87,6 +245,26 @@ pub struct AboutTemplate {
 #[template(path = "admin/settings.html")]
 pub struct SettingsTemplate {
     pub current_username: String,
+    /// What's wrong with the submitted credentials.
+    pub errors: FieldErrors,
+    pub site: SiteSettings,
+    /// What the artist is called while no name is set.
+    pub site_title: String,
+    pub index_orders: &'static [IndexOrder],
+    pub search_languages: &'static [SearchLanguage],
+    pub flashes: Vec<Flash>,
+    pub authenticity_token: String,
+}
+
+#[derive(Template)]
+#[template(path = "admin/redirects.html")]
+pub struct RedirectsTemplate {
+    pub redirects: Vec<RedirectRule>,
+    /// What was entered in the add form, kept when it's refused.
+    pub from_path: String,
+    pub to: String,
+    pub error: Option<String>,
+    pub flashes: Vec<Flash>,
     pub authenticity_token: String,
 }
 
@@ -94,20 +272,59 @@ pub struct SettingsTemplate {
 #[template(path = "admin/login.html")]
 pub struct LoginTemplate {
     pub error: Option<String>,
+    pub flashes: Vec<Flash>,
     pub authenticity_token: String,
 }
 
 
 // Manual implementation of IntoResponse for templates to avoid askama_axum dependency issues
+
+/// The 500 page for a template that failed to render. Doesn't touch the
+/// database, so it still works when that's what went wrong.
+fn render_failed(err: askama::Error) -> Response {
+    tracing::error!("Failed to render template: {}", err);
+    ServerErrorTemplate { footer: DEFAULT_FOOTER.to_string() }.into_response()
+}
+
+fn error_page(status: StatusCode, page: askama::Result<String>) -> Response {
+    match page {
+        Ok(html) => (status, Html(html)).into_response(),
+        Err(err) => {
+            tracing::error!("Failed to render {} page: {}", status, err);
+            (status, status.canonical_reason().unwrap_or_default()).into_response()
+        }
+    }
+}
+
+impl IntoResponse for NotFoundTemplate {
+    fn into_response(self) -> Response {
+        error_page(StatusCode::NOT_FOUND, self.render())
+    }
+}
+
+impl IntoResponse for GoneTemplate {
+    fn into_response(self) -> Response {
+        error_page(StatusCode::GONE, self.render())
+    }
+}
+
+impl IntoResponse for ErrorTemplate {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        error_page(status, self.render())
+    }
+}
+
+impl IntoResponse for ServerErrorTemplate {
+    fn into_response(self) -> Response {
+        error_page(StatusCode::INTERNAL_SERVER_ERROR, self.render())
+    }
+}
 impl IntoResponse for IndexTemplate {
     fn into_response(self) -> Response {
         match self.render() {
             Ok(html) => Html(html).into_response(),
-            Err(err) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to render template: {}", err),
-            )
-                .into_response(),
+            Err(err) => render_failed(err),
         }
     }
 }
@@ -116,11 +333,7 @@ impl IntoResponse for ProjectTemplate {
     fn into_response(self) -> Response {
         match self.render() {
             Ok(html) => Html(html).into_response(),
-            Err(err) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to render template: {}", err),
-            )
-                .into_response(),
+            Err(err) => render_failed(err),
         }
     }
 }
@@ -129,11 +342,16 @@ impl IntoResponse for DashboardTemplate {
     fn into_response(self) -> Response {
         match self.render() {
             Ok(html) => Html(html).into_response(),
-            Err(err) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to render template: {}", err),
-            )
-                .into_response(),
+            Err(err) => render_failed(err),
+        }
+    }
+}
+
+impl IntoResponse for ProjectOrderTemplate {
+    fn into_response(self) -> Response {
+        match self.render() {
+            Ok(html) => Html(html).into_response(),
+            Err(err) => render_failed(err),
         }
     }
 }
@@ -142,11 +360,7 @@ impl IntoResponse for ProjectFormTemplate {
     fn into_response(self) -> Response {
         match self.render() {
             Ok(html) => Html(html).into_response(),
-            Err(err) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to render template: {}", err),
-            )
-                .into_response(),
+            Err(err) => render_failed(err),
         }
     }
 }
@@ -155,11 +369,16 @@ impl IntoResponse for ProjectBlocksTemplate {
     fn into_response(self) -> Response {
         match self.render() {
             Ok(html) => Html(html).into_response(),
-            Err(err) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to render template: {}", err),
-            )
-                .into_response(),
+            Err(err) => render_failed(err),
+        }
+    }
+}
+
+impl IntoResponse for BlocksListTemplate {
+    fn into_response(self) -> Response {
+        match self.render() {
+            Ok(html) => Html(html).into_response(),
+            Err(err) => render_failed(err),
         }
     }
 }
@@ -168,11 +387,7 @@ impl IntoResponse for BlockFormTemplate {
     fn into_response(self) -> Response {
         match self.render() {
             Ok(html) => Html(html).into_response(),
-            Err(err) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to render template: {}", err),
-            )
-                .into_response(),
+            Err(err) => render_failed(err),
         }
     }
 }
@@ -181,11 +396,16 @@ impl IntoResponse for LoginTemplate {
     fn into_response(self) -> Response {
         match self.render() {
             Ok(html) => Html(html).into_response(),
-            Err(err) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to render template: {}", err),
-            )
-                .into_response(),
+            Err(err) => render_failed(err),
+        }
+    }
+}
+
+impl IntoResponse for MapTemplate {
+    fn into_response(self) -> Response {
+        match self.render() {
+            Ok(html) => Html(html).into_response(),
+            Err(err) => render_failed(err),
         }
     }
 }
@@ -194,11 +414,16 @@ impl IntoResponse for ContactTemplate {
     fn into_response(self) -> Response {
         match self.render() {
             Ok(html) => Html(html).into_response(),
-            Err(err) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to render template: {}", err),
-            )
-                .into_response(),
+            Err(err) => render_failed(err),
+        }
+    }
+}
+
+impl IntoResponse for SearchTemplate {
+    fn into_response(self) -> Response {
+        match self.render() {
+            Ok(html) => Html(html).into_response(),
+            Err(err) => render_failed(err),
         }
     }
 }
@@ -207,11 +432,7 @@ impl IntoResponse for AboutTemplate {
     fn into_response(self) -> Response {
         match self.render() {
             Ok(html) => Html(html).into_response(),
-            Err(err) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to render template: {}", err),
-            )
-                .into_response(),
+            Err(err) => render_failed(err),
         }
     }
 }
@@ -220,11 +441,16 @@ impl IntoResponse for SettingsTemplate {
     fn into_response(self) -> Response {
         match self.render() {
             Ok(html) => Html(html).into_response(),
-            Err(err) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to render template: {}", err),
-            )
-                .into_response(),
+            Err(err) => render_failed(err),
+        }
+    }
+}
+
+impl IntoResponse for RedirectsTemplate {
+    fn into_response(self) -> Response {
+        match self.render() {
+            Ok(html) => Html(html).into_response(),
+            Err(err) => render_failed(err),
         }
     }
 }
@@ -233,11 +459,7 @@ impl IntoResponse for PagesListTemplate {
     fn into_response(self) -> Response {
         match self.render() {
             Ok(html) => Html(html).into_response(),
-            Err(err) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to render template: {}", err),
-            )
-                .into_response(),
+            Err(err) => render_failed(err),
         }
     }
 }
@@ -246,11 +468,7 @@ impl IntoResponse for PageFormTemplate {
     fn into_response(self) -> Response {
         match self.render() {
             Ok(html) => Html(html).into_response(),
-            Err(err) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to render template: {}", err),
-            )
-                .into_response(),
+            Err(err) => render_failed(err),
         }
     }
 }
@@ -0,0 +1,138 @@
+#[cfg(test)]
+mod tests {
+    use include_dir::{include_dir, Dir};
+    use serde_json::{json, Value};
+
+    use crate::block_schema::{decode, upgrade, CURRENT_VERSION};
+    use crate::models::{BlockContent, BlockKind, TextFormat};
+
+    // One directory per schema version (`v1`, `v2`, ...), each holding JSON
+    // exactly as it was stored in `content_blocks.content` at that version.
+    static FIXTURES: Dir = include_dir!("$CARGO_MANIFEST_DIR/fixtures/blocks");
+
+    fn fixture_version(dir: &Dir) -> i32 {
+        let name = dir.path().file_name().unwrap().to_str().unwrap();
+        name.strip_prefix('v')
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_else(|| panic!("Fixture directory {:?} is not named v<N>", name))
+    }
+
+    #[test]
+    fn test_every_historical_fixture_loads() {
+        let mut checked = 0;
+        for dir in FIXTURES.dirs() {
+            let version = fixture_version(dir);
+            for file in dir.files() {
+                let raw: Value = serde_json::from_slice(file.contents())
+                    .unwrap_or_else(|e| panic!("{:?} is not valid JSON: {}", file.path(), e));
+                if let Err(e) = decode(version, raw) {
+                    panic!("{:?} (v{}) no longer loads: {}", file.path(), version, e);
+                }
+                checked += 1;
+            }
+        }
+        assert!(checked > 0, "No block fixtures found");
+    }
+
+    #[test]
+    fn test_current_version_has_fixtures() {
+        assert!(
+            FIXTURES.dirs().any(|d| fixture_version(d) == CURRENT_VERSION),
+            "Add fixtures/blocks/v{} when bumping the block schema",
+            CURRENT_VERSION
+        );
+    }
+
+    #[test]
+    fn test_round_trip_at_current_version() {
+        let block = BlockContent::Gallery(vec!["https://example.com/a.webp".to_string()]);
+        let stored = serde_json::to_value(&block).unwrap();
+        let loaded = decode(CURRENT_VERSION, stored).unwrap();
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&block).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_rejects_unknown_versions() {
+        let content = json!({ "type": "Text", "data": "<p>Hi</p>" });
+        assert!(upgrade(0, content.clone()).is_err());
+        assert!(upgrade(CURRENT_VERSION + 1, content).is_err());
+    }
+
+    #[test]
+    fn test_v1_audio_tuples_become_items() {
+        let v1 = json!({ "type": "Audio", "data": [["https://example.com/a.mp3", "Side A"]] });
+        match decode(1, v1).unwrap() {
+            BlockContent::Audio(items) => {
+                assert_eq!(items.len(), 1);
+                assert_eq!(items[0].url, "https://example.com/a.mp3");
+                assert_eq!(items[0].title, "Side A");
+                assert!(items[0].duration.is_none());
+                assert!(items[0].peaks.is_empty());
+            }
+            other => panic!("Expected Audio, got {:?}", other),
+        }
+        assert!(upgrade(1, json!({ "type": "Audio", "data": [["only-url"]] })).is_err());
+    }
+
+    #[test]
+    fn test_v2_file_tuples_become_items() {
+        let v2 = json!({ "type": "File", "data": [["https://example.com/kit.pdf", "Press kit"]] });
+        match decode(2, v2).unwrap() {
+            BlockContent::File(items) => {
+                assert_eq!(items[0].url, "https://example.com/kit.pdf");
+                assert_eq!(items[0].description, "Press kit");
+                assert!(items[0].byte_size.is_none());
+            }
+            other => panic!("Expected File, got {:?}", other),
+        }
+        // v1 files go through every step.
+        assert!(matches!(decode(1, json!({ "type": "File", "data": [["a", "b"]] })), Ok(BlockContent::File(_))));
+    }
+
+    #[test]
+    fn test_v3_text_becomes_html_source() {
+        let v3 = json!({ "type": "Text", "data": "<p>Hi</p>" });
+        match decode(3, v3).unwrap() {
+            BlockContent::Text(text) => {
+                assert_eq!(text.format, TextFormat::Html);
+                assert_eq!(text.source, "<p>Hi</p>");
+                assert_eq!(text.html, "<p>Hi</p>");
+            }
+            other => panic!("Expected Text, got {:?}", other),
+        }
+
+        let columns = json!({ "type": "Columns", "data": { "columns": [
+            [{ "type": "Text", "data": "<p>Left</p>" }],
+            [{ "type": "Quote", "data": { "text": "Right" } }]
+        ] } });
+        let upgraded = upgrade(3, columns).unwrap();
+        assert_eq!(upgraded["data"]["columns"][0][0]["data"]["source"], "<p>Left</p>");
+        assert_eq!(upgraded["data"]["columns"][1][0]["data"]["text"], "Right");
+        assert!(upgrade(3, json!({ "type": "Text", "data": ["not", "html"] })).is_err());
+    }
+
+    #[test]
+    fn test_kind_matches_json_type_tag() {
+        for &kind in BlockKind::ALL {
+            assert_eq!(BlockKind::parse(kind.name()), Some(kind));
+            assert_eq!(BlockKind::parse(&kind.name().to_lowercase()), Some(kind));
+        }
+        for dir in FIXTURES.dirs().filter(|d| fixture_version(d) == CURRENT_VERSION) {
+            for file in dir.files() {
+                let raw: Value = serde_json::from_slice(file.contents()).unwrap();
+                let tag = raw["type"].as_str().unwrap().to_string();
+                let block = decode(CURRENT_VERSION, raw).unwrap();
+                assert_eq!(BlockKind::parse(&tag), Some(block.kind()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_unknown_kind_is_rejected() {
+        assert_eq!(BlockKind::parse("Carousel"), None);
+        assert_eq!(BlockKind::parse(""), None);
+    }
+}
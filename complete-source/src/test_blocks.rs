@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod tests {
+    use crate::models::{AudioItem, BlockContent, Columns, Credit, FileItem, LinkItem, Quote, TextBlock, TextFormat};
+    use crate::routes::admin::form_items;
+
+    fn block(json: &str) -> BlockContent {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_quote_deserialization() {
+        // Attribution and source are optional.
+        let quote: Quote = serde_json::from_str(r#"{"text": "Loud."}"#).unwrap();
+        assert_eq!(quote.text, "Loud.");
+        assert!(quote.attribution.is_none());
+        assert!(quote.source.is_none());
+
+        let quote = Quote { text: "One\n\n  Two \n\n\n".to_string(), ..Default::default() };
+        assert_eq!(quote.paragraphs(), vec!["One", "Two"]);
+    }
+
+    #[test]
+    fn test_rows_tolerate_missing_fields() {
+        // The row editor sends `{}` for rows that were never filled in.
+        let credits: Vec<Credit> = serde_json::from_str(r#"[{"role": "Light", "name": "A"}, {}]"#).unwrap();
+        assert_eq!(credits.len(), 2);
+        assert_eq!(credits[1].name, "");
+
+        let links: Vec<LinkItem> = serde_json::from_str(r#"[{"url": "https://example.com"}]"#).unwrap();
+        assert_eq!(links[0].label, "");
+        assert!(links[0].description.is_none());
+    }
+
+    #[test]
+    fn test_columns_validation() {
+        let text = || BlockContent::Text(TextBlock::new(TextFormat::Html, "<p>Hi</p>"));
+        assert!(Columns { columns: vec![vec![text()], vec![]] }.validate().is_ok());
+        assert!(Columns { columns: vec![vec![], vec![], vec![text()]] }.validate().is_ok());
+        assert!(Columns { columns: vec![vec![text()]] }.validate().is_err());
+        assert!(Columns { columns: vec![vec![]; 4] }.validate().is_err());
+
+        let nested = BlockContent::Columns(Columns { columns: vec![vec![], vec![]] });
+        assert!(Columns { columns: vec![vec![nested], vec![]] }.validate().is_err());
+    }
+
+    #[test]
+    fn test_previews() {
+        let quote = block(r#"{"type": "Quote", "data": {"text": "Loud.", "attribution": "A. Critic"}}"#);
+        assert_eq!(quote.preview(), "\u{201c}Loud.\u{201d} \u{2014} A. Critic");
+
+        let columns = block(
+            r#"{"type": "Columns", "data": {"columns": [[{"type": "Text", "data": {"source": "x", "html": "x"}}, {"type": "Quote", "data": {"text": "y"}}], []]}}"#,
+        );
+        assert_eq!(columns.preview(), "2 columns: Text, Quote | empty");
+    }
+
+    #[test]
+    fn test_columns_render_their_children() {
+        let columns = block(
+            r#"{"type": "Columns", "data": {"columns": [
+                [{"type": "Text", "data": {"format": "markdown", "source": "Left", "html": "<p>Left</p>"}}],
+                [{"type": "Quote", "data": {"text": "<b>not bold</b>", "attribution": "Me"}}]
+            ]}}"#,
+        );
+        let html = columns.render_html();
+        assert!(html.contains(r#"class="columns columns-2""#));
+        assert!(html.contains(r#"class="column-block block-text""#));
+        assert!(html.contains("<p>Left</p>"));
+        assert!(html.contains(r#"class="column-block block-quote""#));
+        // Only Text blocks hold HTML; everything else is escaped.
+        assert!(html.contains("&lt;b&gt;not bold&lt;/b&gt;"));
+    }
+
+    #[test]
+    fn test_link_list_renders_links() {
+        let links = block(r#"{"type": "LinkList", "data": [{"label": "Review", "url": "https://example.com/r", "description": "Long"}]}"#);
+        let html = links.render_html();
+        assert!(html.contains(r#"<a href="https://example.com/r" target="_blank" rel="noopener">Review</a>"#));
+        assert!(html.contains("Long"));
+    }
+
+    #[test]
+    fn test_unreadable_items_are_refused() {
+        let tracks: Vec<AudioItem> = form_items(r#"[{"url": "/media/a.mp3", "title": "A"}]"#, "tracks").unwrap();
+        assert_eq!(tracks[0].url, "/media/a.mp3");
+        assert!(form_items::<String>("  ", "images").unwrap().is_empty());
+
+        // A broken editor mustn't empty the block.
+        let error = form_items::<AudioItem>(r#"[{"url": "/media/a.mp3""#, "tracks").unwrap_err();
+        assert!(error.starts_with("Couldn't read the tracks: "), "{}", error);
+        assert!(form_items::<String>(r#"{"url": "/media/a.jpg"}"#, "images").is_err());
+        let error = form_items::<FileItem>(r#"[{"description": "Price list"}]"#, "files").unwrap_err();
+        assert!(error.contains("missing field `url`"), "{}", error);
+    }
+}
@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod tests {
+    use askama::Template;
+    use sqlx::types::Json;
+    use uuid::Uuid;
+
+    use crate::models::{BlockContent, BlockKind, ContentBlock, Quote};
+    use crate::templates::BlocksListTemplate;
+    use crate::test_fixtures::project;
+
+    #[test]
+    fn test_copy_slug_finds_a_free_suffix() {
+        let show = project("Gent", "gent-show");
+        assert_eq!(show.copy_slug(&[]), "gent-show-copy");
+
+        let taken = vec!["gent-show-copy".to_string(), "gent-show-copy-2".to_string()];
+        assert_eq!(show.copy_slug(&taken), "gent-show-copy-3");
+        // Copying a copy counts up from the original.
+        assert_eq!(project("Gent", "gent-show-copy-2").copy_slug(&taken), "gent-show-copy-3");
+        assert_eq!(project("Gent", "gent-show-copy").copy_slug(&taken), "gent-show-copy-3");
+        // Only a numbered suffix is stripped.
+        assert_eq!(project("Copy", "copy-copyright").copy_slug(&[]), "copy-copyright-copy");
+        assert_eq!(project("X", "x-copy-two").copy_slug(&[]), "x-copy-two-copy");
+    }
+
+    #[test]
+    fn test_blocks_list_offers_other_projects() {
+        let block = || ContentBlock {
+            id: Uuid::nil(),
+            project_id: Uuid::new_v4(),
+            block_type: BlockKind::Quote,
+            content: Json(BlockContent::Quote(Quote { text: "<b>Hi</b>".to_string(), attribution: None, source: None })),
+            sort_order: 0,
+        };
+        let target = project("Fish & <Chips>", "other");
+        let target_id = target.id;
+        let html = BlocksListTemplate {
+            blocks: vec![block()],
+            move_targets: vec![target],
+            authenticity_token: "token".to_string(),
+        }
+        .render()
+        .unwrap();
+        assert!(html.contains(&format!(r#"action="/admin/blocks/duplicate/{}""#, Uuid::nil())));
+        assert!(html.contains(&format!(r#"<option value="{}">Fish &amp; &lt;Chips&gt;</option>"#, target_id)));
+        assert!(!html.contains("<b>Hi</b>"), "previews are escaped");
+
+        // Nowhere to move to, so no move form.
+        let html = BlocksListTemplate { blocks: vec![block()], move_targets: vec![], authenticity_token: "token".to_string() }
+            .render()
+            .unwrap();
+        assert!(!html.contains("/admin/blocks/move/"));
+    }
+}
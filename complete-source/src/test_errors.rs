@@ -0,0 +1,109 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+    use askama::Template;
+
+    use crate::error::{ApiError, AppError};
+    use crate::templates::{GoneTemplate, NotFoundTemplate, ServerErrorTemplate, DEFAULT_FOOTER};
+
+    async fn body(response: axum::response::Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_error_pages_have_their_status() {
+        let footer = || DEFAULT_FOOTER.to_string();
+        assert_eq!(NotFoundTemplate { footer: footer() }.into_response().status(), StatusCode::NOT_FOUND);
+        assert_eq!(GoneTemplate { footer: footer() }.into_response().status(), StatusCode::GONE);
+        assert_eq!(ServerErrorTemplate { footer: footer() }.into_response().status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn test_error_pages_use_the_site_layout() {
+        let pages = [
+            NotFoundTemplate { footer: "<p>Studio footer</p>".to_string() }.render().unwrap(),
+            GoneTemplate { footer: "<p>Studio footer</p>".to_string() }.render().unwrap(),
+            ServerErrorTemplate { footer: "<p>Studio footer</p>".to_string() }.render().unwrap(),
+        ];
+        for (html, code) in pages.iter().zip(["404", "410", "500"]) {
+            assert!(html.contains(&format!(r#"<p class="error-code">{}</p>"#, code)));
+            assert!(html.contains(r#"class="menu menu--circle""#), "navigation on the {} page", code);
+            assert!(html.contains("<p>Studio footer</p>"), "footer on the {} page", code);
+        }
+    }
+
+    #[test]
+    fn test_error_page_footer_is_sanitized() {
+        let html = NotFoundTemplate { footer: "<p>Hi</p><script>alert(1)</script>".to_string() }.render().unwrap();
+        assert!(html.contains("<p>Hi</p>"));
+        assert!(!html.contains("alert(1)"));
+    }
+
+    #[test]
+    fn test_app_error_status_codes() {
+        assert_eq!(AppError::NotFound("Block").status(), StatusCode::NOT_FOUND);
+        assert_eq!(AppError::Validation("Title is required".into()).status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(AppError::Conflict("Slug is taken".into()).status(), StatusCode::CONFLICT);
+        assert_eq!(AppError::Forbidden.status(), StatusCode::FORBIDDEN);
+        assert_eq!(AppError::Storage("disk full".into()).status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(AppError::Internal("boom".into()).status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(AppError::from(sqlx::Error::PoolTimedOut).status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn test_missing_row_is_not_found() {
+        let err = AppError::from(sqlx::Error::RowNotFound);
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+        assert_eq!(err.message(), "Record not found");
+    }
+
+    #[test]
+    fn test_server_error_messages_hide_the_cause() {
+        for err in [
+            AppError::Storage("/var/media/x.jpg: permission denied".into()),
+            AppError::Internal("GitHub answered 401".into()),
+            AppError::Database(sqlx::Error::Protocol("relation \"projects\" does not exist".into())),
+        ] {
+            let message = err.message();
+            assert!(!message.contains("permission") && !message.contains("401") && !message.contains("relation"), "{}", message);
+            // The cause still reaches the log.
+            assert_ne!(err.to_string(), message);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_app_error_renders_an_error_page() {
+        let response = AppError::NotFound("Project").into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let html = body(response).await;
+        assert!(html.contains(r#"<p class="error-code">404</p>"#));
+        assert!(html.contains("Project not found"));
+        assert!(html.contains(r#"href="/admin/dashboard""#));
+    }
+
+    #[tokio::test]
+    async fn test_app_error_page_escapes_the_message() {
+        let html = body(AppError::Validation("<b>Bad</b> input".into()).into_response()).await;
+        assert!(!html.contains("<b>Bad</b>"));
+        assert!(html.contains("&lt;b&gt;Bad&lt;/b&gt; input"));
+    }
+
+    #[tokio::test]
+    async fn test_api_error_is_json() {
+        let response = ApiError::from(AppError::Forbidden).into_response();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(response.headers()["content-type"], "application/json");
+        let json: serde_json::Value = serde_json::from_str(&body(response).await).unwrap();
+        assert_eq!(json["error"], "Invalid CSRF Token. Reload the page and try again");
+    }
+
+    #[tokio::test]
+    async fn test_api_error_from_database_error() {
+        let response = ApiError::from(sqlx::Error::RowNotFound).into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let json: serde_json::Value = serde_json::from_str(&body(response).await).unwrap();
+        assert_eq!(json["error"], "Record not found");
+    }
+}
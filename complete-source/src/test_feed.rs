@@ -0,0 +1,172 @@
+#[cfg(test)]
+mod tests {
+    use time::{Date, Month};
+
+    use crate::feed::{self, Feed, FeedItem};
+    use crate::models::{BlockContent, ContentBlock, Project, TextBlock, TextFormat};
+    use crate::site::Site;
+    use crate::test_fixtures::{self, block};
+
+    const ATOM: &str = "http://www.w3.org/2005/Atom";
+
+    fn site() -> Site {
+        Site::new("https://example.org/", "Stef Meul")
+    }
+
+    fn project(title: &str, slug: &str, day: u8) -> Project {
+        Project {
+            description: Some("Paintings & drawings".to_string()),
+            start_date: Date::from_calendar_date(2024, Month::March, day).unwrap(),
+            thumbnail_url: Some("/media/abc/t.png".to_string()),
+            ..test_fixtures::project(title, slug)
+        }
+    }
+
+    fn text_block(html: &str) -> ContentBlock {
+        block(BlockContent::Text(TextBlock::new(TextFormat::Html, html)))
+    }
+
+    fn feed() -> Feed {
+        let site = site();
+        let gent = project("Gent <Show>", "gent-show", 20);
+        let older = Project {
+            description: None,
+            thumbnail_url: None,
+            ..project("Older", "older", 1)
+        };
+        let mut first = FeedItem::new(&site, &gent, &[text_block("<p>First &amp; <b>best</b></p>"), text_block("<p>Second</p>")]);
+        // As `feed::load` finds it in the media library.
+        first.image.as_mut().unwrap().length = Some(48_213);
+        Feed::new(&site, vec![first, FeedItem::new(&site, &older, &[])])
+    }
+
+    #[test]
+    fn test_site_urls_are_absolute() {
+        let site = site();
+        assert_eq!(site.url, "https://example.org");
+        assert_eq!(site.absolute("/project/gent"), "https://example.org/project/gent");
+        assert_eq!(site.absolute("media/a.png"), "https://example.org/media/a.png");
+        assert_eq!(site.absolute("https://res.cloudinary.com/a.jpg"), "https://res.cloudinary.com/a.jpg");
+        assert_eq!(site.absolute("//cdn.example.com/a.jpg"), "https://cdn.example.com/a.jpg");
+    }
+
+    #[test]
+    fn test_item_from_project() {
+        let item = &feed().items[0];
+        assert_eq!(item.url, "https://example.org/project/gent-show");
+        assert_eq!(item.content_html.as_deref(), Some("<p>First &amp; <b>best</b></p>"));
+        let image = item.image.as_ref().unwrap();
+        assert_eq!(image.url, "https://example.org/media/abc/t.png");
+        assert_eq!(image.mime_type, "image/png");
+        assert_eq!(item.rfc3339(), "2024-03-20T00:00:00Z");
+        assert_eq!(item.rfc2822(), "Wed, 20 Mar 2024 00:00:00 +0000");
+    }
+
+    #[test]
+    fn test_social_card_is_preferred() {
+        let site = site();
+        let project = Project {
+            social_image_url: Some("https://res.cloudinary.com/demo/card.jpg?v=2".to_string()),
+            ..project("Gent", "gent", 1)
+        };
+        let image = FeedItem::new(&site, &project, &[]).image.unwrap();
+        assert_eq!(image.url, "https://res.cloudinary.com/demo/card.jpg?v=2");
+        assert_eq!(image.mime_type, "image/jpeg");
+        assert_eq!(image.length, None);
+    }
+
+    #[test]
+    fn test_atom_is_valid() {
+        let xml = feed::atom(&feed()).unwrap();
+        let doc = roxmltree::Document::parse(&xml).unwrap();
+        let root = doc.root_element();
+        assert!(root.has_tag_name((ATOM, "feed")));
+
+        let child = |node: roxmltree::Node<'_, '_>, name: &str| {
+            node.children().find(|n| n.has_tag_name((ATOM, name))).map(|n| n.text().unwrap_or_default().to_string())
+        };
+        assert_eq!(child(root, "id").as_deref(), Some("https://example.org/"));
+        assert_eq!(child(root, "updated").as_deref(), Some("2024-03-20T00:00:00Z"));
+        let self_link = root.children().find(|n| n.attribute("rel") == Some("self")).unwrap();
+        assert_eq!(self_link.attribute("href"), Some("https://example.org/feed.xml"));
+
+        let entries: Vec<_> = root.children().filter(|n| n.has_tag_name((ATOM, "entry"))).collect();
+        assert_eq!(entries.len(), 2);
+        let first = entries[0];
+        assert_eq!(child(first, "title").as_deref(), Some("Gent <Show>"));
+        assert_eq!(child(first, "summary").as_deref(), Some("Paintings & drawings"));
+        // The HTML arrives intact once the XML is read.
+        assert_eq!(child(first, "content").as_deref(), Some("<p>First &amp; <b>best</b></p>"));
+        let enclosure = first.children().find(|n| n.attribute("rel") == Some("enclosure")).unwrap();
+        assert_eq!(enclosure.attribute("type"), Some("image/png"));
+        assert_eq!(enclosure.attribute("length"), Some("48213"));
+
+        // Every entry has what Atom requires.
+        for entry in &entries {
+            for required in ["id", "title", "updated"] {
+                assert!(child(*entry, required).is_some(), "entry without {}", required);
+            }
+        }
+        assert!(child(entries[1], "content").is_none());
+        assert!(child(entries[1], "summary").is_none());
+    }
+
+    #[test]
+    fn test_rss_is_valid() {
+        let xml = feed::rss(&feed()).unwrap();
+        let doc = roxmltree::Document::parse(&xml).unwrap();
+        let root = doc.root_element();
+        assert!(root.has_tag_name("rss"));
+        assert_eq!(root.attribute("version"), Some("2.0"));
+
+        let channel = root.children().find(|n| n.has_tag_name("channel")).unwrap();
+        let items: Vec<_> = channel.children().filter(|n| n.has_tag_name("item")).collect();
+        assert_eq!(items.len(), 2);
+
+        let text = |node: roxmltree::Node<'_, '_>, name: &str| {
+            node.children().find(|n| n.has_tag_name(name)).and_then(|n| n.text()).map(str::to_string)
+        };
+        let first = items[0];
+        assert_eq!(text(first, "link").as_deref(), Some("https://example.org/project/gent-show"));
+        assert_eq!(text(first, "pubDate").as_deref(), Some("Wed, 20 Mar 2024 00:00:00 +0000"));
+        let encoded = first.children().find(|n| n.has_tag_name(("http://purl.org/rss/1.0/modules/content/", "encoded"))).unwrap();
+        assert_eq!(encoded.text(), Some("<p>First &amp; <b>best</b></p>"));
+        let enclosure = first.children().find(|n| n.has_tag_name("enclosure")).unwrap();
+        assert_eq!(enclosure.attribute("url"), Some("https://example.org/media/abc/t.png"));
+        assert_eq!(enclosure.attribute("length"), Some("48213"));
+        assert!(items[1].children().all(|n| !n.has_tag_name("enclosure")));
+    }
+
+    #[test]
+    fn test_rss_leaves_out_images_of_unknown_size() {
+        let mut feed = feed();
+        feed.items[0].image.as_mut().unwrap().length = None;
+        assert!(!feed::rss(&feed).unwrap().contains("<enclosure"));
+        // Atom doesn't need the size.
+        let atom = feed::atom(&feed).unwrap();
+        assert!(atom.contains(r#"<link rel="enclosure" type="image/png" href="https://example.org/media/abc/t.png"/>"#));
+    }
+
+    #[test]
+    fn test_json_feed() {
+        let json: serde_json::Value = serde_json::from_str(&feed::json_feed(&feed())).unwrap();
+        assert_eq!(json["version"], "https://jsonfeed.org/version/1.1");
+        assert_eq!(json["feed_url"], "https://example.org/feed.json");
+        let items = json["items"].as_array().unwrap();
+        assert_eq!(items[0]["id"], "https://example.org/project/gent-show");
+        assert_eq!(items[0]["content_html"], "<p>First &amp; <b>best</b></p>");
+        assert_eq!(items[0]["image"], "https://example.org/media/abc/t.png");
+        assert_eq!(items[0]["date_published"], "2024-03-20T00:00:00Z");
+        // An item needs some content; without text or a description it's the title.
+        assert_eq!(items[1]["content_text"], "Older");
+        assert!(items[1].get("image").is_none());
+    }
+
+    #[test]
+    fn test_empty_feed_is_still_valid() {
+        let empty = Feed::new(&site(), vec![]);
+        assert!(roxmltree::Document::parse(&feed::atom(&empty).unwrap()).is_ok());
+        assert!(roxmltree::Document::parse(&feed::rss(&empty).unwrap()).is_ok());
+        assert_eq!(empty.updated_rfc3339(), "1970-01-01T00:00:00Z");
+    }
+}
@@ -0,0 +1,38 @@
+//! Values the tests build on. Change what a test is about with struct
+//! update syntax, e.g. `Project { featured: true, ..project("Gent", "gent") }`.
+
+use time::{Date, Month};
+use uuid::Uuid;
+
+use crate::models::{BlockContent, ContentBlock, Project};
+
+/// A project started on 1 March 2024, with no description, images or place.
+pub(crate) fn project(title: &str, slug: &str) -> Project {
+    Project {
+        id: Uuid::new_v4(),
+        title: title.to_string(),
+        slug: slug.to_string(),
+        description: None,
+        start_date: Date::from_calendar_date(2024, Month::March, 1).unwrap(),
+        end_date: None,
+        thumbnail_url: None,
+        latitude: None,
+        longitude: None,
+        location_name: None,
+        featured: false,
+        position: None,
+        thumbnail_focus_x: 0.5,
+        thumbnail_focus_y: 0.5,
+        social_image_url: None,
+    }
+}
+
+pub(crate) fn block(content: BlockContent) -> ContentBlock {
+    ContentBlock {
+        id: Uuid::new_v4(),
+        project_id: Uuid::nil(),
+        block_type: content.kind(),
+        content: sqlx::types::Json(content),
+        sort_order: 0,
+    }
+}
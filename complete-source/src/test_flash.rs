@@ -0,0 +1,119 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use askama::Template;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use axum::middleware;
+    use axum::response::Redirect;
+    use axum::routing::post;
+    use axum::Router;
+    use tower::ServiceExt;
+    use tower_sessions::{MemoryStore, Session, SessionManagerLayer};
+
+    use crate::flash::{self, Flash, Level};
+    use crate::templates::DashboardTemplate;
+
+    fn session() -> Session {
+        Session::new(None, Arc::new(MemoryStore::default()), None)
+    }
+
+    fn message(level: Level, text: &str) -> Flash {
+        Flash { level, text: text.to_string() }
+    }
+
+    #[tokio::test]
+    async fn test_messages_are_taken_once_in_order() {
+        let session = session();
+        flash::success(&session, "Project saved").await;
+        flash::warning(&session, "Gent wasn't found on the map").await;
+        assert_eq!(
+            flash::take(&session).await,
+            vec![message(Level::Success, "Project saved"), message(Level::Warning, "Gent wasn't found on the map")]
+        );
+        assert!(flash::take(&session).await.is_empty());
+    }
+
+    #[test]
+    fn test_hx_trigger_raises_the_flash_event() {
+        let trigger = flash::hx_trigger(&[message(Level::Error, "GitHub refused the deployment")]).unwrap();
+        let json: serde_json::Value = serde_json::from_str(trigger.to_str().unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "flash": { "messages": [{ "level": "error", "text": "GitHub refused the deployment" }] } })
+        );
+    }
+
+    #[test]
+    fn test_hx_trigger_keeps_non_ascii_text() {
+        let trigger = flash::hx_trigger(&[message(Level::Success, "“Gent” saved 🎉")]).unwrap();
+        assert!(trigger.as_bytes().is_ascii());
+        let json: serde_json::Value = serde_json::from_slice(trigger.as_bytes()).unwrap();
+        assert_eq!(json["flash"]["messages"][0]["text"], "“Gent” saved 🎉");
+    }
+
+    #[test]
+    fn test_admin_pages_render_messages() {
+        let html = DashboardTemplate {
+            projects: vec![],
+            query: String::new(),
+            results: vec![],
+            flashes: vec![message(Level::Success, "Project deleted"), message(Level::Info, "<b>Hi</b>")],
+            authenticity_token: "token".to_string(),
+        }
+        .render()
+        .unwrap();
+        assert!(html.contains(r#"<p class="flash flash-success">"#));
+        assert!(html.contains("Project deleted."));
+        assert!(html.contains("&lt;b&gt;Hi&lt;/b&gt;"));
+
+        let html = DashboardTemplate {
+            projects: vec![],
+            query: String::new(),
+            results: vec![],
+            flashes: vec![],
+            authenticity_token: "token".to_string(),
+        }
+        .render()
+        .unwrap();
+        assert!(!html.contains("flash-messages"));
+    }
+
+    fn app() -> Router {
+        Router::new()
+            .route("/partial", post(|session: Session| async move {
+                flash::success(&session, "Block saved").await;
+                "<li>Block</li>"
+            }))
+            .route("/redirect", post(|session: Session| async move {
+                flash::success(&session, "Block saved").await;
+                Redirect::to("/admin/dashboard")
+            }))
+            .route_layer(middleware::from_fn(crate::flash_middleware))
+            .layer(SessionManagerLayer::new(MemoryStore::default()))
+    }
+
+    fn request(path: &str, htmx: bool) -> Request<Body> {
+        let request = Request::post(path);
+        let request = if htmx { request.header("HX-Request", "true") } else { request };
+        request.body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_htmx_partials_carry_their_messages() {
+        let response = app().oneshot(request("/partial", true)).await.unwrap();
+        let trigger: serde_json::Value = serde_json::from_slice(response.headers()["HX-Trigger"].as_bytes()).unwrap();
+        assert_eq!(trigger["flash"]["messages"][0]["text"], "Block saved");
+    }
+
+    #[tokio::test]
+    async fn test_messages_wait_for_the_next_page() {
+        let response = app().oneshot(request("/partial", false)).await.unwrap();
+        assert!(!response.headers().contains_key("HX-Trigger"));
+
+        let response = app().oneshot(request("/redirect", true)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert!(!response.headers().contains_key("HX-Trigger"));
+    }
+}
@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod tests {
+    use axum::{extract::Query, response::IntoResponse, routing::get, Json, Router};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    use crate::geocode::{Geocoder, Nominatim};
+    use crate::models::{BlockContent, GeoPoint, MapMarker};
+
+    const GENT: GeoPoint = GeoPoint { lat: 51.0543, lng: 3.7174 };
+
+    // Stands in for Nominatim's /search endpoint.
+    async fn mock_search(Query(params): Query<HashMap<String, String>>) -> impl IntoResponse {
+        if params.get("format").map(String::as_str) != Some("jsonv2") {
+            return axum::http::StatusCode::BAD_REQUEST.into_response();
+        }
+        match params.get("q").map(String::as_str) {
+            Some("Vooruit, Gent") => Json(json!([{ "lat": "51.0478", "lon": "3.7276", "display_name": "Vooruit" }])).into_response(),
+            Some("Nowhere") => Json(json!([])).into_response(),
+            Some("Broken") => Json(json!([{ "lat": "north", "lon": "3.7" }])).into_response(),
+            _ => axum::http::StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        }
+    }
+
+    async fn nominatim_with_mock() -> Geocoder {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, Router::new().route("/search", get(mock_search)))
+                .await
+                .unwrap();
+        });
+        Geocoder::Nominatim(Nominatim::new(&format!("http://{}/", addr)))
+    }
+
+    #[tokio::test]
+    async fn test_stub_ignores_case_and_whitespace() {
+        let geocoder = Geocoder::stub(&[("Gent", GENT)]);
+        assert!(geocoder.is_enabled());
+        assert_eq!(geocoder.geocode("  gENT ").await.unwrap(), Some(GENT));
+        assert_eq!(geocoder.geocode("Antwerpen").await.unwrap(), None);
+        assert_eq!(geocoder.geocode("   ").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_finds_nothing() {
+        let geocoder = Geocoder::Disabled;
+        assert!(!geocoder.is_enabled());
+        assert_eq!(geocoder.geocode("Gent").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_nominatim_parses_first_result() {
+        let geocoder = nominatim_with_mock().await;
+        let point = geocoder.geocode("Vooruit, Gent").await.unwrap().unwrap();
+        assert_eq!(point, GeoPoint { lat: 51.0478, lng: 3.7276 });
+        assert_eq!(geocoder.geocode("Nowhere").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_nominatim_errors() {
+        let geocoder = nominatim_with_mock().await;
+        assert!(geocoder.geocode("Broken").await.unwrap_err().contains("bad coordinates"));
+        assert!(geocoder.geocode("Anything else").await.unwrap_err().contains("500"));
+    }
+
+    #[test]
+    fn test_point_validation() {
+        assert!(GENT.validate().is_ok());
+        assert!(GeoPoint { lat: -90.0, lng: 180.0 }.validate().is_ok());
+        assert!(GeoPoint { lat: 91.0, lng: 0.0 }.validate().is_err());
+        assert!(GeoPoint { lat: 0.0, lng: -180.5 }.validate().is_err());
+        assert!(GeoPoint { lat: f64::NAN, lng: 0.0 }.validate().is_err());
+    }
+
+    #[test]
+    fn test_map_block_renders_markers_and_fallback() {
+        let block = BlockContent::Map(vec![MapMarker {
+            lat: GENT.lat,
+            lng: GENT.lng,
+            label: "Tom & Jerry".to_string(),
+            url: Some("/project/gent".to_string()),
+        }]);
+        let html = block.render_html();
+        assert!(html.contains(r#"class="slippy-map""#));
+        assert!(html.contains("data-markers=\"[{&quot;lat&quot;:51.0543"));
+        assert!(html.contains("data-tile-url="));
+        assert!(html.contains(r#"<a href="/project/gent">Tom &amp; Jerry</a>"#));
+        assert!(html.contains("51.0543, 3.7174"));
+        assert_eq!(block.preview(), "Map: Tom & Jerry");
+    }
+}
@@ -1,7 +1,6 @@
 #[cfg(test)]
 mod tests {
     use serde::{Deserialize, Serialize};
-    use serde_json::json;
 
     #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
     #[serde(tag = "type", content = "data")]
@@ -0,0 +1,116 @@
+#[cfg(test)]
+mod tests {
+    use askama::Template;
+    use time::{Date, Month, OffsetDateTime};
+
+    use crate::json_ld::{self, Artist};
+    use crate::models::{BlockContent, Columns, Page, Project, TextFormat};
+    use crate::settings::SiteSettings;
+    use crate::site::Site;
+    use crate::templates::AboutTemplate;
+    use crate::test_fixtures::{self, block};
+
+    fn site() -> Site {
+        Site::new("https://example.org", "Stef Meul")
+    }
+
+    fn artist() -> Artist {
+        let settings = SiteSettings {
+            artist_links: "https://www.instagram.com/stefmeul\n\nhttps://vimeo.com/stefmeul".to_string(),
+            ..SiteSettings::default()
+        };
+        Artist::new(&site(), &settings)
+    }
+
+    fn date(day: u8) -> Date {
+        Date::from_calendar_date(2024, Month::March, day).unwrap()
+    }
+
+    fn show() -> Project {
+        Project {
+            description: Some("Large paintings in oil.".to_string()),
+            start_date: date(1),
+            end_date: Some(date(31)),
+            thumbnail_url: Some("/media/abc/thumb.jpg".to_string()),
+            latitude: Some(51.0543),
+            longitude: Some(3.7174),
+            location_name: Some("S.M.A.K., Gent".to_string()),
+            ..test_fixtures::project("Gent Show", "gent-show")
+        }
+    }
+
+    fn about() -> Page {
+        Page {
+            slug: "about".to_string(),
+            title: "About".to_string(),
+            content: "<p>Born in Gent.</p>".to_string(),
+            updated_at: OffsetDateTime::UNIX_EPOCH,
+            format: TextFormat::Html,
+            source: "<p>Born in Gent.</p>".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_exhibition_page() {
+        let blocks = [
+            block(BlockContent::Gallery(vec!["/media/abc/thumb.jpg".to_string(), "/media/abc/room.jpg".to_string()])),
+            block(BlockContent::Columns(Columns {
+                columns: vec![vec![BlockContent::Gallery(vec!["https://res.cloudinary.com/demo/detail.jpg".to_string()])]],
+            })),
+        ];
+        insta::assert_json_snapshot!(json_ld::project_page(&site(), &artist(), &show(), &blocks));
+    }
+
+    #[test]
+    fn test_work_without_images_or_place() {
+        let project = Project {
+            description: None,
+            end_date: None,
+            thumbnail_url: None,
+            latitude: None,
+            longitude: None,
+            location_name: None,
+            ..show()
+        };
+        let blocks = [block(BlockContent::Video("https://vimeo.com/1".to_string()))];
+        insta::assert_json_snapshot!(json_ld::project_page(&site(), &artist(), &project, &blocks));
+    }
+
+    #[test]
+    fn test_about_page() {
+        insta::assert_json_snapshot!(json_ld::page(&site(), &artist(), &about()));
+    }
+
+    #[test]
+    fn test_artist_is_named_after_the_site_by_default() {
+        let artist = Artist::new(&site(), &SiteSettings::default());
+        assert_eq!(artist, Artist { name: "Stef Meul".to_string(), links: vec![] });
+        let person = json_ld::person(&site(), &artist);
+        assert_eq!(person["@id"], "https://example.org/#artist");
+        assert!(person.get("sameAs").is_none());
+
+        let named = SiteSettings { artist_name: " Stefanie Meul ".to_string(), ..SiteSettings::default() };
+        assert_eq!(Artist::new(&site(), &named).name, "Stefanie Meul");
+    }
+
+    #[test]
+    fn test_script_cannot_close_its_element() {
+        let project = Project { title: "</script><b>Gent & co</b>".to_string(), ..show() };
+        let data = json_ld::project_page(&site(), &artist(), &project, &[]);
+        let script = json_ld::script(&data);
+        assert!(!script.contains('<') && !script.contains('>') && !script.contains('&'));
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&script).unwrap(), data);
+    }
+
+    #[test]
+    fn test_page_embeds_the_script() {
+        let html = AboutTemplate {
+            page: about(),
+            json_ld: json_ld::script(&json_ld::page(&site(), &artist(), &about())),
+            footer: String::new(),
+        }
+        .render()
+        .unwrap();
+        assert!(html.contains(r#"<script type="application/ld+json">{"@context":"https://schema.org","#));
+    }
+}
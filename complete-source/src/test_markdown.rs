@@ -0,0 +1,73 @@
+#[cfg(test)]
+mod tests {
+    use crate::markdown::{to_html, to_plain_text};
+    use crate::models::{BlockContent, TextBlock, TextFormat};
+
+    #[test]
+    fn test_commonmark_and_tables() {
+        let html = to_html("# Title\n\nSome *emphasis* and ~~old~~ text.\n\n| Year | Venue |\n|:-----|:-----:|\n| 2024 | Kunsthal |\n");
+        assert!(html.contains("<h1>Title</h1>"));
+        assert!(html.contains("<em>emphasis</em>"));
+        assert!(html.contains("<del>old</del>"));
+        assert!(html.contains(r#"<th style="text-align: center">Venue</th>"#));
+        assert!(html.contains(r#"<td style="text-align: center">Kunsthal</td>"#));
+    }
+
+    #[test]
+    fn test_footnotes_are_numbered_with_fixed_ids() {
+        let html = to_html("First.[^note] Second.[^other] Again.[^note]\n\n[^other]: Other.\n[^note]: Note.\n");
+        assert!(html.contains(r##"<a href="#fn-1" id="fnref-1">1</a>"##));
+        assert!(html.contains(r##"<a href="#fn-2" id="fnref-2">2</a>"##));
+        assert!(html.contains(r#"<div class="footnote" id="fn-2">"#));
+        assert!(html.contains(r#"<div class="footnote" id="fn-1">"#));
+        assert!(!html.contains(r#"id="note""#), "author labels don't become ids");
+        // Later references to the same note link back without repeating the id.
+        assert_eq!(html.matches(r#"id="fnref-1""#).count(), 1);
+        assert!(html.contains(r##"Again.<sup class="footnote-ref"><a href="#fn-1">1</a></sup>"##));
+    }
+
+    #[test]
+    fn test_markdown_text_is_sanitised() {
+        let text = TextBlock::new(
+            TextFormat::Markdown,
+            "Hi <img src=x onerror=alert(1)> [link](javascript:alert(1)).[^1]\n\n<script>alert(2)</script>\n\n[^1]: Note.",
+        );
+        // The source is kept as written; only the HTML is cleaned.
+        assert!(text.source.contains("<script>"));
+        assert!(!text.html.contains("script"));
+        assert!(!text.html.contains("onerror"));
+        assert!(!text.html.contains("javascript"));
+        assert!(text.html.contains(r##"<a href="#fn-1" id="fnref-1" rel="noopener noreferrer">1</a>"##));
+        assert!(text.html.contains(r#"<div class="footnote" id="fn-1">"#));
+    }
+
+    #[test]
+    fn test_html_text_source_is_cleaned() {
+        let text = TextBlock::new(TextFormat::Html, r#"<p onclick="x()">Hi</p>"#);
+        assert_eq!(text.source, "<p>Hi</p>");
+        assert_eq!(text.html, text.source);
+    }
+
+    #[test]
+    fn test_plain_text_from_either_format() {
+        assert_eq!(to_plain_text("# Title\n\nSome **bold** `code`\nand a [link](https://example.com)."), "Title Some bold code and a link.");
+
+        let html = TextBlock::new(TextFormat::Html, "<h2>Title</h2><p>Fish &amp; chips&nbsp;&#8212; <em>now</em></p>");
+        assert_eq!(html.plain_text(), "Title Fish & chips \u{2014} now");
+
+        let long = BlockContent::Text(TextBlock::new(TextFormat::Markdown, &"word ".repeat(20)));
+        assert_eq!(long.preview(), format!("{}\u{2026}", "word ".repeat(10).trim_end()));
+        let short = BlockContent::Text(TextBlock::new(TextFormat::Markdown, "Just *this*."));
+        assert_eq!(short.preview(), "Just this.");
+    }
+
+    #[test]
+    fn test_format_names() {
+        for &format in TextFormat::ALL {
+            assert_eq!(TextFormat::parse(format.name()), Some(format));
+        }
+        assert_eq!(TextFormat::parse(" Markdown "), Some(TextFormat::Markdown));
+        assert_eq!(TextFormat::parse("rtf"), None);
+        assert_eq!(serde_json::to_value(TextFormat::Markdown).unwrap(), "markdown");
+    }
+}
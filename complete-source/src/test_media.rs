@@ -0,0 +1,213 @@
+#[cfg(test)]
+mod tests {
+    use lopdf::{dictionary, Document, Object};
+    use uuid::Uuid;
+
+    use askama::Template;
+    use std::path::Path;
+
+    use crate::media::{
+        cloudinary_pdf_thumbnail_url, cloudinary_poster_url, content_disposition, parse_probe, pdf_page_count,
+        with_renditions, Probe,
+    };
+    use crate::models::{format_bytes, BlockContent, CaptionTrack, Columns, FileItem, MediaItem, MediaMetadata};
+    use crate::routes::admin::{block_to_form_content, hosted_video_block, HostedVideoForm};
+    use crate::templates::BlockTemplate;
+    use crate::upload::{safe_filename, LocalStorage};
+
+    fn pdf_with_pages(count: usize) -> Vec<u8> {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let kids: Vec<Object> = (0..count)
+            .map(|_| doc.add_object(dictionary! { "Type" => "Page", "Parent" => pages_id }).into())
+            .collect();
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => kids, "Count" => count as i64 }),
+        );
+        let catalog = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog);
+
+        let mut bytes = Vec::new();
+        doc.save_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_pdf_page_count() {
+        assert_eq!(pdf_page_count(&pdf_with_pages(3)), Ok(3));
+        assert!(pdf_page_count(b"%PDF-1.4 truncated").is_err());
+    }
+
+    #[test]
+    fn test_cloudinary_pdf_thumbnail_url() {
+        assert_eq!(
+            cloudinary_pdf_thumbnail_url("https://res.cloudinary.com/demo/image/upload/v17/kit.pdf").as_deref(),
+            Some("https://res.cloudinary.com/demo/image/upload/pg_1,w_400,c_limit/v17/kit.jpg")
+        );
+        // Raw uploads can't be transformed.
+        assert_eq!(cloudinary_pdf_thumbnail_url("https://res.cloudinary.com/demo/raw/upload/v17/kit.pdf"), None);
+        assert_eq!(cloudinary_pdf_thumbnail_url("https://res.cloudinary.com/demo/image/upload/v17/a.png"), None);
+    }
+
+    #[test]
+    fn test_content_disposition_keeps_original_name() {
+        assert_eq!(
+            content_disposition("Press Kit.pdf"),
+            "attachment; filename=\"Press Kit.pdf\"; filename*=UTF-8''Press%20Kit.pdf"
+        );
+        assert_eq!(
+            content_disposition("Café \"final\".pdf"),
+            "attachment; filename=\"Caf_ _final_.pdf\"; filename*=UTF-8''Caf%C3%A9%20%22final%22.pdf"
+        );
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(42_153_984), "40.2 MB");
+        assert_eq!(format_bytes(300 * 1024 * 1024), "300 MB");
+    }
+
+    #[test]
+    fn test_file_item_labels_and_links() {
+        let id = Uuid::new_v4();
+        let local = FileItem {
+            url: format!("/media/{}/kit.pdf", id),
+            media_id: Some(id),
+            filename: Some("Press Kit.pdf".to_string()),
+            mime_type: Some("application/pdf".to_string()),
+            page_count: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(local.download_url(), format!("/download/{}", id));
+        assert_eq!(local.type_label().as_deref(), Some("PDF"));
+        assert_eq!(local.pages_label().as_deref(), Some("1 page"));
+        assert_eq!(local.icon(), "picture_as_pdf");
+
+        let remote = FileItem { url: "https://example.com/files/stems.zip?dl=1".to_string(), ..Default::default() };
+        assert_eq!(remote.download_url(), remote.url);
+        assert_eq!(remote.display_filename(), "stems.zip");
+        assert_eq!(remote.type_label().as_deref(), Some("ZIP"));
+        assert_eq!(remote.size_label(), None);
+    }
+
+    fn video(id: Uuid, metadata: MediaMetadata) -> MediaItem {
+        MediaItem {
+            id,
+            url: format!("/media/{}/walk.mp4", id),
+            original_name: "Walk.mp4".to_string(),
+            mime_type: "video/mp4".to_string(),
+            byte_size: 1024,
+            local_path: None,
+            metadata: sqlx::types::Json(metadata),
+            created_at: time::OffsetDateTime::UNIX_EPOCH,
+        }
+    }
+
+    fn render(content: &BlockContent) -> String {
+        BlockTemplate { content }.render().unwrap()
+    }
+
+    #[test]
+    fn test_safe_filename() {
+        assert_eq!(safe_filename("Walk in the park.mp4"), "Walk-in-the-park.mp4");
+        assert_eq!(safe_filename("../../etc/passwd"), "passwd");
+        assert_eq!(safe_filename("..\\..\\boot.ini"), "boot.ini");
+        assert_eq!(safe_filename("uploads/.."), "file");
+        assert_eq!(safe_filename(".htaccess"), "htaccess");
+        assert_eq!(safe_filename("café.mov"), "caf-.mov");
+        assert_eq!(safe_filename(""), "file");
+        assert_eq!(safe_filename("/"), "file");
+    }
+
+    #[test]
+    fn test_local_storage_urls() {
+        let local = LocalStorage::new("/srv/media");
+        let id = Uuid::nil();
+        let dir = local.dir_for(id);
+        assert_eq!(local.url_for(&dir.join("walk.mp4")).as_deref(), Some(format!("/media/{}/walk.mp4", id).as_str()));
+        assert_eq!(local.url_for(&dir.join("hls/index.m3u8")).as_deref(), Some(format!("/media/{}/hls/index.m3u8", id).as_str()));
+        assert_eq!(local.url_for(Path::new("/etc/passwd")), None);
+    }
+
+    #[test]
+    fn test_cloudinary_poster_url() {
+        assert_eq!(
+            cloudinary_poster_url("https://res.cloudinary.com/demo/video/upload/v1/walk.mp4").as_deref(),
+            Some("https://res.cloudinary.com/demo/video/upload/v1/walk.jpg")
+        );
+        assert_eq!(cloudinary_poster_url("https://res.cloudinary.com/demo/image/upload/v1/a.png"), None);
+        assert_eq!(cloudinary_poster_url("https://res.cloudinary.com/demo/video/upload/v1.2/walk"), None);
+    }
+
+    #[test]
+    fn test_parse_ffprobe_output() {
+        let output = br#"{
+            "streams": [
+                {"codec_type": "audio", "sample_rate": "48000"},
+                {"codec_type": "video", "width": 1920, "height": 1080}
+            ],
+            "format": {"duration": "12.480000", "format_name": "mov,mp4"}
+        }"#;
+        assert_eq!(parse_probe(output), Some(Probe { duration: Some(12.48), width: Some(1920), height: Some(1080) }));
+        // Audio only, or a format without a duration.
+        assert_eq!(parse_probe(br#"{"streams": [{"codec_type": "audio"}], "format": {}}"#), Some(Probe::default()));
+        assert_eq!(parse_probe(b"not json"), None);
+    }
+
+    #[test]
+    fn test_hosted_video_form_round_trip() {
+        let id = Uuid::new_v4();
+        let metadata = MediaMetadata { duration: Some(65.0), width: Some(640), height: Some(360), ..Default::default() };
+        let form = HostedVideoForm {
+            media_id: Some(id),
+            captions: vec![
+                CaptionTrack { src: "/media/en.vtt".to_string(), srclang: "en".to_string(), label: "English".to_string() },
+                CaptionTrack { src: String::new(), srclang: "nl".to_string(), label: "Nederlands".to_string() },
+            ],
+        };
+        let block = hosted_video_block(form, video(id, metadata.clone())).unwrap();
+        let BlockContent::HostedVideo(saved) = &block else { panic!("not a hosted video") };
+        assert_eq!(saved.captions.len(), 1, "empty caption rows are dropped");
+        assert_eq!(saved.duration_label().as_deref(), Some("1:05"));
+
+        let form: HostedVideoForm = serde_json::from_str(&block_to_form_content(block.clone())).unwrap();
+        assert_eq!(form.media_id, Some(id));
+        let again = hosted_video_block(form, video(id, metadata)).unwrap();
+        assert_eq!(serde_json::to_value(&again).unwrap(), serde_json::to_value(&block).unwrap());
+
+        let not_video = MediaItem { mime_type: "image/png".to_string(), ..video(id, MediaMetadata::default()) };
+        assert!(hosted_video_block(HostedVideoForm { media_id: Some(id), captions: vec![] }, not_video).is_err());
+    }
+
+    #[test]
+    fn test_hosted_video_plays_the_original_until_the_stream_exists() {
+        let id = Uuid::new_v4();
+        let form = || HostedVideoForm { media_id: Some(id), captions: vec![] };
+        let mut block = hosted_video_block(form(), video(id, MediaMetadata::default())).unwrap();
+        let html = render(&block);
+        assert!(!html.contains("poster="));
+        assert!(!html.contains("m3u8"));
+        assert!(html.contains(&format!(r#"<source src="/media/{}/walk.mp4" type="video/mp4">"#, id)));
+
+        // The renditions made in the background reach blocks already saved,
+        // including inside Columns, and only those of this video.
+        let rendered = MediaMetadata {
+            poster_url: Some(format!("/media/{}/poster.jpg", id)),
+            hls_url: Some(format!("/media/{}/hls/index.m3u8", id)),
+            ..Default::default()
+        };
+        let mut columns = BlockContent::Columns(Columns { columns: vec![vec![block.clone()], vec![]] });
+        assert!(with_renditions(&mut columns, id, &rendered));
+        assert!(!with_renditions(&mut columns.clone(), Uuid::new_v4(), &rendered));
+        assert!(with_renditions(&mut block, id, &rendered));
+
+        let html = render(&block);
+        assert!(html.contains(&format!(r#"poster="/media/{}/poster.jpg""#, id)));
+        let hls = html.find("application/vnd.apple.mpegurl").unwrap();
+        assert!(hls < html.find("video/mp4").unwrap(), "the stream comes before the original");
+        assert_eq!(render(&columns).matches("index.m3u8").count(), 1);
+    }
+}
@@ -0,0 +1,254 @@
+#[cfg(test)]
+mod tests {
+    use axum::{extract::Query, http::StatusCode, response::IntoResponse, routing::get, Json, Router};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    use crate::oembed::{instagram_frame_url, meta_content, page_embed, page_url, OEmbedRegistry, Provider, Resolver};
+    use crate::sanitize::Policy;
+
+    // Stands in for a provider's oEmbed endpoint. The response depends on the
+    // path of the `url` being resolved, so each test can pick a behaviour.
+    async fn mock_oembed(Query(params): Query<HashMap<String, String>>) -> impl IntoResponse {
+        let url = params.get("url").cloned().unwrap_or_default();
+        if params.get("format").map(String::as_str) != Some("json") {
+            return StatusCode::NOT_IMPLEMENTED.into_response();
+        }
+
+        if url.ends_with("/video") {
+            Json(json!({
+                "version": "1.0",
+                "type": "video",
+                "provider_name": "Mock Media",
+                "title": "A Video",
+                "html": "<iframe src=\"https://player.example.com/embed/1\"></iframe>",
+                "thumbnail_url": "https://img.example.com/1.jpg",
+                "width": 640,
+                "height": "360"
+            }))
+            .into_response()
+        } else if url.ends_with("/photo") {
+            Json(json!({
+                "version": "1.0",
+                "type": "photo",
+                "url": "https://img.example.com/photo.jpg",
+                "title": "A \"Photo\""
+            }))
+            .into_response()
+        } else if url.ends_with("/hostile") {
+            Json(json!({
+                "version": "1.0",
+                "type": "rich",
+                "html": "<iframe src=\"//player.example.com/embed/2\" onload=\"steal()\" allow=\"autoplay\" allowfullscreen></iframe>\
+                         <iframe src=\"https://player.example.com.evil.com/x\"></iframe>\
+                         <script src=\"https://evil.com/x.js\"></script><p>Caption</p>"
+            }))
+            .into_response()
+        } else if url.ends_with("/link") {
+            Json(json!({ "version": "1.0", "type": "link", "title": "Just a link" })).into_response()
+        } else {
+            StatusCode::NOT_FOUND.into_response()
+        }
+    }
+
+    async fn registry_with_mock() -> OEmbedRegistry {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, Router::new().route("/oembed", get(mock_oembed)))
+                .await
+                .unwrap();
+        });
+
+        OEmbedRegistry::new(vec![Provider::new(
+            "Mock",
+            &format!("http://{}/oembed", addr),
+            &["https://media.example.com/*"],
+            &["https://player.example.com"],
+        )])
+    }
+
+    #[tokio::test]
+    async fn test_resolves_video_html() {
+        let registry = registry_with_mock().await;
+        let embed = registry.resolve(" https://media.example.com/works/video ").await.unwrap();
+        assert_eq!(embed.url, "https://media.example.com/works/video");
+        assert_eq!(embed.provider, "Mock Media");
+        assert_eq!(embed.title.as_deref(), Some("A Video"));
+        assert_eq!(embed.thumbnail_url.as_deref(), Some("https://img.example.com/1.jpg"));
+        assert_eq!((embed.width, embed.height), (Some(640), Some(360)));
+        assert!(embed.html.contains("player.example.com/embed/1"));
+    }
+
+    #[tokio::test]
+    async fn test_photo_becomes_escaped_img() {
+        let registry = registry_with_mock().await;
+        let embed = registry.resolve("http://media.example.com/works/photo").await.unwrap();
+        assert_eq!(embed.provider, "Mock");
+        assert_eq!(embed.html, r#"<img src="https://img.example.com/photo.jpg" alt="A &quot;Photo&quot;">"#);
+    }
+
+    #[tokio::test]
+    async fn test_provider_html_is_sanitised() {
+        let registry = registry_with_mock().await;
+        let embed = registry.resolve("https://media.example.com/works/hostile").await.unwrap();
+        assert!(embed.html.starts_with(
+            r#"<iframe src="https://player.example.com/embed/2" allow="autoplay" allowfullscreen=""></iframe>"#
+        ));
+        // Only the provider's player keeps a source.
+        assert!(!embed.html.contains("evil.com"));
+        assert!(!embed.html.contains("onload"));
+        assert!(!embed.html.contains("<script"));
+        assert!(embed.html.contains("<p>Caption</p>"));
+    }
+
+    #[test]
+    fn test_stored_embeds_are_cleaned_on_render() {
+        let policy = Policy::default().for_embed(&["https://player.vimeo.com".to_string()]);
+        assert_eq!(
+            policy.clean(r#"<iframe src="https://player.vimeo.com/video/1?h=2" width="640" frameborder="0"></iframe>"#),
+            r#"<iframe src="https://player.vimeo.com/video/1?h=2" width="640"></iframe>"#
+        );
+        assert_eq!(policy.clean(r#"<iframe src="https://player.vimeo.com.evil.com/"></iframe>"#), "<iframe></iframe>");
+        assert_eq!(policy.clean(r#"<iframe src="javascript:alert(1)"></iframe>"#), "<iframe></iframe>");
+        // Rich text still can't hold iframes at all.
+        assert_eq!(Policy::default().clean(r#"<iframe src="https://player.vimeo.com/video/1"></iframe>"#), "");
+    }
+
+    fn bandcamp() -> Provider {
+        crate::oembed::builtin_providers().into_iter().find(|p| p.name == "Bandcamp").unwrap()
+    }
+
+    #[test]
+    fn test_player_from_page_meta() {
+        // An album page's Open Graph tags, as Bandcamp writes them.
+        let page = r#"<html><head>
+            <meta property="og:title" content="Tapes &amp; Tides">
+            <meta content="https://f4.bcbits.com/img/a1_5.jpg" property="og:image">
+            <meta property="og:video" content="https://bandcamp.com/EmbeddedPlayer/v=2/album=1/size=large/">
+            <meta property="og:video:width" content="400"><meta property="og:video:height" content="120">
+            </head></html>"#;
+        let embed = page_embed(&bandcamp(), "https://stef.bandcamp.com/album/tapes", page).unwrap();
+        assert_eq!(embed.provider, "Bandcamp");
+        assert_eq!(embed.title.as_deref(), Some("Tapes & Tides"));
+        assert_eq!(embed.thumbnail_url.as_deref(), Some("https://f4.bcbits.com/img/a1_5.jpg"));
+        assert_eq!((embed.width, embed.height), (Some(400), Some(120)));
+        assert_eq!(
+            embed.html,
+            r#"<iframe src="https://bandcamp.com/EmbeddedPlayer/v=2/album=1/size=large/" title="Tapes &amp; Tides" width="400" height="120" allowfullscreen></iframe>"#
+        );
+
+        let page = r#"<meta property="og:title" content="Nothing">"#;
+        let error = page_embed(&bandcamp(), "https://stef.bandcamp.com/album/none", page).unwrap_err();
+        assert!(error.contains("link to an album or a track"), "{}", error);
+    }
+
+    #[test]
+    fn test_only_bandcamp_pages_are_fetched() {
+        let page = |url: &str| page_url("bandcamp.com", url).map(|u| u.to_string());
+        assert_eq!(page("https://stef.bandcamp.com/album/tapes").as_deref(), Ok("https://stef.bandcamp.com/album/tapes"));
+        assert_eq!(page("https://bandcamp.com/album/tapes").as_deref(), Ok("https://bandcamp.com/album/tapes"));
+        // What's fetched is the normalised address, over https.
+        assert_eq!(page("http://Stef.Bandcamp.com/track/one").as_deref(), Ok("https://stef.bandcamp.com/track/one"));
+
+        for url in [
+            "https://127.0.0.1:8080/.bandcamp.com/album/x",
+            "https://127.0.0.1/.bandcamp.com/album/x",
+            "https://stef.bandcamp.com:8443/album/x",
+            "https://user@stef.bandcamp.com/album/x",
+            "https://evilbandcamp.com/album/x",
+            "https://bandcamp.com.evil.com/album/x",
+            "ftp://stef.bandcamp.com/album/x",
+            "file:///etc/passwd",
+        ] {
+            assert!(page(url).is_err(), "{} was allowed", url);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bandcamp_wildcard_stays_in_the_host() {
+        let bandcamp = bandcamp();
+        assert!(!bandcamp.matches("https://127.0.0.1:8080/.bandcamp.com/album/x"));
+        assert!(!bandcamp.matches("https://localhost/.bandcamp.com/track/x"));
+        assert!(!bandcamp.matches("https://a.b.bandcamp.com.evil.com/album/x"));
+        assert!(bandcamp.matches("http://stef.bandcamp.com/album/tapes"));
+
+        // Refused before anything is fetched.
+        let registry = OEmbedRegistry::new(vec![bandcamp]);
+        let error = registry.resolve("https://127.0.0.1:8080/.bandcamp.com/album/x").await.unwrap_err();
+        assert!(error.starts_with("No enabled oEmbed provider handles"), "{}", error);
+    }
+
+    #[test]
+    fn test_meta_content() {
+        let page = r#"<meta name="description" content="x"><meta content='a &amp; b' property="og:title">"#;
+        assert_eq!(meta_content(page, "og:title").as_deref(), Some("a & b"));
+        assert_eq!(meta_content(page, "description").as_deref(), Some("x"));
+        assert_eq!(meta_content(page, "og:video"), None);
+    }
+
+    #[test]
+    fn test_bandcamp_and_instagram() {
+        let providers = crate::oembed::builtin_providers();
+        let bandcamp = providers.iter().find(|p| p.name == "Bandcamp").unwrap();
+        assert_eq!(bandcamp.resolver, Resolver::PageMeta { domain: "bandcamp.com" });
+        assert!(bandcamp.matches("https://artist.bandcamp.com/album/tapes"));
+        assert!(bandcamp.matches("https://artist.bandcamp.com/track/one"));
+        assert!(!bandcamp.matches("https://artist.bandcamp.com/merch"));
+
+        assert_eq!(
+            instagram_frame_url("https://www.instagram.com/p/C0de_1/?utm_source=ig").as_deref(),
+            Some("https://www.instagram.com/p/C0de_1/embed/")
+        );
+        assert_eq!(
+            instagram_frame_url("http://www.instagram.com/reel/Xy9").as_deref(),
+            Some("https://www.instagram.com/reel/Xy9/embed/")
+        );
+        assert_eq!(instagram_frame_url("https://www.instagram.com/stefmeul/"), None);
+    }
+
+    #[tokio::test]
+    async fn test_provider_errors_are_reported() {
+        let registry = registry_with_mock().await;
+        assert!(registry.resolve("https://media.example.com/works/missing").await.is_err());
+        assert!(registry.resolve("https://media.example.com/works/link").await.is_err());
+        assert!(registry.resolve("https://elsewhere.example.com/video").await.is_err());
+    }
+
+    #[test]
+    fn test_scheme_matching() {
+        let youtube = crate::oembed::builtin_providers()
+            .into_iter()
+            .find(|p| p.name == "YouTube")
+            .unwrap();
+        assert!(youtube.matches("https://www.youtube.com/watch?v=abc"));
+        assert!(youtube.matches("http://youtu.be/abc"));
+        assert!(!youtube.matches("https://www.youtube.com/channel/abc"));
+        assert!(!youtube.matches("https://evil.example.com/?https://youtu.be/abc"));
+
+        // In the host, `*` is one label; in the path, anything.
+        let wild = Provider::new("Wild", "", &["https://*.example.com/works/*"], &[]);
+        assert!(wild.matches("https://stef-2.example.com/works/a/b.jpg"));
+        assert!(!wild.matches("https://a.b.example.com/works/x"));
+        assert!(!wild.matches("https://evil.com:1/.example.com/works/x"));
+        assert!(!wild.matches("https://evil.com/x.example.com/works/x"));
+        assert!(!wild.matches("https://.example.com/works/x"));
+    }
+
+    #[test]
+    fn test_frame_src_lists_enabled_providers_and_video_blocks() {
+        let registry = OEmbedRegistry::new(vec![
+            Provider::new("A", "https://a.example.com/oembed", &["https://a.example.com/*"], &["https://player.a.example.com"]),
+            Provider::new("B", "https://b.example.com/oembed", &["https://b.example.com/*"], &["https://player.a.example.com", "https://b.example.com"]),
+        ]);
+        assert_eq!(
+            registry.frame_src(),
+            "'self' https://player.a.example.com https://b.example.com https://www.youtube.com https://player.vimeo.com"
+        );
+        // Video blocks frame YouTube and Vimeo without oEmbed.
+        assert_eq!(
+            OEmbedRegistry::new(Vec::new()).frame_src(),
+            "'self' https://www.youtube.com https://player.vimeo.com"
+        );
+    }
+}
@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod tests {
+    use askama::Template;
+    use time::OffsetDateTime;
+    use uuid::Uuid;
+
+    use crate::redirects::{normalize, redirects_file, stub_html, validate_from, validate_to, RedirectRule};
+    use crate::templates::RedirectsTemplate;
+
+    fn rule(from: &str, target: &str, project_title: Option<&str>) -> RedirectRule {
+        RedirectRule {
+            id: Uuid::new_v4(),
+            from_path: from.to_string(),
+            target: Some(target.to_string()),
+            project_title: project_title.map(str::to_string),
+            created_at: OffsetDateTime::now_utc(),
+        }
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("/old-work/"), "/old-work");
+        assert_eq!(normalize(" /old-work "), "/old-work");
+        assert_eq!(normalize("/"), "/");
+        assert_eq!(normalize("//"), "/");
+    }
+
+    #[test]
+    fn test_validate_from() {
+        assert_eq!(validate_from("/2019/old-work/").as_deref(), Ok("/2019/old-work"));
+        assert_eq!(validate_from("/project/gent-show").as_deref(), Ok("/project/gent-show"));
+        assert!(validate_from("old-work").is_err());
+        assert!(validate_from("/").is_err());
+        assert!(validate_from("/admin").is_err());
+        assert!(validate_from("/admin/login").is_err());
+        assert!(validate_from("/static/css/main.css").is_err());
+        assert!(validate_from("/media/x.jpg").is_err());
+        assert!(validate_from("/administrator").is_ok());
+        assert!(validate_from("/a/../etc").is_err());
+        assert!(validate_from("/a//b").is_err());
+        assert!(validate_from("/old work").is_err());
+        assert!(validate_from("/old?x=1").is_err());
+    }
+
+    #[test]
+    fn test_validate_to() {
+        assert_eq!(validate_to(" /project/new ", "/old").as_deref(), Ok("/project/new"));
+        assert!(validate_to("https://example.com/a?b=1&c=2", "/old").is_ok());
+        assert!(validate_to("//evil.example", "/old").is_err());
+        assert!(validate_to("javascript:alert(1)", "/old").is_err());
+        assert!(validate_to("project/new", "/old").is_err());
+        assert!(validate_to("/old/", "/old").is_err());
+        assert!(validate_to("/a b", "/old").is_err());
+        assert!(validate_to("/a\"><script>", "/old").is_err());
+        assert!(validate_to("/caf%C3%A9", "/old").is_ok());
+        assert!(validate_to("/café", "/old").is_err());
+    }
+
+    #[test]
+    fn test_redirects_file() {
+        let mut gone = rule("/project/deleted", "", None);
+        gone.target = None;
+        let rules = [rule("/old-work", "/project/new-work", Some("New Work")), gone, rule("/cv", "https://example.com/cv.pdf", None)];
+        assert_eq!(redirects_file(&rules), "/old-work /project/new-work 301\n/cv https://example.com/cv.pdf 301\n");
+        assert_eq!(redirects_file(&[]), "");
+    }
+
+    #[test]
+    fn test_stub_html_escapes_target() {
+        let html = stub_html("https://example.com/a?b=1&c=2");
+        assert!(html.contains(r#"<meta http-equiv="refresh" content="0; url=https://example.com/a?b=1&amp;c=2">"#));
+        assert!(html.contains(r#"<link rel="canonical" href="https://example.com/a?b=1&amp;c=2">"#));
+    }
+
+    #[test]
+    fn test_admin_list_keeps_refused_input() {
+        let html = RedirectsTemplate {
+            redirects: vec![rule("/project/old-slug", "/project/new-slug", Some("Gent Show"))],
+            from_path: "/admin".to_string(),
+            to: "/project/new-slug".to_string(),
+            error: Some("Admin, static and media addresses can't be redirected".to_string()),
+            flashes: vec![],
+            authenticity_token: "token".to_string(),
+        }
+        .render()
+        .unwrap();
+        assert!(html.contains("/project/old-slug → /project/new-slug"));
+        assert!(html.contains("Old slug of Gent Show"));
+        assert!(html.contains(r#"value="/admin""#));
+        assert!(html.contains("can&#x27;t be redirected"));
+    }
+}
@@ -0,0 +1,35 @@
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use crate::routes::api::{check_block_order, check_order, check_project_order, OrderError};
+
+    #[test]
+    fn test_any_permutation_is_accepted() {
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        assert_eq!(check_block_order(&[a, b, c], &[c, a, b]), Ok(()));
+        assert_eq!(check_block_order(&[], &[]), Ok(()));
+    }
+
+    #[test]
+    fn test_order_must_list_exactly_the_projects_blocks() {
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let stranger = Uuid::new_v4();
+
+        assert_eq!(check_block_order(&[a, b], &[a, b, a]), Err(format!("Block {} is listed more than once", a)));
+        assert_eq!(check_block_order(&[a, b], &[b, stranger, a]), Err(format!("Block {} does not belong to this project", stranger)));
+        assert_eq!(check_block_order(&[a, b, c], &[b]), Err(format!("The order is missing block(s) {}, {}", a, c)));
+        // A block added elsewhere since the page was loaded.
+        assert!(check_block_order(&[a, b, c], &[b, a]).is_err());
+    }
+
+    #[test]
+    fn test_project_order_reports_in_project_terms() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let deleted = Uuid::new_v4();
+        assert_eq!(check_project_order(&[a, b], &[b, a]), Ok(()));
+        assert_eq!(check_project_order(&[a, b], &[b, deleted, a]), Err(format!("Project {} does not exist", deleted)));
+        assert_eq!(check_project_order(&[a, b], &[a]), Err(format!("The order is missing project(s) {}", b)));
+        assert_eq!(check_order(&[a, b], &[b, b]), Err(OrderError::Repeated(b)));
+    }
+}
@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod tests {
+    use crate::models::{BlockContent, Columns, Quote, TextBlock, TextFormat};
+    use crate::sanitize::{Policy, Stripped};
+
+    #[test]
+    fn test_quill_output_survives() {
+        let html = concat!(
+            r#"<h2>Title</h2><p class="ql-align-center">Centered <strong>bold</strong> <em>it</em> <u>u</u> <s>s</s></p>"#,
+            r#"<ol><li class="ql-indent-1">one</li></ol><blockquote>quote</blockquote>"#,
+            r#"<pre class="ql-syntax" spellcheck="false">let x = 1;</pre>"#,
+            r#"<p><a href="https://example.com" target="_blank">link</a> <a href="/page/about">about</a></p>"#,
+        );
+        let cleaned = Policy::default().clean(html);
+        assert!(cleaned.contains(r#"<p class="ql-align-center">Centered <strong>bold</strong>"#));
+        assert!(cleaned.contains(r#"<li class="ql-indent-1">one</li>"#));
+        assert!(cleaned.contains(r#"<pre class="ql-syntax">let x = 1;</pre>"#));
+        assert!(cleaned.contains(r#"<a href="https://example.com" target="_blank" rel="noopener noreferrer">link</a>"#));
+        assert!(cleaned.contains(r#"<a href="/page/about" rel="noopener noreferrer">about</a>"#));
+    }
+
+    #[test]
+    fn test_scripts_and_handlers_are_reported() {
+        let html = concat!(
+            r#"<p onclick="steal()">Hi<script>if (a<b) alert(1)</script></p>"#,
+            r#"<a href="javascript:alert(1)" target="_top">x</a><img src="/a.webp" onerror="x()">"#,
+            r#"<iframe src="https://evil.example"></iframe><p class="hero">styled</p>"#,
+        );
+        let (cleaned, stripped) = Policy::default().clean_with_report(html);
+        assert_eq!(cleaned, r#"<p>Hi</p><a rel="noopener noreferrer">x</a><img src="/a.webp"><p>styled</p>"#);
+        assert_eq!(stripped.tags.get("script"), Some(&1));
+        assert_eq!(stripped.tags.get("iframe"), Some(&1));
+        assert!(!stripped.tags.contains_key("b"), "script body isn't markup");
+        for attr in ["p[onclick]", "a[href]", "a[target]", "img[onerror]", "p[class]"] {
+            assert_eq!(stripped.attributes.get(attr), Some(&1), "{} not reported", attr);
+        }
+        assert!(!stripped.attributes.contains_key("img[src]"));
+        assert!(stripped.to_string().starts_with("<iframe>, <script>, a[href]"));
+    }
+
+    #[test]
+    fn test_clean_content_reports_nothing() {
+        let (cleaned, stripped) = Policy::default().clean_with_report("<p>&copy; 2024 <em>Artist</em></p>");
+        assert_eq!(cleaned, "<p>© 2024 <em>Artist</em></p>");
+        assert!(stripped.is_empty());
+        assert_eq!(stripped, Stripped::default());
+    }
+
+    #[test]
+    fn test_policy_can_be_adjusted() {
+        let mut policy = Policy::default();
+        policy.allow_tags(&["details".to_string(), "summary".to_string(), "script".to_string()]);
+        policy.deny_tags(&["img".to_string()]);
+        policy.allow_attribute(Some("details"), "open");
+        policy.allow_attribute(None, "onload");
+        policy.allow_attribute(None, "style");
+
+        let cleaned = policy.clean(r#"<details open style="color:red"><summary>More</summary>a</details><img src="/a.webp"><script>x</script>"#);
+        assert_eq!(cleaned, r#"<details open=""><summary>More</summary>a</details>"#);
+        assert!(!policy.generic_attributes.contains("onload"));
+    }
+
+    #[test]
+    fn test_markdown_tables_and_footnotes_survive() {
+        let html = Policy::default().clean(r#"<table><tr><th style="text-align: center; color: red">A</th></tr></table><div class="footnote hero" id="fn-1">x</div><a id="main" href="/">y</a>"#);
+        assert_eq!(
+            html,
+            r#"<table><tbody><tr><th style="text-align:center">A</th></tr></tbody></table><div class="footnote" id="fn-1">x</div><a rel="noopener noreferrer" href="/">y</a>"#
+        );
+    }
+
+    // Built by hand so nothing is sanitised until the test asks for it.
+    fn text(format: TextFormat, source: &str) -> BlockContent {
+        BlockContent::Text(TextBlock { format, source: source.to_string(), html: String::new() })
+    }
+
+    #[test]
+    fn test_block_sanitize_reaches_column_children() {
+        let mut block = BlockContent::Columns(Columns {
+            columns: vec![
+                vec![text(TextFormat::Html, r#"<p onclick="x()">left</p>"#)],
+                vec![
+                    text(TextFormat::Markdown, "right<script>x()</script>"),
+                    BlockContent::Quote(Quote { text: "<script>kept as text</script>".to_string(), attribution: None, source: None }),
+                ],
+            ],
+        });
+        let stripped = block.sanitize(&Policy::default());
+        assert_eq!(stripped.to_string(), "<script>, p[onclick]");
+
+        let BlockContent::Columns(layout) = &block else { unreachable!() };
+        assert!(matches!(&layout.columns[0][0], BlockContent::Text(t) if t.source == "<p>left</p>" && t.html == t.source));
+        // Markdown keeps its source; only the rendered HTML is cleaned.
+        assert!(matches!(&layout.columns[1][0], BlockContent::Text(t) if t.source.contains("<script>") && t.html == "<p>right</p>\n"));
+        // Quotes are escaped on render, not sanitised.
+        assert!(matches!(&layout.columns[1][1], BlockContent::Quote(q) if q.text.contains("<script>")));
+    }
+
+    #[test]
+    fn test_text_block_is_cleaned_on_render() {
+        // As if stored before the policy got stricter.
+        let stale = TextBlock { format: TextFormat::Html, source: String::new(), html: r#"<p>Hi</p><img src="x" onerror="alert(1)">"#.to_string() };
+        let html = BlockContent::Text(stale).render_html();
+        assert!(html.contains("<p>Hi</p>"));
+        assert!(!html.contains("onerror"));
+    }
+}
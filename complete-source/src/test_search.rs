@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod tests {
+    use askama::Template;
+    use uuid::Uuid;
+
+    use crate::models::{BlockContent, Columns, TextBlock, TextFormat};
+    use crate::search::{block_text, highlight, HitKind, SearchHit};
+    use crate::settings::{SearchLanguage, SiteSettings};
+    use crate::templates::{DashboardTemplate, SearchTemplate};
+
+    fn text(format: TextFormat, source: &str) -> BlockContent {
+        BlockContent::Text(TextBlock::new(format, source))
+    }
+
+    fn hit(kind: HitKind, slug: &str, snippet: &str) -> SearchHit {
+        SearchHit {
+            kind,
+            id: (kind == HitKind::Project).then(Uuid::nil),
+            slug: slug.to_string(),
+            title: "Gent <Show>".to_string(),
+            snippet: snippet.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_block_text_strips_markup() {
+        assert_eq!(
+            block_text(&text(TextFormat::Html, "<p>Large <b>paintings</b></p>")).as_deref(),
+            Some("Large paintings")
+        );
+        assert_eq!(block_text(&text(TextFormat::Markdown, "# Oil\n\n*on* canvas")).as_deref(), Some("Oil on canvas"));
+        assert_eq!(block_text(&text(TextFormat::Html, "<p> </p>")), None);
+        assert_eq!(block_text(&BlockContent::Gallery(vec!["/media/a.jpg".to_string()])), None);
+    }
+
+    #[test]
+    fn test_block_text_reads_columns() {
+        let columns = BlockContent::Columns(Columns {
+            columns: vec![
+                vec![text(TextFormat::Html, "<p>Left</p>"), BlockContent::Video("https://vimeo.com/1".to_string())],
+                vec![text(TextFormat::Html, "<p>Right</p>")],
+            ],
+        });
+        assert_eq!(block_text(&columns).as_deref(), Some("Left\n\nRight"));
+        assert_eq!(block_text(&BlockContent::Columns(Columns { columns: vec![vec![], vec![]] })), None);
+    }
+
+    #[test]
+    fn test_highlight_escapes_and_marks() {
+        assert_eq!(
+            highlight("Large \u{E000}paintings\u{E001} & <drawings>"),
+            "Large <mark>paintings</mark> &amp; &lt;drawings&gt;"
+        );
+        // Stray markers can't leave a tag open or closed twice.
+        assert_eq!(highlight("\u{E001}a \u{E000}b"), "a <mark>b</mark>");
+    }
+
+    #[test]
+    fn test_hit_paths() {
+        let project = hit(HitKind::Project, "gent-show", "");
+        assert_eq!(project.public_path(), "/project/gent-show");
+        assert_eq!(project.admin_path(), format!("/admin/projects/edit/{}", Uuid::nil()));
+        let page = hit(HitKind::Page, "about", "");
+        assert_eq!(page.public_path(), "/about");
+        assert_eq!(page.admin_path(), "/admin/pages/edit/about");
+    }
+
+    #[test]
+    fn test_search_language_setting() {
+        for &language in SearchLanguage::ALL {
+            assert_eq!(SearchLanguage::parse(language.name()), Some(language));
+        }
+        assert_eq!(SearchLanguage::parse("klingon"), None);
+
+        let rows = [("search_language".to_string(), "Dutch".to_string())];
+        let site = SiteSettings::from_rows(rows);
+        assert_eq!(site.search_language, SearchLanguage::Dutch);
+        let saved = site.to_rows().into_iter().map(|(k, v)| (k.to_string(), v));
+        assert_eq!(SiteSettings::from_rows(saved), site);
+    }
+
+    #[test]
+    fn test_search_page_shows_snippets() {
+        let html = SearchTemplate {
+            query: "paint".to_string(),
+            results: vec![hit(HitKind::Project, "gent-show", "Large <mark>paintings</mark>")],
+            index_url: None,
+            footer: String::new(),
+        }
+        .render()
+        .unwrap();
+        assert!(html.contains(r#"name="q" value="paint""#));
+        assert!(html.contains(r#"<a href="/project/gent-show">Gent &lt;Show&gt;</a>"#));
+        assert!(html.contains("Large <mark>paintings</mark>"));
+
+        let html = SearchTemplate { query: "<script>".to_string(), results: vec![], index_url: None, footer: String::new() }
+            .render()
+            .unwrap();
+        assert!(html.contains("Nothing found for “&lt;script&gt;”."));
+    }
+
+    #[test]
+    fn test_dashboard_links_results_to_editors() {
+        let html = DashboardTemplate {
+            projects: vec![],
+            query: "bio".to_string(),
+            results: vec![hit(HitKind::Page, "about", "")],
+            flashes: vec![],
+            authenticity_token: "token".to_string(),
+        }
+        .render()
+        .unwrap();
+        assert!(html.contains(r#"<a href="/admin/pages/edit/about">"#));
+        assert!(html.contains("Results for “bio”"));
+        assert!(!html.contains("search-snippet"));
+    }
+}
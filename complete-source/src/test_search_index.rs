@@ -0,0 +1,135 @@
+#[cfg(test)]
+mod tests {
+    use askama::Template;
+    use time::OffsetDateTime;
+
+    use crate::models::{BlockContent, BlockKind, Page, Project, TextBlock, TextFormat};
+    use crate::search_index::{build, excerpt, IndexEntry, MANIFEST_PATH};
+    use crate::templates::SearchTemplate;
+    use crate::test_fixtures::{self, block};
+
+    fn project() -> Project {
+        Project {
+            description: Some("Large   paintings\nin oil.".to_string()),
+            location_name: Some("Gent".to_string()),
+            ..test_fixtures::project("Gent Show", "gent-show")
+        }
+    }
+
+    fn entry(title: &str, text: &str) -> IndexEntry {
+        IndexEntry {
+            title: title.to_string(),
+            slug: title.to_lowercase(),
+            url: format!("/project/{}", title.to_lowercase()),
+            year: Some(2024),
+            tags: vec![],
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_project_entry() {
+        let blocks = [
+            block(BlockContent::Text(TextBlock::new(TextFormat::Html, "<p>Shown at <b>S.M.A.K.</b></p>"))),
+            block(BlockContent::Video("https://vimeo.com/1".to_string())),
+            block(BlockContent::Gallery(vec![])),
+            block(BlockContent::Video("https://vimeo.com/2".to_string())),
+        ];
+        assert_eq!(blocks[1].block_type, BlockKind::Video);
+        let entry = IndexEntry::project(&project(), &blocks);
+        assert_eq!(entry.url, "/project/gent-show");
+        assert_eq!(entry.year, Some(2024));
+        assert_eq!(entry.tags, vec!["Gent", "video", "images"]);
+        assert_eq!(entry.text, "Large paintings in oil. Shown at S.M.A.K.");
+    }
+
+    #[test]
+    fn test_page_entry_is_plain_text() {
+        let page = Page {
+            slug: "about".to_string(),
+            title: "About".to_string(),
+            content: "<h1>Bio</h1>".to_string(),
+            updated_at: OffsetDateTime::now_utc(),
+            format: TextFormat::Markdown,
+            source: "# Bio\n\nBorn in *Gent*.".to_string(),
+        };
+        let entry = IndexEntry::page(&page);
+        assert_eq!(entry.url, "/about");
+        assert_eq!(entry.text, "Bio Born in Gent.");
+        // Pages have no year or tags, which are left out of the JSON.
+        assert_eq!(
+            serde_json::to_value(&entry).unwrap(),
+            serde_json::json!({ "title": "About", "slug": "about", "url": "/about", "text": "Bio Born in Gent." })
+        );
+    }
+
+    #[test]
+    fn test_excerpt_cuts_between_words() {
+        assert_eq!(excerpt("  one\ttwo\n three ", 100), "one two three");
+        assert_eq!(excerpt("one two three", 8), "one two");
+        assert_eq!(excerpt("één twee", 4), "één");
+        assert_eq!(excerpt("unbreakable", 4), "");
+    }
+
+    #[test]
+    fn test_chunks_stay_under_the_limit() {
+        let entries: Vec<IndexEntry> = (0..10).map(|i| entry(&format!("Work{}", i), &"word ".repeat(20))).collect();
+        let size = serde_json::to_string(&entries[0]).unwrap().len();
+        let index = build(&entries, size * 3 + 4).unwrap();
+
+        assert_eq!(index.chunks.len(), 4);
+        let mut titles = Vec::new();
+        for (name, json) in &index.chunks {
+            assert!(json.len() <= size * 3 + 4, "{} is {} bytes", name, json.len());
+            let chunk: Vec<serde_json::Value> = serde_json::from_str(json).unwrap();
+            titles.extend(chunk.into_iter().map(|e| e["title"].as_str().unwrap().to_string()));
+        }
+        assert_eq!(titles, entries.iter().map(|e| e.title.clone()).collect::<Vec<_>>());
+
+        let manifest: serde_json::Value = serde_json::from_str(&index.manifest).unwrap();
+        assert_eq!(manifest["entries"], 10);
+        assert_eq!(manifest["chunks"][0], "/search/chunk-0.json");
+        assert_eq!(index.chunks[3].0, "chunk-3.json");
+    }
+
+    #[test]
+    fn test_chunk_limit_counts_every_byte() {
+        let entries: Vec<IndexEntry> = (0..3).map(|i| entry(&format!("Work{}", i), "x")).collect();
+        let size = serde_json::to_string(&entries[0]).unwrap().len();
+
+        // Three entries, two commas and the brackets fill the chunk exactly.
+        let index = build(&entries, size * 3 + 4).unwrap();
+        assert_eq!(index.chunks.len(), 1);
+        assert_eq!(index.chunks[0].1.len(), size * 3 + 4);
+
+        // One byte less and the third doesn't fit.
+        let index = build(&entries, size * 3 + 3).unwrap();
+        let sizes: Vec<usize> = index.chunks.iter().map(|(_, json)| json.len()).collect();
+        assert_eq!(sizes, [size * 2 + 3, size + 2]);
+    }
+
+    #[test]
+    fn test_oversized_entries_get_their_own_chunk() {
+        let entries = [entry("Big", &"word ".repeat(100)), entry("Small", "x")];
+        let index = build(&entries, 100).unwrap();
+        assert_eq!(index.chunks.len(), 2);
+
+        let empty = build(&[], 100).unwrap();
+        assert!(empty.chunks.is_empty());
+        assert_eq!(empty.manifest, r#"{"entries":0,"chunks":[]}"#);
+    }
+
+    #[test]
+    fn test_static_search_page_points_at_the_index() {
+        let html = SearchTemplate {
+            query: String::new(),
+            results: vec![],
+            index_url: Some(MANIFEST_PATH.to_string()),
+            footer: String::new(),
+        }
+        .render()
+        .unwrap();
+        assert!(html.contains(r#"data-search-index="/search/index.json""#));
+        assert!(html.contains("<noscript>"));
+    }
+}
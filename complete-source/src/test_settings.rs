@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod tests {
+    use askama::Template;
+    use time::{Date, Month};
+
+    use crate::models::Project;
+    use crate::routes::public::{by_year, neighbours};
+    use crate::settings::{IndexOrder, SiteSettings};
+    use crate::templates::IndexTemplate;
+    use crate::test_fixtures;
+
+    fn rows(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_index_order_names() {
+        for &order in IndexOrder::ALL {
+            assert_eq!(IndexOrder::parse(order.name()), Some(order));
+        }
+        assert_eq!(IndexOrder::parse(" Manual "), Some(IndexOrder::Manual));
+        assert_eq!(IndexOrder::parse("random"), None);
+        assert_eq!(IndexOrder::default(), IndexOrder::Chronological);
+        assert!(IndexOrder::Manual.order_by().starts_with("position ASC NULLS LAST"));
+    }
+
+    #[test]
+    fn test_settings_fall_back_to_defaults() {
+        assert_eq!(SiteSettings::from_rows(rows(&[])), SiteSettings::default());
+        assert_eq!(SiteSettings::from_rows(rows(&[("index_order", "sideways"), ("theme", "dark")])), SiteSettings::default());
+
+        let manual = SiteSettings::from_rows(rows(&[("index_order", "manual")]));
+        assert_eq!(manual.index_order, IndexOrder::Manual);
+        let saved = manual.to_rows().into_iter().map(|(k, v)| (k.to_string(), v));
+        assert_eq!(SiteSettings::from_rows(saved), manual);
+    }
+
+    fn project(title: &str, year: i32, featured: bool) -> Project {
+        Project {
+            start_date: Date::from_calendar_date(year, Month::June, 1).unwrap(),
+            featured,
+            ..test_fixtures::project(title, &title.to_lowercase().replace(' ', "-"))
+        }
+    }
+
+    #[test]
+    fn test_featured_section_above_timeline() {
+        let html = IndexTemplate {
+            featured: vec![project("Big Commission", 2024, true)],
+            grouped_projects: vec![(2024, vec![project("Big Commission", 2024, true), project("Sketch", 2024, false)])],
+            json_ld: String::new(),
+            footer: String::new(),
+        }
+        .render()
+        .unwrap();
+        let featured = html.find(r#"class="featured-projects""#).expect("featured section");
+        assert!(featured < html.find(r#"class="cards-container""#).unwrap());
+        assert!(html.contains(r#"<a href="/project/big-commission">"#));
+
+        let html = IndexTemplate { featured: vec![], grouped_projects: vec![(2024, vec![project("Sketch", 2024, false)])], json_ld: String::new(), footer: String::new() }
+            .render()
+            .unwrap();
+        assert!(!html.contains("featured-projects"));
+    }
+
+    #[test]
+    fn test_project_neighbours_follow_the_index() {
+        // In manual order, as `IndexOrder::Manual` sorts them.
+        let placed = |title: &str, year: i32, position: i32| Project { position: Some(position), ..project(title, year, false) };
+        let projects = || vec![placed("Early", 2023, 0), placed("Mural", 2024, 1), placed("Print", 2024, 2), placed("Sketch", 2023, 3)];
+        let titles: Vec<Vec<String>> = by_year(projects()).into_iter().map(|(_, p)| p.into_iter().map(|p| p.title).collect()).collect();
+        assert_eq!(titles, [vec!["Mural", "Print"], vec!["Early", "Sketch"]]);
+
+        let around = |title: &str| {
+            let projects = projects();
+            let id = projects.iter().find(|p| p.title == title).unwrap().id;
+            let (prev, next) = neighbours(by_year(projects), id);
+            (prev.map(|p| p.title), next.map(|p| p.title))
+        };
+        let named = |title: &str| Some(title.to_string());
+        assert_eq!(around("Mural"), (None, named("Print")));
+        // Across the year boundary, not by date: Early started before Sketch.
+        assert_eq!(around("Print"), (named("Mural"), named("Early")));
+        assert_eq!(around("Early"), (named("Print"), named("Sketch")));
+        assert_eq!(around("Sketch"), (named("Early"), None));
+    }
+}
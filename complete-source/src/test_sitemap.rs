@@ -0,0 +1,135 @@
+#[cfg(test)]
+mod tests {
+    use askama::Template;
+    use time::{Date, Duration, Month, UtcOffset};
+
+    use crate::robots;
+    use crate::settings::SiteSettings;
+    use crate::site::Site;
+    use crate::sitemap::{self, SitemapUrl};
+    use crate::templates::SettingsTemplate;
+    use crate::validation::FieldErrors;
+
+    const SITEMAPS: &str = "http://www.sitemaps.org/schemas/sitemap/0.9";
+
+    fn site() -> Site {
+        Site::new("https://example.org", "Stef Meul")
+    }
+
+    fn urls(count: usize) -> Vec<SitemapUrl> {
+        (0..count)
+            .map(|i| {
+                let lastmod = Date::from_calendar_date(2024, Month::March, 1)
+                    .unwrap()
+                    .with_hms_milli(12, 30, 0, 250)
+                    .unwrap()
+                    .assume_offset(UtcOffset::from_hms(1, 0, 0).unwrap())
+                    + Duration::days(i as i64);
+                SitemapUrl::new(&site(), &format!("/project/work-{}", i), Some(lastmod))
+            })
+            .collect()
+    }
+
+    /// Each `<loc>` and `<lastmod>` pair under the root of `xml`.
+    fn entries(xml: &str, root: &str, entry: &str) -> Vec<(String, Option<String>)> {
+        let doc = roxmltree::Document::parse(xml).unwrap();
+        assert!(doc.root_element().has_tag_name((SITEMAPS, root)));
+        let text = |node: roxmltree::Node<'_, '_>, name: &str| {
+            node.children().find(|n| n.has_tag_name((SITEMAPS, name))).and_then(|n| n.text()).map(str::to_string)
+        };
+        doc.root_element()
+            .children()
+            .filter(|n| n.has_tag_name((SITEMAPS, entry)))
+            .map(|n| (text(n, "loc").unwrap(), text(n, "lastmod")))
+            .collect()
+    }
+
+    #[test]
+    fn test_small_sitemap_is_one_urlset() {
+        let mut urls = urls(2);
+        urls.push(SitemapUrl::new(&site(), "/about?a=1&b=2", None));
+        let files = sitemap::files(&site(), &urls, sitemap::MAX_URLS).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].0, "/sitemap.xml");
+
+        let found = entries(&files[0].1, "urlset", "url");
+        assert_eq!(found.len(), 3);
+        // In UTC, to the second.
+        assert_eq!(found[0], ("https://example.org/project/work-0".to_string(), Some("2024-03-01T11:30:00Z".to_string())));
+        assert_eq!(found[2], ("https://example.org/about?a=1&b=2".to_string(), None));
+    }
+
+    #[test]
+    fn test_large_sitemap_is_split_behind_an_index() {
+        let urls = urls(5);
+        let files = sitemap::files(&site(), &urls, 2).unwrap();
+        let paths: Vec<&str> = files.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, ["/sitemap.xml", "/sitemap/1.xml", "/sitemap/2.xml", "/sitemap/3.xml"]);
+
+        let parts = entries(&files[0].1, "sitemapindex", "sitemap");
+        assert_eq!(parts.len(), 3);
+        // A part changed when its newest address did.
+        assert_eq!(parts[0], ("https://example.org/sitemap/1.xml".to_string(), Some("2024-03-02T11:30:00Z".to_string())));
+        assert_eq!(parts[2].1.as_deref(), Some("2024-03-05T11:30:00Z"));
+
+        let listed: Vec<String> = files[1..].iter().flat_map(|(_, xml)| entries(xml, "urlset", "url")).map(|(loc, _)| loc).collect();
+        assert_eq!(listed, urls.iter().map(|u| u.loc.clone()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_robots_keeps_every_agent_out_of_the_admin() {
+        let robots = robots::render(&site(), "Disallow: /download\n\n# No training\nUser-agent: GPTBot\nUser-agent: CCBot\nDisallow: /\n");
+        assert_eq!(
+            robots,
+            "User-agent: *\nDisallow: /admin\nDisallow: /download\n# No training\n\n\
+             User-agent: GPTBot\nUser-agent: CCBot\nDisallow: /admin\nDisallow: /\n\n\
+             Sitemap: https://example.org/sitemap.xml\n"
+        );
+        // A group of its own can't widen the one for all crawlers.
+        assert_eq!(
+            robots::render(&site(), "User-agent: GPTBot\nDisallow: /"),
+            "User-agent: *\nDisallow: /admin\n\nUser-agent: GPTBot\nDisallow: /admin\nDisallow: /\n\n\
+             Sitemap: https://example.org/sitemap.xml\n"
+        );
+        assert_eq!(
+            robots::render(&site(), ""),
+            "User-agent: *\nDisallow: /admin\n\nSitemap: https://example.org/sitemap.xml\n"
+        );
+    }
+
+    #[test]
+    fn test_robots_rules_are_checked() {
+        let mut errors = FieldErrors::new();
+        let rules = robots::validate(&mut errors, "  user-agent: Bingbot \r\ncrawl-delay: 10\n# fine\n\n");
+        assert!(errors.is_empty());
+        assert_eq!(rules, "user-agent: Bingbot\ncrawl-delay: 10\n# fine");
+
+        robots::validate(&mut errors, "Disallow: /drafts\nNoindex /drafts\nnonsense");
+        assert_eq!(errors.get("robots_rules"), Some("Line 2 isn't a rule like “Disallow: /drafts” or a # comment"));
+    }
+
+    #[test]
+    fn test_settings_keep_refused_rules() {
+        let rows = [("robots_rules".to_string(), "User-agent: GPTBot\nDisallow: /".to_string())];
+        let site = SiteSettings::from_rows(rows);
+        let saved = site.to_rows().into_iter().map(|(k, v)| (k.to_string(), v));
+        assert_eq!(SiteSettings::from_rows(saved), site);
+
+        let mut errors = FieldErrors::new();
+        errors.add("robots_rules", "Line 1 isn't a rule");
+        let html = SettingsTemplate {
+            current_username: "stef".to_string(),
+            errors,
+            site: SiteSettings { robots_rules: "Block <everything>".to_string(), ..site },
+            site_title: "Stef Meul".to_string(),
+            index_orders: crate::settings::IndexOrder::ALL,
+            search_languages: crate::settings::SearchLanguage::ALL,
+            flashes: vec![],
+            authenticity_token: "token".to_string(),
+        }
+        .render()
+        .unwrap();
+        assert!(html.contains(r#"class="form-input input-error">Block &lt;everything&gt;</textarea>"#));
+        assert!(html.contains("Line 1 isn&#x27;t a rule."));
+    }
+}
@@ -0,0 +1,98 @@
+#[cfg(test)]
+mod tests {
+    use askama::Template;
+    use time::{Date, Month};
+
+    use crate::models::Project;
+    use crate::slug::{check, first_free, slugify, validate, SlugError, MAX_LEN};
+    use crate::templates::{ProjectFormTemplate, ProjectInput};
+    use crate::validation::FieldErrors;
+    use crate::test_fixtures;
+
+    fn taken(slugs: &[&str]) -> Vec<String> {
+        slugs.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_slugify_transliterates() {
+        assert_eq!(slugify("Café Müller"), "cafe-muller");
+        assert_eq!(slugify("  Łódź — Night Walks (2024)! "), "lodz-night-walks-2024");
+        assert_eq!(slugify("Ærøskøbing"), "aeroskobing");
+        assert_eq!(slugify("!!! ???"), "");
+    }
+
+    #[test]
+    fn test_slugify_keeps_to_the_limit() {
+        let long = "word ".repeat(40);
+        let slug = slugify(&long);
+        assert!(slug.len() <= MAX_LEN);
+        assert!(!slug.ends_with('-'));
+        assert_eq!(slugify(&"a".repeat(100)).len(), MAX_LEN);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate("gent-show-2").is_ok());
+        assert!(validate("").is_err());
+        assert!(validate("Gent-Show").is_err());
+        assert!(validate("gent--show").is_err());
+        assert!(validate("-gent").is_err());
+        assert!(validate("gent show").is_err());
+        assert!(validate("../admin").is_err());
+        assert!(validate(&"a".repeat(MAX_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_first_free() {
+        assert_eq!(first_free("gent-show", &taken(&["other"])), "gent-show");
+        assert_eq!(first_free("gent-show", &taken(&["gent-show"])), "gent-show-2");
+        assert_eq!(first_free("gent-show", &taken(&["gent-show", "gent-show-2"])), "gent-show-3");
+        let long = "a".repeat(MAX_LEN);
+        let free = first_free(&long, &taken(&[&long]));
+        assert_eq!(free.len(), MAX_LEN);
+        assert!(free.ends_with("-2"));
+    }
+
+    #[test]
+    fn test_check_suggests_alternatives() {
+        assert_eq!(check("gent-show", "Gent Show", &taken(&["nowhere"])), Ok(()));
+
+        let error = check("gent-show", "Gent Show", &taken(&["gent-show"])).unwrap_err();
+        assert_eq!(error.suggestion.as_deref(), Some("gent-show-2"));
+
+        let error = check("Gent Show", "Whatever", &taken(&[])).unwrap_err();
+        assert_eq!(error.suggestion.as_deref(), Some("gent-show"));
+
+        let error = check("", "Café Müller", &taken(&["cafe-muller"])).unwrap_err();
+        assert_eq!(error.suggestion.as_deref(), Some("cafe-muller-2"));
+
+        let error = check("", "???", &taken(&[])).unwrap_err();
+        assert_eq!(error.suggestion, None);
+    }
+
+    #[test]
+    fn test_form_shows_slug_error_with_input() {
+        let project = Project {
+            description: Some("Kept as typed".to_string()),
+            start_date: Date::from_calendar_date(2024, Month::June, 1).unwrap(),
+            ..test_fixtures::project("Gent Show", "gent-show")
+        };
+        let html = ProjectFormTemplate {
+            input: ProjectInput::from(&project),
+            project: Some(project),
+            is_new: true,
+            errors: FieldErrors::new(),
+            slug_error: Some(SlugError::taken("gent-show", &taken(&["gent-show"]))),
+            gallery_images: vec![],
+            geocoding: false,
+            flashes: vec![],
+            authenticity_token: "token".to_string(),
+        }
+        .render()
+        .unwrap();
+        assert!(html.contains(r#"action="/admin/projects""#));
+        assert!(html.contains("Another project already uses this slug"));
+        assert!(html.contains(r#"data-slug="gent-show-2""#));
+        assert!(html.contains("Kept as typed"));
+    }
+}
@@ -0,0 +1,79 @@
+#[cfg(test)]
+mod tests {
+    use crate::models::{BlockContent, Columns, TextBlock, TextFormat};
+    use crate::thumbnail::{cloudinary_social_card_url, crop_rect, local_social_card, social_card_name, FocalPoint};
+    use crate::upload::{CloudinaryConfig, LocalStorage, Storage};
+
+    #[test]
+    fn test_focal_point_from_form_input() {
+        assert_eq!(FocalPoint::parse(Some("0.25"), Some(" 0.8 ")), FocalPoint { x: 0.25, y: 0.8 });
+        assert_eq!(FocalPoint::parse(Some("2"), Some("-1")), FocalPoint { x: 1.0, y: 0.0 });
+        assert_eq!(FocalPoint::parse(Some("left"), None), FocalPoint::default());
+        assert_eq!(FocalPoint::parse(Some("NaN"), Some("inf")), FocalPoint::default());
+        assert_eq!(FocalPoint::new(0.333, 0.5).css_position(), "33% 50%");
+    }
+
+    #[test]
+    fn test_crop_centres_on_focus_within_the_image() {
+        let card = 1200.0 / 630.0;
+        // A portrait image is cropped top to bottom around the focus...
+        assert_eq!(crop_rect(1000, 2000, card, FocalPoint::new(0.5, 0.5)), (1000, 525, 0, 738));
+        // ...but never past its edges.
+        assert_eq!(crop_rect(1000, 2000, card, FocalPoint::new(0.5, 0.0)), (1000, 525, 0, 0));
+        assert_eq!(crop_rect(1000, 2000, card, FocalPoint::new(0.5, 1.0)), (1000, 525, 0, 1475));
+        // A wide image is cropped side to side.
+        assert_eq!(crop_rect(4000, 1000, 1.0, FocalPoint::new(0.1, 0.5)), (1000, 1000, 0, 0));
+        assert_eq!(crop_rect(4000, 1000, 1.0, FocalPoint::new(0.6, 0.5)), (1000, 1000, 1900, 0));
+    }
+
+    #[test]
+    fn test_cloudinary_social_card() {
+        let url = "https://res.cloudinary.com/demo/image/upload/v1/works/piece.webp";
+        assert_eq!(
+            cloudinary_social_card_url(url, Some((1000, 2000)), FocalPoint::new(0.5, 0.0)).as_deref(),
+            Some("https://res.cloudinary.com/demo/image/upload/c_crop,w_1000,h_525,x_0,y_0/c_fill,w_1200,h_630/v1/works/piece.jpg")
+        );
+        // Unknown size: a centred crop is the best that can be done.
+        assert_eq!(
+            cloudinary_social_card_url(url, None, FocalPoint::new(0.2, 0.2)).as_deref(),
+            Some("https://res.cloudinary.com/demo/image/upload/c_fill,w_1200,h_630/v1/works/piece.jpg")
+        );
+        assert_eq!(cloudinary_social_card_url("/media/abc/piece.webp", None, FocalPoint::default()), None);
+        assert_eq!(cloudinary_social_card_url("https://res.cloudinary.com/demo/video/upload/v1/clip.mp4", None, FocalPoint::default()), None);
+    }
+
+    #[test]
+    fn test_gallery_images_include_columns() {
+        let block = BlockContent::Columns(Columns {
+            columns: vec![
+                vec![BlockContent::Gallery(vec!["/media/a.webp".to_string()])],
+                vec![
+                    BlockContent::Text(TextBlock::new(TextFormat::Html, "<p>x</p>")),
+                    BlockContent::Gallery(vec!["/media/b.webp".to_string(), "/media/c.webp".to_string()]),
+                ],
+            ],
+        });
+        assert_eq!(block.gallery_images(), vec!["/media/a.webp", "/media/b.webp", "/media/c.webp"]);
+        assert!(BlockContent::Video("https://example.com/v.mp4".to_string()).gallery_images().is_empty());
+    }
+
+    #[test]
+    fn test_replaced_social_cards_are_found_by_url() {
+        let local = LocalStorage::new("/srv/media");
+        let name = social_card_name(FocalPoint::new(0.25, 0.8));
+        assert_eq!(name, "social-250-800.jpg");
+        let card = local.url_for(&local.dir_for(uuid::Uuid::nil()).join(&name)).unwrap();
+        assert_eq!(local.path_for(&card), Some(local.dir_for(uuid::Uuid::nil()).join(&name)));
+
+        let storage = Storage::Local(local);
+        assert!(local_social_card(&storage, &card).is_some());
+        // Only rendered cards are ever removed, never the thumbnail itself.
+        assert_eq!(local_social_card(&storage, "/media/abc/social-photo.jpg"), None);
+        assert_eq!(local_social_card(&storage, "/media/abc/thumb.jpg"), None);
+        assert_eq!(local_social_card(&storage, "/media/../etc/social-1-2.jpg"), None);
+        assert_eq!(local_social_card(&storage, "https://res.cloudinary.com/demo/social-1-2.jpg"), None);
+
+        let cloudinary = Storage::Cloudinary(CloudinaryConfig::new("demo".into(), "key".into(), "secret".into()));
+        assert_eq!(local_social_card(&cloudinary, &card), None);
+    }
+}
@@ -0,0 +1,185 @@
+#[cfg(test)]
+mod tests {
+    use askama::Template;
+    use time::{Date, Month};
+    use uuid::Uuid;
+
+    use crate::models::{BlockKind, GeoPoint};
+    use crate::settings::{IndexOrder, SearchLanguage, SiteSettings};
+    use crate::templates::{BlockFormTemplate, ProjectFormTemplate, ProjectInput, SettingsTemplate};
+    use crate::validation::{coordinates, date_range, password, required, web_links, FieldErrors};
+
+    fn date(year: i32, month: Month, day: u8) -> Date {
+        Date::from_calendar_date(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_first_error_per_field_is_kept() {
+        let mut errors = FieldErrors::new();
+        assert!(errors.is_empty());
+        errors.add("title", "Title is required");
+        errors.add("title", "Title is too long");
+        assert_eq!(errors.get("title"), Some("Title is required"));
+        assert!(!errors.has("slug"));
+        assert_eq!(errors.clone().check(1), Err(errors));
+        assert_eq!(FieldErrors::new().check(1), Ok(1));
+    }
+
+    #[test]
+    fn test_required_trims() {
+        let mut errors = FieldErrors::new();
+        assert_eq!(required(&mut errors, "title", "Title", "  Gent  "), "Gent");
+        assert!(errors.is_empty());
+        required(&mut errors, "title", "Title", "   ");
+        assert_eq!(errors.get("title"), Some("Title is required"));
+    }
+
+    #[test]
+    fn test_date_range() {
+        let mut errors = FieldErrors::new();
+        assert_eq!(
+            date_range(&mut errors, "2024-03-01", Some("2024-03-10")),
+            (Some(date(2024, Month::March, 1)), Some(date(2024, Month::March, 10)))
+        );
+        assert_eq!(date_range(&mut errors, "2024-03-01", Some("")), (Some(date(2024, Month::March, 1)), None));
+        assert_eq!(date_range(&mut errors, "2024-03-01", None), (Some(date(2024, Month::March, 1)), None));
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_bad_start_date_is_an_error() {
+        let mut errors = FieldErrors::new();
+        assert_eq!(date_range(&mut errors, "next spring", None), (None, None));
+        assert_eq!(errors.get("start_date"), Some("Start date must be a date like 2024-03-01"));
+
+        let mut errors = FieldErrors::new();
+        date_range(&mut errors, "", Some("2024-13-01"));
+        assert_eq!(errors.get("start_date"), Some("Start date is required"));
+        assert_eq!(errors.get("end_date"), Some("End date must be a date like 2024-03-01"));
+    }
+
+    #[test]
+    fn test_end_date_before_start_date() {
+        let mut errors = FieldErrors::new();
+        date_range(&mut errors, "2024-03-10", Some("2024-03-01"));
+        assert_eq!(errors.get("end_date"), Some("End date can't be before the start date"));
+        assert!(!errors.has("start_date"));
+    }
+
+    #[test]
+    fn test_coordinates() {
+        let mut errors = FieldErrors::new();
+        assert_eq!(coordinates(&mut errors, Some(" 51.05 "), Some("3.72")), Some(GeoPoint { lat: 51.05, lng: 3.72 }));
+        assert_eq!(coordinates(&mut errors, Some(""), None), None);
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_bad_coordinates() {
+        let mut errors = FieldErrors::new();
+        coordinates(&mut errors, Some("north"), Some("200"));
+        assert_eq!(errors.get("latitude"), Some("Latitude must be a number"));
+        assert_eq!(errors.get("longitude"), Some("Longitude must be between -180 and 180"));
+
+        let mut errors = FieldErrors::new();
+        coordinates(&mut errors, Some("51.05"), Some(" "));
+        assert_eq!(errors.get("longitude"), Some("Enter both latitude and longitude, or neither"));
+        assert!(!errors.has("latitude"));
+    }
+
+    #[test]
+    fn test_password_length() {
+        let mut errors = FieldErrors::new();
+        password(&mut errors, "admin123");
+        assert!(errors.is_empty());
+        password(&mut errors, "short");
+        assert_eq!(errors.get("password"), Some("Password must be at least 8 characters"));
+    }
+
+    #[test]
+    fn test_artist_links_are_checked() {
+        let mut errors = FieldErrors::new();
+        let links = web_links(&mut errors, "artist_links", " https://vimeo.com/stef \r\n\nhttp://stef.be\n");
+        assert!(errors.is_empty());
+        assert_eq!(links, "https://vimeo.com/stef\nhttp://stef.be");
+
+        web_links(&mut errors, "artist_links", "https://vimeo.com/stef\ninstagram.com/stef\njavascript:alert(1)");
+        assert_eq!(
+            errors.get("artist_links"),
+            Some("\"instagram.com/stef\" isn't a web address like https://instagram.com/name")
+        );
+    }
+
+    #[test]
+    fn test_project_form_keeps_what_was_typed() {
+        let mut errors = FieldErrors::new();
+        errors.add("start_date", "Start date must be a date like 2024-03-01");
+        errors.add("latitude", "Latitude must be a number");
+        let html = ProjectFormTemplate {
+            project: None,
+            input: ProjectInput {
+                start_date: "next spring".to_string(),
+                latitude: "north".to_string(),
+                ..ProjectInput::default()
+            },
+            is_new: true,
+            errors,
+            slug_error: None,
+            gallery_images: vec![],
+            geocoding: false,
+            flashes: vec![],
+            authenticity_token: "token".to_string(),
+        }
+        .render()
+        .unwrap();
+        assert!(html.contains(r#"value="next spring""#));
+        assert!(html.contains(r#"value="north""#));
+        assert!(html.contains("Start date must be a date like 2024-03-01."));
+        assert!(html.contains("Latitude must be a number."));
+        assert_eq!(html.matches("form-input input-error").count(), 2);
+    }
+
+    #[test]
+    fn test_block_form_shows_content_error() {
+        let mut errors = FieldErrors::new();
+        errors.add("content", "A quote needs some text");
+        let html = BlockFormTemplate {
+            project_id: Uuid::nil(),
+            block_id: None,
+            block_type: BlockKind::Quote,
+            sort_order: "3".to_string(),
+            content: r#"{"text":""}"#.to_string(),
+            errors,
+            embed_providers: vec![],
+            media_videos: vec![],
+            flashes: vec![],
+            authenticity_token: "token".to_string(),
+        }
+        .render()
+        .unwrap();
+        assert!(html.contains("A quote needs some text"));
+        assert!(html.contains(r#"name="sort_order" value="3""#));
+        assert!(html.contains("{&quot;text&quot;:&quot;&quot;}"));
+    }
+
+    #[test]
+    fn test_settings_show_credential_errors() {
+        let mut errors = FieldErrors::new();
+        errors.add("password", "Password must be at least 8 characters");
+        let html = SettingsTemplate {
+            current_username: "stef".to_string(),
+            errors,
+            site: SiteSettings::default(),
+            site_title: "Stef Meul".to_string(),
+            index_orders: IndexOrder::ALL,
+            search_languages: SearchLanguage::ALL,
+            flashes: vec![],
+            authenticity_token: "token".to_string(),
+        }
+        .render()
+        .unwrap();
+        assert!(html.contains(r#"value="stef""#));
+        assert!(html.contains("Password must be at least 8 characters."));
+        assert!(html.contains(r#"minlength="8""#));
+    }
+}
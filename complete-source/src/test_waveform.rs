@@ -0,0 +1,64 @@
+#[cfg(test)]
+mod tests {
+    use axum::body::Bytes;
+
+    use crate::models::AudioItem;
+    use crate::waveform::{analyze, downsample, PEAK_COUNT};
+
+    // A mono 16-bit PCM WAV: one second of silence followed by one second
+    // of a full-scale square wave.
+    fn test_wav(sample_rate: u32) -> Vec<u8> {
+        let mut samples: Vec<i16> = vec![0; sample_rate as usize];
+        samples.extend((0..sample_rate).map(|i| if (i / 50) % 2 == 0 { i16::MAX } else { -i16::MAX }));
+        let data_len = (samples.len() * 2) as u32;
+
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+        wav.extend_from_slice(&sample_rate.to_le_bytes());
+        wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        for s in samples {
+            wav.extend_from_slice(&s.to_le_bytes());
+        }
+        wav
+    }
+
+    #[test]
+    fn test_analyzes_wav() {
+        let analysis = analyze(Bytes::from(test_wav(44_100)), Some("wav")).unwrap();
+        assert!((analysis.duration.unwrap() - 2.0).abs() < 0.01);
+        assert_eq!(analysis.peaks.len(), PEAK_COUNT);
+        assert_eq!(analysis.peaks[0], 0.0);
+        assert_eq!(analysis.peaks[PEAK_COUNT - 1], 1.0);
+        assert!(analysis.artist.is_none());
+    }
+
+    #[test]
+    fn test_rejects_non_audio() {
+        assert!(analyze(Bytes::from_static(b"definitely not audio"), Some("mp3")).is_err());
+        assert!(analyze(Bytes::new(), None).is_err());
+    }
+
+    #[test]
+    fn test_downsample_normalises() {
+        assert_eq!(downsample(&[0.1, 0.2, 0.4, 0.1], 2), vec![0.5, 1.0]);
+        assert_eq!(downsample(&[0.3, 0.6], 10), vec![0.5, 1.0]);
+        assert_eq!(downsample(&[0.0, 0.0], 2), vec![0.0, 0.0]);
+        assert!(downsample(&[], 10).is_empty());
+    }
+
+    #[test]
+    fn test_waveform_path_has_a_bar_per_peak() {
+        let item = AudioItem { peaks: vec![0.0, 1.0], ..Default::default() };
+        assert_eq!(item.waveform_path(), "M0.5 49.0V51.0M1.5 2.0V98.0");
+        assert_eq!(AudioItem::default().waveform_path(), "");
+    }
+}
@@ -0,0 +1,154 @@
+//! Project thumbnails: an uploaded or gallery image plus a focal point that
+//! decides how it is cropped. The index crops with CSS `object-position`;
+//! social cards get a 1200×630 image rendered when the project is saved.
+//!
+//! Local files are cropped with `ffmpeg` when it is on the PATH, Cloudinary
+//! images with a URL transformation. Without either, the thumbnail itself
+//! is used for social cards.
+
+use sqlx::PgPool;
+use std::path::Path;
+
+use crate::upload::Storage;
+
+pub const SOCIAL_CARD_WIDTH: u32 = 1200;
+pub const SOCIAL_CARD_HEIGHT: u32 = 630;
+
+/// The part of an image that should stay in view when it's cropped, as
+/// fractions of its width and height from the top left.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FocalPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Default for FocalPoint {
+    fn default() -> Self {
+        FocalPoint { x: 0.5, y: 0.5 }
+    }
+}
+
+impl FocalPoint {
+    pub fn new(x: f64, y: f64) -> FocalPoint {
+        let clamp = |v: f64| if v.is_finite() { v.clamp(0.0, 1.0) } else { 0.5 };
+        FocalPoint { x: clamp(x), y: clamp(y) }
+    }
+
+    /// Reads form input, falling back to the centre for anything unreadable.
+    pub fn parse(x: Option<&str>, y: Option<&str>) -> FocalPoint {
+        let parse = |v: Option<&str>| v.and_then(|v| v.trim().parse::<f64>().ok()).unwrap_or(0.5);
+        FocalPoint::new(parse(x), parse(y))
+    }
+
+    /// The CSS `object-position` that keeps this point in view.
+    pub fn css_position(&self) -> String {
+        format!("{}% {}%", (self.x * 100.0).round(), (self.y * 100.0).round())
+    }
+}
+
+/// The largest crop of `aspect` (width / height) that fits a `width`×`height`
+/// image, centred on `focus` as far as the edges allow. Returns the crop's
+/// `(width, height, x, y)` in pixels.
+pub fn crop_rect(width: u32, height: u32, aspect: f64, focus: FocalPoint) -> (u32, u32, u32, u32) {
+    let (w, h) = (width as f64, height as f64);
+    let (crop_w, crop_h) = if w / h > aspect { (h * aspect, h) } else { (w, w / aspect) };
+    let x = (w * focus.x - crop_w / 2.0).clamp(0.0, w - crop_w);
+    let y = (h * focus.y - crop_h / 2.0).clamp(0.0, h - crop_h);
+    (crop_w.round() as u32, crop_h.round() as u32, x.round() as u32, y.round() as u32)
+}
+
+/// A Cloudinary URL for the social card of an uploaded image. Without the
+/// image's dimensions the crop can't be placed, so it is centred instead.
+pub fn cloudinary_social_card_url(url: &str, size: Option<(u32, u32)>, focus: FocalPoint) -> Option<String> {
+    let (before, after) = url.split_once("/image/upload/")?;
+    let resize = format!("c_fill,w_{},h_{}", SOCIAL_CARD_WIDTH, SOCIAL_CARD_HEIGHT);
+    let transformation = match size {
+        Some((width, height)) if width > 0 && height > 0 => {
+            let aspect = SOCIAL_CARD_WIDTH as f64 / SOCIAL_CARD_HEIGHT as f64;
+            let (w, h, x, y) = crop_rect(width, height, aspect, focus);
+            format!("c_crop,w_{},h_{},x_{},y_{}/{}", w, h, x, y, resize)
+        }
+        _ => resize,
+    };
+    let base = after.rsplit_once('.').map_or(after, |(base, _)| base);
+    Some(format!("{}/image/upload/{}/{}.jpg", before, transformation, base))
+}
+
+/// Crop and scale `input` to a social card at `output` with ffmpeg. The
+/// crop is worked out in the filter, so the image size needn't be known.
+pub async fn render_social_card(input: &Path, output: &Path, focus: FocalPoint) -> Result<(), String> {
+    let (cw, ch) = (SOCIAL_CARD_WIDTH, SOCIAL_CARD_HEIGHT);
+    let filter = format!(
+        "crop=w='min(iw,ih*{cw}/{ch})':h='min(ih,iw*{ch}/{cw})':x='max(0,min(iw-ow,iw*{x}-ow/2))':y='max(0,min(ih-oh,ih*{y}-oh/2))',scale={cw}:{ch}",
+        x = focus.x,
+        y = focus.y,
+    );
+    crate::media::run_ffmpeg(&[
+        "-i".as_ref(), input.as_os_str(),
+        "-vf".as_ref(), filter.as_ref(),
+        "-frames:v".as_ref(), "1".as_ref(),
+        "-q:v".as_ref(), "3".as_ref(),
+        output.as_os_str(),
+    ]).await
+}
+
+/// The file a social card is rendered to, next to its thumbnail.
+pub fn social_card_name(focus: FocalPoint) -> String {
+    format!("social-{}-{}.jpg", (focus.x * 1000.0).round(), (focus.y * 1000.0).round())
+}
+
+fn is_social_card_name(name: &str) -> bool {
+    name.strip_prefix("social-")
+        .and_then(|rest| rest.strip_suffix(".jpg"))
+        .and_then(|rest| rest.split_once('-'))
+        .is_some_and(|(x, y)| x.parse::<u32>().is_ok() && y.parse::<u32>().is_ok())
+}
+
+/// The file of a rendered social card in local storage, if `url` is one.
+pub fn local_social_card(storage: &Storage, url: &str) -> Option<std::path::PathBuf> {
+    let path = storage.local()?.path_for(url)?;
+    is_social_card_name(path.file_name()?.to_str()?).then_some(path)
+}
+
+/// Deletes a social card that was replaced, e.g. when the focal point moved,
+/// unless another project still uses it. Copies of a project share its card.
+pub async fn remove_unused_social_card(pool: &PgPool, storage: &Storage, url: &str) {
+    let Some(path) = local_social_card(storage, url) else {
+        return;
+    };
+    let used: Result<bool, sqlx::Error> = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM projects WHERE social_image_url = $1)")
+        .bind(url)
+        .fetch_one(pool)
+        .await;
+    match used {
+        Ok(false) => {
+            if let Err(e) = tokio::fs::remove_file(&path).await {
+                tracing::warn!("Couldn't remove the old social card {:?}: {}", path, e);
+            }
+        }
+        Ok(true) => {}
+        Err(e) => tracing::warn!("Couldn't check whether {} is still used: {}", url, e),
+    }
+}
+
+/// The social card for a thumbnail, if one can be made. Images that aren't
+/// in the media library (e.g. pasted URLs) are only handled on Cloudinary.
+pub async fn social_card(pool: &PgPool, storage: &Storage, thumbnail_url: &str, focus: FocalPoint) -> Option<String> {
+    let item = crate::media::find_by_url(pool, thumbnail_url).await;
+
+    if let (Storage::Local(local), Some(path)) = (storage, item.as_ref().and_then(|i| i.local_path.as_deref())) {
+        let path = Path::new(path);
+        // Named after the focal point, so a changed one doesn't hit a cached card.
+        let card = path.parent()?.join(social_card_name(focus));
+        return match render_social_card(path, &card, focus).await {
+            Ok(()) => local.url_for(&card),
+            Err(e) => {
+                tracing::warn!("Social card for {:?} failed: {}", path, e);
+                None
+            }
+        };
+    }
+
+    let size = item.as_ref().and_then(|i| Some((i.metadata.0.width?, i.metadata.0.height?)));
+    cloudinary_social_card_url(thumbnail_url, size, focus)
+}
@@ -1,5 +1,7 @@
 use serde::Deserialize;
 use sha1::{Digest, Sha1};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
 
 #[derive(Clone)]
 pub struct CloudinaryConfig {
@@ -18,9 +20,123 @@ impl CloudinaryConfig {
     }
 }
 
+/// Files stored on the server's own disk and served under `/media`.
+#[derive(Clone)]
+pub struct LocalStorage {
+    pub root: PathBuf,
+}
+
+impl LocalStorage {
+    pub const URL_PREFIX: &'static str = "/media";
+
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn url_for(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        let parts: Vec<String> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        Some(format!("{}/{}", Self::URL_PREFIX, parts.join("/")))
+    }
+
+    /// The file behind a `/media` URL, the reverse of [`url_for`](Self::url_for).
+    pub fn path_for(&self, url: &str) -> Option<PathBuf> {
+        let relative = url.strip_prefix(Self::URL_PREFIX)?.strip_prefix('/')?;
+        let mut path = self.root.clone();
+        for part in relative.split('/') {
+            if part.is_empty() || part == "." || part == ".." || part.contains('\\') {
+                return None;
+            }
+            path.push(part);
+        }
+        Some(path)
+    }
+
+    /// Directory that holds an upload and anything derived from it.
+    pub fn dir_for(&self, id: Uuid) -> PathBuf {
+        self.root.join(id.to_string())
+    }
+}
+
+/// Where uploads go. Cloudinary is the default; `STORAGE_BACKEND=local`
+/// keeps files on disk instead, which also enables server-side processing
+/// that needs the file (e.g. HLS renditions).
+#[derive(Clone)]
+pub enum Storage {
+    Cloudinary(CloudinaryConfig),
+    Local(LocalStorage),
+}
+
+/// The result of storing an upload.
+pub struct StoredFile {
+    pub url: String,
+    /// Set for the local backend.
+    pub path: Option<PathBuf>,
+    /// Media details Cloudinary reports back for audio and video uploads.
+    pub duration: Option<f64>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+impl Storage {
+    pub fn local(&self) -> Option<&LocalStorage> {
+        match self {
+            Storage::Local(local) => Some(local),
+            Storage::Cloudinary(_) => None,
+        }
+    }
+
+    pub async fn store(&self, id: Uuid, file_bytes: Vec<u8>, filename: &str) -> Result<StoredFile, String> {
+        match self {
+            Storage::Cloudinary(config) => {
+                let uploaded = upload_file(config, file_bytes, filename, "auto").await?;
+                Ok(StoredFile {
+                    url: uploaded.secure_url,
+                    path: None,
+                    duration: uploaded.duration,
+                    width: uploaded.width,
+                    height: uploaded.height,
+                })
+            }
+            Storage::Local(local) => {
+                let dir = local.dir_for(id);
+                tokio::fs::create_dir_all(&dir).await.map_err(|e| e.to_string())?;
+                let path = dir.join(safe_filename(filename));
+                tokio::fs::write(&path, file_bytes).await.map_err(|e| e.to_string())?;
+                let url = local.url_for(&path).ok_or("Upload path outside media root")?;
+                Ok(StoredFile {
+                    url,
+                    path: Some(path),
+                    duration: None,
+                    width: None,
+                    height: None,
+                })
+            }
+        }
+    }
+}
+
+pub(crate) fn safe_filename(filename: &str) -> String {
+    let name: String = filename
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or("")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '-' })
+        .collect();
+    let name = name.trim_start_matches('.');
+    if name.is_empty() { "file".to_string() } else { name.to_string() }
+}
+
 #[derive(Deserialize)]
-struct CloudinaryResponse {
-    secure_url: String,
+pub struct CloudinaryResponse {
+    pub secure_url: String,
+    pub duration: Option<f64>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
 }
 
 pub async fn upload_file(
@@ -28,16 +144,16 @@ pub async fn upload_file(
     file_bytes: Vec<u8>,
     filename: &str,
     resource_type: &str, // "image", "video", or "auto"
-) -> Result<String, String> {
+) -> Result<CloudinaryResponse, String> {
     let client = reqwest::Client::new();
-    
+
     let timestamp = chrono::Utc::now().timestamp().to_string();
     let to_sign = format!("timestamp={}{}", timestamp, config.api_secret);
-    
+
     let mut hasher = Sha1::new();
     hasher.update(to_sign);
     let signature = format!("{:x}", hasher.finalize());
-    
+
     let part = reqwest::multipart::Part::bytes(file_bytes)
         .file_name(filename.to_string());
 
@@ -48,7 +164,7 @@ pub async fn upload_file(
         .part("file", part);
 
     let url = format!("https://api.cloudinary.com/v1_1/{}/{}/upload", config.cloud_name, resource_type);
-    
+
     let resp = client.post(&url)
         .multipart(form)
         .send()
@@ -60,6 +176,5 @@ pub async fn upload_file(
         return Err(format!("Upload failed: {}", text));
     }
 
-    let data: CloudinaryResponse = resp.json().await.map_err(|e| e.to_string())?;
-    Ok(data.secure_url)
+    resp.json().await.map_err(|e| e.to_string())
 }
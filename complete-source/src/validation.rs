@@ -0,0 +1,131 @@
+//! Field-level checks for the admin forms. A refused form is shown again with
+//! what was typed and a message next to each field that needs fixing.
+
+use time::Date;
+
+use crate::models::GeoPoint;
+
+/// The shortest password the credentials form accepts.
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Messages for the fields of a submitted form, keyed by field name.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FieldErrors(Vec<(&'static str, String)>);
+
+impl FieldErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records what's wrong with `field`. Only the first message for a field
+    /// is kept, as that's the one to fix first.
+    pub fn add(&mut self, field: &'static str, message: impl Into<String>) {
+        if !self.has(field) {
+            self.0.push((field, message.into()));
+        }
+    }
+
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.0.iter().find(|(name, _)| *name == field).map(|(_, message)| message.as_str())
+    }
+
+    pub fn has(&self, field: &str) -> bool {
+        self.get(field).is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// `value` if nothing was recorded, or the errors.
+    pub fn check<T>(self, value: T) -> Result<T, FieldErrors> {
+        if self.is_empty() { Ok(value) } else { Err(self) }
+    }
+}
+
+/// The trimmed value, or an error on `field` when it's empty.
+pub fn required<'a>(errors: &mut FieldErrors, field: &'static str, label: &str, value: &'a str) -> &'a str {
+    let value = value.trim();
+    if value.is_empty() {
+        errors.add(field, format!("{} is required", label));
+    }
+    value
+}
+
+/// Parses a `YYYY-MM-DD` date, as sent by a date input.
+pub fn parse_date(value: &str) -> Option<Date> {
+    Date::parse(value.trim(), &time::format_description::well_known::Iso8601::DATE).ok()
+}
+
+/// The start date and optional end date of a date range.
+pub fn date_range(errors: &mut FieldErrors, start: &str, end: Option<&str>) -> (Option<Date>, Option<Date>) {
+    let start_date = match required(errors, "start_date", "Start date", start) {
+        "" => None,
+        start => parse_date(start).or_else(|| {
+            errors.add("start_date", "Start date must be a date like 2024-03-01");
+            None
+        }),
+    };
+    let end_date = match end.map(str::trim).filter(|e| !e.is_empty()) {
+        None => None,
+        Some(end) => parse_date(end).or_else(|| {
+            errors.add("end_date", "End date must be a date like 2024-03-01");
+            None
+        }),
+    };
+    if let (Some(start), Some(end)) = (start_date, end_date) {
+        if end < start {
+            errors.add("end_date", "End date can't be before the start date");
+        }
+    }
+    (start_date, end_date)
+}
+
+/// Coordinates entered as text: both or neither, each a number in range.
+pub fn coordinates(errors: &mut FieldErrors, latitude: Option<&str>, longitude: Option<&str>) -> Option<GeoPoint> {
+    let mut number = |field: &'static str, label: &str, value: Option<&str>, limit: f64| {
+        let value = value.map(str::trim).filter(|v| !v.is_empty())?;
+        match value.parse::<f64>() {
+            Ok(n) if (-limit..=limit).contains(&n) => Some(n),
+            Ok(_) => {
+                errors.add(field, format!("{} must be between -{} and {}", label, limit, limit));
+                None
+            }
+            Err(_) => {
+                errors.add(field, format!("{} must be a number", label));
+                None
+            }
+        }
+    };
+    let lat = number("latitude", "Latitude", latitude, 90.0);
+    let lng = number("longitude", "Longitude", longitude, 180.0);
+    match (lat, lng) {
+        (Some(lat), Some(lng)) => Some(GeoPoint { lat, lng }),
+        (None, Some(_)) if !errors.has("latitude") => {
+            errors.add("latitude", "Enter both latitude and longitude, or neither");
+            None
+        }
+        (Some(_), None) if !errors.has("longitude") => {
+            errors.add("longitude", "Enter both latitude and longitude, or neither");
+            None
+        }
+        _ => None,
+    }
+}
+
+/// A new password, which has to be long enough to be worth having.
+pub fn password(errors: &mut FieldErrors, value: &str) {
+    if value.chars().count() < MIN_PASSWORD_LENGTH {
+        errors.add("password", format!("Password must be at least {} characters", MIN_PASSWORD_LENGTH));
+    }
+}
+
+/// Web addresses entered one per line, as stored: trimmed, without blank
+/// lines. Each has to be a full `http(s)://` URL.
+pub fn web_links(errors: &mut FieldErrors, field: &'static str, value: &str) -> String {
+    let links: Vec<&str> = value.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    if let Some(bad) = links.iter().find(|l| reqwest::Url::parse(l).map_or(true, |u| !matches!(u.scheme(), "http" | "https"))) {
+        errors.add(field, format!("{:?} isn't a web address like https://instagram.com/name", bad));
+    }
+    links.join("\n")
+}
@@ -0,0 +1,151 @@
+//! Server-side audio analysis for Audio blocks: duration, tags and a small
+//! array of waveform peaks, so the public page can draw a track without
+//! downloading it first. WAV, MP3, FLAC and Ogg Vorbis are supported.
+
+use std::io::Cursor;
+
+use axum::body::Bytes;
+
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as DecodeError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::{MetadataOptions, StandardTagKey};
+use symphonia::core::probe::Hint;
+
+/// Number of peaks stored per track. Enough for a full-width player.
+pub const PEAK_COUNT: usize = 200;
+
+#[derive(Debug, Default)]
+pub struct AudioAnalysis {
+    pub duration: Option<f64>,
+    pub artist: Option<String>,
+    /// Normalised to 0.0..=1.0, `PEAK_COUNT` long (shorter for tiny files).
+    pub peaks: Vec<f32>,
+}
+
+/// Decode a whole file and summarise it. CPU-bound, so call it from
+/// `spawn_blocking`.
+pub fn analyze(bytes: Bytes, extension: Option<&str>) -> Result<AudioAnalysis, String> {
+    let mut hint = Hint::new();
+    if let Some(ext) = extension {
+        hint.with_extension(ext);
+    }
+
+    let source = MediaSourceStream::new(Box::new(Cursor::new(bytes)), Default::default());
+    let mut probed = symphonia::default::get_probe()
+        .format(&hint, source, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|e| format!("Unsupported audio format: {}", e))?;
+
+    // Tags can live in the container or in a leading ID3 block.
+    let mut artist = probed.format.metadata().current().and_then(|rev| artist_tag(rev.tags()));
+    if artist.is_none() {
+        if let Some(meta) = probed.metadata.get() {
+            artist = meta.current().and_then(|rev| artist_tag(rev.tags()));
+        }
+    }
+
+    let mut format = probed.format;
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or("No audio track found")?;
+    let track_id = track.id;
+    let sample_rate = track.codec_params.sample_rate;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| format!("Unsupported codec: {}", e))?;
+
+    // Keep the loudest sample of every chunk of frames, then fold those
+    // down to PEAK_COUNT once the total length is known.
+    const CHUNK_FRAMES: usize = 256;
+    let mut chunk_peaks: Vec<f32> = Vec::new();
+    let mut current_peak = 0f32;
+    let mut frames_in_chunk = 0usize;
+    let mut total_frames: u64 = 0;
+    let mut samples: Option<SampleBuffer<f32>> = None;
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(DecodeError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(DecodeError::ResetRequired) => break,
+            Err(e) => return Err(e.to_string()),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A corrupt packet shouldn't sink the whole upload.
+            Err(DecodeError::DecodeError(_)) => continue,
+            Err(e) => return Err(e.to_string()),
+        };
+
+        let spec = *decoded.spec();
+        let channels = spec.channels.count().max(1);
+        let buf = samples.get_or_insert_with(|| SampleBuffer::new(decoded.capacity() as u64, spec));
+        if buf.capacity() < decoded.capacity() * channels {
+            *buf = SampleBuffer::new(decoded.capacity() as u64, spec);
+        }
+        buf.copy_interleaved_ref(decoded);
+
+        for frame in buf.samples().chunks(channels) {
+            let loudest = frame.iter().fold(0f32, |acc, s| acc.max(s.abs()));
+            current_peak = current_peak.max(loudest);
+            frames_in_chunk += 1;
+            total_frames += 1;
+            if frames_in_chunk == CHUNK_FRAMES {
+                chunk_peaks.push(current_peak);
+                current_peak = 0.0;
+                frames_in_chunk = 0;
+            }
+        }
+    }
+    if frames_in_chunk > 0 {
+        chunk_peaks.push(current_peak);
+    }
+
+    if total_frames == 0 {
+        return Err("Audio file contains no samples".to_string());
+    }
+
+    Ok(AudioAnalysis {
+        duration: sample_rate.map(|rate| total_frames as f64 / rate as f64),
+        artist,
+        peaks: downsample(&chunk_peaks, PEAK_COUNT),
+    })
+}
+
+fn artist_tag(tags: &[symphonia::core::meta::Tag]) -> Option<String> {
+    tags.iter()
+        .find(|t| t.std_key == Some(StandardTagKey::Artist))
+        .map(|t| t.value.to_string().trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// Reduce `peaks` to at most `count` values (by taking the max of each
+/// bucket) and normalise so the loudest is 1.0. Values are rounded to keep
+/// the stored JSON small.
+pub fn downsample(peaks: &[f32], count: usize) -> Vec<f32> {
+    if peaks.is_empty() || count == 0 {
+        return Vec::new();
+    }
+    let buckets = count.min(peaks.len());
+    let reduced: Vec<f32> = (0..buckets)
+        .map(|i| {
+            let start = i * peaks.len() / buckets;
+            let end = ((i + 1) * peaks.len() / buckets).max(start + 1);
+            peaks[start..end].iter().fold(0f32, |a, &b| a.max(b))
+        })
+        .collect();
+
+    let max = reduced.iter().fold(0f32, |a, &b| a.max(b));
+    reduced
+        .into_iter()
+        .map(|p| if max > 0.0 { (p / max * 1000.0).round() / 1000.0 } else { 0.0 })
+        .collect()
+}
@@ -15,15 +15,7 @@ function initAdminBlocks() {
             });
 
             saveBtn.addEventListener('click', async function () {
-                const updates = [];
-                const items = list.querySelectorAll('.block-item');
-
-                items.forEach((item, index) => {
-                    updates.push({
-                        id: item.dataset.id,
-                        sort_order: index
-                    });
-                });
+                const blockIds = Array.from(list.querySelectorAll('.block-item'), item => item.dataset.id);
 
                 try {
                     const csrfToken = document.getElementById('csrf_token') ? document.getElementById('csrf_token').value : '';
@@ -33,14 +25,15 @@ function initAdminBlocks() {
                             'Content-Type': 'application/json',
                             'X-CSRF-Token': csrfToken
                         },
-                        body: JSON.stringify({ updates })
+                        body: JSON.stringify({ project_id: list.dataset.projectId, block_ids: blockIds })
                     });
 
                     if (resp.ok) {
                         saveBtn.style.display = 'none';
                         window.showAlert('Success', 'Order saved!');
                     } else {
-                        window.showAlert('Error', 'Failed to save order.');
+                        // e.g. a block was added or moved in another tab
+                        window.showAlert('Error', 'Failed to save order: ' + window.apiError(await resp.text()));
                     }
                 } catch (e) {
                     console.error(e);
@@ -50,7 +43,48 @@ function initAdminBlocks() {
         }
     }
 
-    // 2. Block Form Initialization (for Block Edit Page)
+    // 2. Project Ordering (for the Project Order screen)
+    const projectList = document.getElementById('projects-order');
+    const saveProjectsBtn = document.getElementById('save-project-order-btn');
+
+    if (projectList && saveProjectsBtn && !projectList.dataset.initialized && typeof Sortable !== 'undefined') {
+        projectList.dataset.initialized = 'true';
+        new Sortable(projectList, {
+            handle: '.drag-handle',
+            animation: 150,
+            onUpdate: function () {
+                saveProjectsBtn.style.display = 'inline-block';
+            }
+        });
+
+        saveProjectsBtn.addEventListener('click', async function () {
+            const projectIds = Array.from(projectList.querySelectorAll('.block-item'), item => item.dataset.id);
+
+            try {
+                const csrfToken = document.getElementById('csrf_token') ? document.getElementById('csrf_token').value : '';
+                const resp = await fetch('/admin/api/projects/order', {
+                    method: 'POST',
+                    headers: {
+                        'Content-Type': 'application/json',
+                        'X-CSRF-Token': csrfToken
+                    },
+                    body: JSON.stringify({ project_ids: projectIds })
+                });
+
+                if (resp.ok) {
+                    saveProjectsBtn.style.display = 'none';
+                    window.showAlert('Success', 'Order saved!');
+                } else {
+                    window.showAlert('Error', 'Failed to save order: ' + window.apiError(await resp.text()));
+                }
+            } catch (e) {
+                console.error(e);
+                window.showAlert('Error', 'Error saving order.');
+            }
+        });
+    }
+
+    // 3. Block Form Initialization (for Block Edit Page)
     if (window.initBlockForm) {
         if (document.getElementById('block-form')) {
             window.initBlockForm();
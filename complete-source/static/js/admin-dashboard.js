@@ -1,11 +1,4 @@
 function initAdminDashboard() {
-    const urlParams = new URLSearchParams(window.location.search);
-    if (urlParams.get('deploy') === 'success') {
-        window.showAlert('Success', 'Deployment triggered successfully!');
-        // clear param
-        window.history.replaceState({}, document.title, window.location.pathname);
-    }
-
     // Handle Deploy Confirmation
     const deployForm = document.getElementById('deploy-form');
     if (deployForm) {
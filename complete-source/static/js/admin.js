@@ -1,5 +1,158 @@
 console.log("Admin script loaded");
 
+// Renders `input` through the preview endpoint into `preview` while typing.
+// Returns a function that refreshes the preview straight away.
+window.bindTextPreview = function (input, preview, getFormat) {
+    const csrfToken = document.getElementById('csrf_token') ? document.getElementById('csrf_token').value : '';
+    let timer = null;
+    let latest = 0;
+
+    const refresh = () => {
+        const request = ++latest;
+        fetch('/admin/api/preview', {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+                'X-CSRF-Token': csrfToken
+            },
+            body: JSON.stringify({ format: getFormat(), source: input.value })
+        })
+            .then(resp => resp.ok ? resp.json() : Promise.reject(resp.status))
+            .then(data => {
+                // Ignore responses that arrive after a newer request.
+                if (request === latest) preview.innerHTML = data.html;
+            })
+            .catch(err => console.error('Preview failed:', err));
+    };
+
+    input.addEventListener('input', () => {
+        clearTimeout(timer);
+        timer = setTimeout(refresh, 300);
+    });
+    return refresh;
+};
+
+function initPageForm() {
+    const form = document.getElementById('page-form');
+    if (!form || form.dataset.initialized === 'true') return;
+    form.dataset.initialized = 'true';
+
+    const format = document.getElementById('page-format');
+    const refresh = window.bindTextPreview(document.getElementById('content'), document.getElementById('page-preview'), () => format.value);
+    format.addEventListener('change', refresh);
+}
+
+document.addEventListener('DOMContentLoaded', initPageForm);
+document.addEventListener('router:load', initPageForm);
+
+// Slug suggestions under a refused slug fill the field in.
+document.addEventListener('click', (event) => {
+    const button = event.target.closest('.slug-suggestion');
+    if (!button) return;
+    const input = document.getElementById('slug');
+    input.value = button.dataset.slug;
+    input.classList.remove('input-error');
+    button.closest('.field-error').remove();
+    input.focus();
+});
+
+// Thumbnail picker on the project form: upload or pick a gallery image,
+// then click it to set the focal point the crops centre on.
+function initProjectForm() {
+    const picker = document.getElementById('thumbnail-picker');
+    if (!picker || picker.dataset.initialized === 'true') return;
+    picker.dataset.initialized = 'true';
+
+    const urlInput = document.getElementById('thumbnail_url');
+    const focusX = document.getElementById('thumbnail_focus_x');
+    const focusY = document.getElementById('thumbnail_focus_y');
+    const editor = document.getElementById('thumbnail-editor');
+    const stage = document.getElementById('thumbnail-stage');
+    const image = document.getElementById('thumbnail-image');
+    const marker = document.getElementById('focal-marker');
+    const status = document.getElementById('thumbnail-status');
+    const crops = picker.querySelectorAll('.crop-preview');
+
+    function render() {
+        const url = urlInput.value;
+        editor.classList.toggle('hidden', !url);
+        picker.querySelectorAll('.thumbnail-option').forEach(option => {
+            option.classList.toggle('selected', option.dataset.url === url);
+        });
+        if (!url) return;
+
+        const x = parseFloat(focusX.value) * 100;
+        const y = parseFloat(focusY.value) * 100;
+        if (image.getAttribute('src') !== url) image.src = url;
+        marker.style.left = x + '%';
+        marker.style.top = y + '%';
+        crops.forEach(crop => {
+            if (crop.getAttribute('src') !== url) crop.src = url;
+            crop.style.objectPosition = `${x}% ${y}%`;
+        });
+    }
+
+    function choose(url) {
+        urlInput.value = url;
+        focusX.value = 0.5;
+        focusY.value = 0.5;
+        render();
+    }
+
+    stage.addEventListener('click', (e) => {
+        const rect = image.getBoundingClientRect();
+        const clamp = v => Math.min(1, Math.max(0, v));
+        focusX.value = clamp((e.clientX - rect.left) / rect.width).toFixed(3);
+        focusY.value = clamp((e.clientY - rect.top) / rect.height).toFixed(3);
+        render();
+    });
+
+    picker.querySelectorAll('.thumbnail-option').forEach(option => {
+        option.addEventListener('click', () => choose(option.dataset.url));
+    });
+
+    document.getElementById('thumbnail-remove').addEventListener('click', () => choose(''));
+
+    document.getElementById('thumbnail-file').addEventListener('change', async (e) => {
+        let file = e.target.files[0];
+        if (!file) return;
+        const submit = document.querySelector('#project-form button[type="submit"]');
+        submit.disabled = true;
+        status.textContent = 'Uploading ' + file.name + '...';
+
+        try {
+            try {
+                const compressed = await imageCompression(file, { maxSizeMB: 1, maxWidthOrHeight: 1920, useWebWorker: true, fileType: 'image/webp' });
+                file = new File([compressed], file.name.replace(/\.[^/.]+$/, '') + '.webp', { type: 'image/webp', lastModified: Date.now() });
+            } catch (error) {
+                console.error('Compression failed, using original file:', error);
+            }
+
+            const formData = new FormData();
+            formData.append('file', file);
+            const resp = await fetch('/admin/api/upload', {
+                method: 'POST',
+                headers: { 'X-CSRF-Token': document.getElementById('csrf_token').value },
+                body: formData
+            });
+            if (!resp.ok) throw window.apiError(await resp.text());
+            choose((await resp.json()).url);
+            status.textContent = '';
+        } catch (error) {
+            status.textContent = '';
+            window.showAlert('Error', 'Upload failed: ' + error);
+        } finally {
+            submit.disabled = false;
+            e.target.value = '';
+        }
+    });
+
+    render();
+}
+
+document.addEventListener('DOMContentLoaded', initProjectForm);
+document.addEventListener('router:load', initProjectForm);
+
 // Global initialization function for Block Form
 window.initBlockForm = function () {
     console.log("initBlockForm called");
@@ -25,17 +178,45 @@ window.initBlockForm = function () {
 
     // Elements
     const textGroup = document.getElementById('text-editor-group');
+    const textFormat = document.getElementById('text-format');
+    const quillWrapper = document.getElementById('quill-wrapper');
+    const markdownGroup = document.getElementById('markdown-group');
+    const markdownSource = document.getElementById('markdown-source');
+    const markdownPreview = document.getElementById('markdown-preview');
     const videoGroup = document.getElementById('video-input-group');
+    const embedGroup = document.getElementById('embed-input-group');
+    const hostedVideoGroup = document.getElementById('hosted-video-group');
+    const videoSelect = document.getElementById('media-video-select');
+    const captionList = document.getElementById('caption-list');
     const fileGroup = document.getElementById('file-uploader-group');
     const fileList = document.getElementById('file-list');
     const dropZone = document.getElementById('drop-zone');
     const fileInput = document.getElementById('file-input');
     const saveBtn = document.getElementById('save-btn');
+    const quoteGroup = document.getElementById('quote-group');
+    const rowsGroup = document.getElementById('rows-group');
+    const rowList = document.getElementById('row-list');
+    const columnsGroup = document.getElementById('columns-group');
+    const columnCount = document.getElementById('column-count');
+    const columnEditors = document.getElementById('column-editors');
 
     // State
     let uploadedFiles = [];
+    let captions = [];
+    let rows = [];
+    let columns = [];
     let isUploading = false;
 
+    // Declared before the per-type setup below, which renders straight away.
+    const ROW_FIELDS = {
+        Credits: [['role', 'Role (e.g. Sound design)'], ['name', 'Name']],
+        LinkList: [['label', 'Label'], ['url', 'https://...'], ['description', 'Description (Optional)']],
+        // Leave latitude/longitude empty to look the address up on save.
+        Map: [['label', 'Label'], ['address', 'Address (Optional)'], ['lat', 'Latitude'], ['lng', 'Longitude'], ['url', 'Link (Optional)']]
+    };
+    const ROW_LABELS = { Credits: 'Credits', LinkList: 'Links', Map: 'Markers' };
+    const CHILD_TYPES = ['Text', 'Quote', 'Credits', 'LinkList', 'Gallery', 'Video', 'Embed', 'Map'];
+
     // Helper to update save button state
     function updateSaveBtn() {
         if (saveBtn) {
@@ -48,6 +229,7 @@ window.initBlockForm = function () {
     // --- UI Initialization ---
     if (blockType === 'Text') {
         if (textGroup) textGroup.classList.remove('hidden');
+        const text = parseJson(initialContent, { format: 'html', source: '' });
 
         // Clear previous instance if any
         const editorContainer = document.getElementById('quill-editor');
@@ -88,12 +270,30 @@ window.initBlockForm = function () {
             }
             Quill.register(CustomLink, true);
 
-            if (initialContent) {
-                quill.clipboard.dangerouslyPasteHTML(initialContent);
+            if (text.format === 'html' && text.source) {
+                quill.clipboard.dangerouslyPasteHTML(text.source);
             }
+        }
 
-            quill.on('text-change', function () {
-                if (contentInput) contentInput.value = quill.root.innerHTML;
+        // Markdown is written in a plain textarea with a server-rendered preview.
+        if (markdownSource) {
+            if (text.format === 'markdown') markdownSource.value = text.source;
+            const refreshPreview = window.bindTextPreview(markdownSource, markdownPreview, () => 'markdown');
+            const showFormat = (format) => {
+                quillWrapper.classList.toggle('hidden', format === 'markdown');
+                markdownGroup.classList.toggle('hidden', format !== 'markdown');
+                if (format === 'markdown') refreshPreview();
+            };
+            textFormat.value = text.format;
+            showFormat(text.format);
+            textFormat.addEventListener('change', () => {
+                // Carry the text across rather than starting over.
+                if (textFormat.value === 'markdown' && !markdownSource.value.trim() && quill) {
+                    markdownSource.value = quill.getText().trim();
+                } else if (textFormat.value === 'html' && quill && quill.getLength() <= 1 && markdownPreview.innerHTML) {
+                    quill.clipboard.dangerouslyPasteHTML(markdownPreview.innerHTML);
+                }
+                showFormat(textFormat.value);
             });
         }
 
@@ -101,6 +301,60 @@ window.initBlockForm = function () {
         if (videoGroup) videoGroup.classList.remove('hidden');
         const vidInput = document.getElementById('video-url-input');
         if (vidInput) vidInput.value = initialContent;
+    } else if (blockType === 'Embed') {
+        if (embedGroup) embedGroup.classList.remove('hidden');
+        const embedInput = document.getElementById('embed-url-input');
+        if (embedInput) embedInput.value = initialContent;
+    } else if (blockType === 'HostedVideo') {
+        if (hostedVideoGroup) hostedVideoGroup.classList.remove('hidden');
+        if (fileGroup) fileGroup.classList.remove('hidden');
+        if (fileInput) fileInput.accept = 'video/*,.vtt,text/vtt';
+        try {
+            if (initialContent) {
+                const parsed = JSON.parse(initialContent);
+                if (videoSelect && parsed.media_id) videoSelect.value = parsed.media_id;
+                captions = parsed.captions || [];
+                renderCaptionList();
+            }
+        } catch (e) {
+            console.error("Error parsing initial content", e);
+        }
+    } else if (blockType === 'Quote') {
+        if (quoteGroup) quoteGroup.classList.remove('hidden');
+        const quote = parseJson(initialContent, {});
+        document.getElementById('quote-text').value = quote.text || '';
+        document.getElementById('quote-attribution').value = quote.attribution || '';
+        document.getElementById('quote-source').value = quote.source || '';
+    } else if (blockType === 'Credits' || blockType === 'LinkList' || blockType === 'Map') {
+        if (rowsGroup) rowsGroup.classList.remove('hidden');
+        document.getElementById('rows-label').textContent = ROW_LABELS[blockType];
+        rows = parseJson(initialContent, []);
+        if (rows.length === 0) rows.push({});
+        renderRows();
+        document.getElementById('add-row-btn').addEventListener('click', () => {
+            rows.push({});
+            renderRows();
+        });
+    } else if (blockType === 'Columns') {
+        if (columnsGroup) columnsGroup.classList.remove('hidden');
+        const layout = parseJson(initialContent, { columns: [[], []] });
+        columns = layout.columns.map(column => column.map(child => ({
+            block_type: child.block_type,
+            format: child.block_type === 'Text' ? parseJson(child.content, {}).format || 'html' : 'html',
+            text: childContentToText(child.block_type, child.content)
+        })));
+        columnCount.value = String(columns.length);
+        columnCount.addEventListener('change', () => {
+            const count = parseInt(columnCount.value, 10);
+            while (columns.length < count) columns.push([]);
+            if (columns.length > count) {
+                // Keep the blocks of dropped columns in the last remaining one.
+                const dropped = columns.splice(count).flat();
+                columns[count - 1].push(...dropped);
+            }
+            renderColumns();
+        });
+        renderColumns();
     } else {
         // Gallery, Audio, File
         if (fileGroup) fileGroup.classList.remove('hidden');
@@ -109,8 +363,10 @@ window.initBlockForm = function () {
                 const parsed = JSON.parse(initialContent);
                 if (blockType === 'Gallery') {
                     uploadedFiles = parsed.map(url => ({ url, title: '' }));
+                } else if (blockType === 'Audio') {
+                    uploadedFiles = parsed.map(item => ({ ...item }));
                 } else {
-                    uploadedFiles = parsed.map(item => ({ url: item[0], title: item[1] }));
+                    uploadedFiles = parsed.map(item => ({ ...item, title: item.description }));
                 }
                 renderFileList();
             }
@@ -200,7 +456,7 @@ window.initBlockForm = function () {
                         const resp = JSON.parse(xhr.responseText);
                         resolve(resp);
                     } else {
-                        reject(xhr.responseText);
+                        reject(window.apiError(xhr.responseText));
                     }
                 };
                 xhr.onerror = () => reject("Network error");
@@ -209,10 +465,37 @@ window.initBlockForm = function () {
             xhr.send(formData);
             const result = await promise;
 
-            uploadedFiles.push({
-                url: result.url,
-                title: result.original_name
-            });
+            if (blockType === 'HostedVideo') {
+                addHostedVideoUpload(result);
+                return;
+            }
+
+            if (blockType === 'Audio') {
+                uploadedFiles.push({
+                    url: result.url,
+                    title: result.original_name.replace(/\.[^/.]+$/, ""),
+                    media_id: result.media_id,
+                    duration: result.duration,
+                    artist: result.artist,
+                    artwork_url: null,
+                    peaks: result.peaks || []
+                });
+            } else if (blockType === 'File') {
+                uploadedFiles.push({
+                    url: result.url,
+                    title: result.original_name,
+                    media_id: result.media_id,
+                    filename: result.original_name,
+                    byte_size: result.byte_size,
+                    page_count: result.page_count,
+                    thumbnail_url: result.thumbnail_url
+                });
+            } else {
+                uploadedFiles.push({
+                    url: result.url,
+                    title: result.original_name
+                });
+            }
             renderFileList();
 
         } catch (error) {
@@ -232,17 +515,36 @@ window.initBlockForm = function () {
             let preview = '';
             if (blockType === 'Gallery') {
                 preview = `<img src="${file.url}" alt="preview">`;
+            } else if (file.thumbnail_url) {
+                preview = `<img src="${file.thumbnail_url}" alt="preview">`;
             } else {
                 preview = `<span class="material-icons file-icon">description</span>`;
             }
 
             const inputPlaceholder = blockType === 'File' ? 'Description' : (blockType === 'Audio' ? 'Song Title' : 'Caption (Optional)');
 
+            let fileMeta = '';
+            if (blockType === 'File' && file.byte_size) {
+                const pages = file.page_count ? ` · ${file.page_count} pages` : '';
+                fileMeta = `<div class="file-meta">${formatBytes(file.byte_size)}${pages}</div>`;
+            }
+
+            let audioFields = '';
+            if (blockType === 'Audio') {
+                const duration = file.duration ? ` · ${formatDuration(file.duration)}` : '';
+                audioFields = `
+                        <input type="text" class="form-input" placeholder="Artist / credit" value="${file.artist || ''}" onchange="window.updateFileField(${index}, 'artist', this.value)">
+                        <input type="url" class="form-input" placeholder="Artwork URL (Optional)" value="${file.artwork_url || ''}" onchange="window.updateFileField(${index}, 'artwork_url', this.value)">
+                        <div class="file-meta">${file.peaks && file.peaks.length ? 'Waveform ready' : 'No waveform'}${duration}</div>`;
+            }
+
             div.innerHTML = `
                     ${preview}
                     <div class="file-info">
                         <div class="file-name">${file.url.split('/').pop()}</div>
                         ${blockType !== 'Gallery' ? `<input type="text" class="form-input" placeholder="${inputPlaceholder}" value="${file.title}" onchange="window.updateFileTitle(${index}, this.value)">` : ''}
+                        ${audioFields}
+                        ${fileMeta}
                     </div>
                     <div class="remove-btn" onclick="window.removeFile(${index})">
                         <span class="material-icons">close</span>
@@ -252,6 +554,216 @@ window.initBlockForm = function () {
         });
     }
 
+    function addHostedVideoUpload(result) {
+        if (result.mime_type && result.mime_type.startsWith('video/')) {
+            if (videoSelect) {
+                const option = document.createElement('option');
+                option.value = result.media_id;
+                option.textContent = result.original_name;
+                videoSelect.appendChild(option);
+                videoSelect.value = result.media_id;
+            }
+        } else if (result.original_name.toLowerCase().endsWith('.vtt')) {
+            captions.push({ src: result.url, srclang: 'en', label: 'English' });
+            renderCaptionList();
+        } else {
+            window.showAlert('Error', result.original_name + ' is neither a video nor a WebVTT file.');
+        }
+    }
+
+    function renderCaptionList() {
+        if (!captionList) return;
+        captionList.innerHTML = '';
+        captions.forEach((track, index) => {
+            const div = document.createElement('div');
+            div.className = 'file-item';
+            div.innerHTML = `
+                    <span class="material-icons file-icon">closed_caption</span>
+                    <div class="file-info">
+                        <div class="file-name">${track.src.split('/').pop()}</div>
+                        <input type="text" class="form-input" placeholder="Language code (e.g. en)" value="${track.srclang}" onchange="window.updateCaption(${index}, 'srclang', this.value)">
+                        <input type="text" class="form-input" placeholder="Label (e.g. English)" value="${track.label}" onchange="window.updateCaption(${index}, 'label', this.value)">
+                    </div>
+                    <div class="remove-btn" onclick="window.removeCaption(${index})">
+                        <span class="material-icons">close</span>
+                    </div>
+                `;
+            captionList.appendChild(div);
+        });
+    }
+
+    window.updateCaption = function (index, field, value) {
+        captions[index][field] = value;
+    };
+
+    window.removeCaption = function (index) {
+        captions.splice(index, 1);
+        renderCaptionList();
+    };
+
+    // --- Credits / Link list / Map marker rows ---
+    function renderRows() {
+        if (!rowList) return;
+        rowList.innerHTML = '';
+        rows.forEach((row, index) => {
+            const div = document.createElement('div');
+            div.className = 'file-item row-item';
+            const inputs = ROW_FIELDS[blockType].map(([field, placeholder]) =>
+                `<input type="text" class="form-input" placeholder="${placeholder}" value="${escapeAttr(row[field] || '')}" onchange="window.updateRow(${index}, '${field}', this.value)">`
+            ).join('');
+            div.innerHTML = `
+                    <div class="file-info">${inputs}</div>
+                    <div class="remove-btn" onclick="window.removeRow(${index})">
+                        <span class="material-icons">close</span>
+                    </div>
+                `;
+            rowList.appendChild(div);
+        });
+    }
+
+    window.updateRow = function (index, field, value) {
+        rows[index][field] = value;
+    };
+
+    window.removeRow = function (index) {
+        rows.splice(index, 1);
+        renderRows();
+    };
+
+    // --- Columns ---
+    // Child blocks are edited as plain text and converted to the same content
+    // strings a top-level block form submits.
+
+    function childContentToText(type, content) {
+        const parsed = parseJson(content, null);
+        switch (type) {
+            case 'Text':
+                return parsed ? parsed.source : content || '';
+            case 'Gallery':
+                return (parsed || []).join('\n');
+            case 'Quote':
+                return parsed ? [parsed.text, parsed.attribution ? '— ' + parsed.attribution : '', parsed.source ? '— ' + parsed.source : ''].filter(Boolean).join('\n') : '';
+            case 'Credits':
+                return (parsed || []).map(c => `${c.role}: ${c.name}`).join('\n');
+            case 'LinkList':
+                return (parsed || []).map(l => [l.label, l.url, l.description].filter(Boolean).join(' | ')).join('\n');
+            case 'Map':
+                return (parsed || []).map(m => [m.label, `${m.lat}, ${m.lng}`, m.url].filter(Boolean).join(' | ')).join('\n');
+            default:
+                return content || '';
+        }
+    }
+
+    function childTextToContent(type, text, format) {
+        const lines = text.split('\n').map(l => l.trim()).filter(Boolean);
+        switch (type) {
+            case 'Text':
+                return JSON.stringify({ format: format || 'html', source: text.trim() });
+            case 'Gallery':
+                return JSON.stringify(lines);
+            case 'Quote': {
+                // Trailing lines starting with a dash are attribution, then source.
+                const all = text.trim().split('\n');
+                const credits = [];
+                while (all.length > 1 && /^\s*[—-]/.test(all[all.length - 1])) {
+                    credits.unshift(all.pop().replace(/^\s*[—-]+\s*/, ''));
+                }
+                return JSON.stringify({ text: all.join('\n'), attribution: credits[0] || null, source: credits[1] || null });
+            }
+            case 'Credits':
+                return JSON.stringify(lines.map(line => {
+                    const at = line.indexOf(':');
+                    return at < 0 ? { role: '', name: line } : { role: line.slice(0, at).trim(), name: line.slice(at + 1).trim() };
+                }));
+            case 'LinkList':
+                return JSON.stringify(lines.map(line => {
+                    const [label, url, description] = line.split('|').map(p => p.trim());
+                    return url ? { label, url, description: description || null } : { label: label, url: label };
+                }));
+            case 'Map':
+                // "Label | lat, lng | link", or an address in place of the coordinates.
+                return JSON.stringify(lines.map(line => {
+                    const [label, place = '', url] = line.split('|').map(p => p.trim());
+                    const [lat, lng] = place.split(',').map(parseCoordinate);
+                    return lat !== null && lng !== null && lng !== undefined
+                        ? { label, lat, lng, url: url || null }
+                        : { label, address: place || label, url: url || null };
+                }));
+            default:
+                return text.trim();
+        }
+    }
+
+    function renderColumns() {
+        if (!columnEditors) return;
+        columnEditors.innerHTML = '';
+        columnEditors.className = `column-editors columns-${columns.length}`;
+        columns.forEach((column, ci) => {
+            const col = document.createElement('div');
+            col.className = 'column-editor';
+            const children = column.map((child, bi) => `
+                    <div class="file-item column-child">
+                        <div class="file-info">
+                            <select class="form-input" onchange="window.updateChild(${ci}, ${bi}, 'block_type', this.value)">
+                                ${CHILD_TYPES.map(t => `<option value="${t}" ${t === child.block_type ? 'selected' : ''}>${t}</option>`).join('')}
+                            </select>
+                            ${child.block_type === 'Text' ? `
+                            <select class="form-input" onchange="window.updateChild(${ci}, ${bi}, 'format', this.value)">
+                                <option value="html" ${child.format !== 'markdown' ? 'selected' : ''}>HTML</option>
+                                <option value="markdown" ${child.format === 'markdown' ? 'selected' : ''}>Markdown</option>
+                            </select>` : ''}
+                            <textarea class="form-input" rows="4" onchange="window.updateChild(${ci}, ${bi}, 'text', this.value)">${escapeAttr(child.text)}</textarea>
+                        </div>
+                        <div class="remove-btn" onclick="window.removeChild(${ci}, ${bi})">
+                            <span class="material-icons">close</span>
+                        </div>
+                    </div>`).join('');
+            col.innerHTML = `
+                    <div class="form-label">Column ${ci + 1}</div>
+                    ${children}
+                    <button type="button" class="btn" onclick="window.addChild(${ci})">
+                        <span class="material-icons">add</span> Add block
+                    </button>
+                `;
+            columnEditors.appendChild(col);
+        });
+    }
+
+    window.addChild = function (ci) {
+        columns[ci].push({ block_type: 'Text', format: 'html', text: '' });
+        renderColumns();
+    };
+
+    window.updateChild = function (ci, bi, field, value) {
+        columns[ci][bi][field] = value;
+        // Text children get a format picker.
+        if (field === 'block_type') renderColumns();
+    };
+
+    window.removeChild = function (ci, bi) {
+        columns[ci].splice(bi, 1);
+        renderColumns();
+    };
+
+    function parseJson(value, fallback) {
+        if (!value) return fallback;
+        try {
+            return JSON.parse(value);
+        } catch (e) {
+            console.error("Error parsing initial content", e);
+            return fallback;
+        }
+    }
+
+    function parseCoordinate(value) {
+        const number = parseFloat(String(value ?? '').trim());
+        return Number.isFinite(number) ? number : null;
+    }
+
+    function escapeAttr(value) {
+        return String(value).replace(/&/g, '&amp;').replace(/"/g, '&quot;').replace(/</g, '&lt;');
+    }
+
     // Expose helpers globally so onclick handlers work
     window.removeFile = function (index) {
         uploadedFiles.splice(index, 1);
@@ -262,6 +774,26 @@ window.initBlockForm = function () {
         uploadedFiles[index].title = value;
     };
 
+    window.updateFileField = function (index, field, value) {
+        uploadedFiles[index][field] = value.trim() || null;
+    };
+
+    function formatBytes(bytes) {
+        const units = ['B', 'KB', 'MB', 'GB'];
+        let size = bytes;
+        let unit = 0;
+        while (size >= 1024 && unit < units.length - 1) {
+            size /= 1024;
+            unit++;
+        }
+        return `${unit === 0 ? size : size.toFixed(1)} ${units[unit]}`;
+    }
+
+    function formatDuration(seconds) {
+        const total = Math.round(seconds);
+        return `${Math.floor(total / 60)}:${(total % 60).toString().padStart(2, '0')}`;
+    }
+
     // --- Form Submission ---
     form.addEventListener('submit', function (e) {
         // CRITICAL: Prevent default submission so we can update values first
@@ -270,30 +802,65 @@ window.initBlockForm = function () {
         console.log('Form submitting, block type:', blockType);
 
         if (blockType === 'Text') {
-            // Explicitly sync Quill content to hidden input before submission
-            if (typeof quill !== 'undefined' && quill) {
-                const htmlContent = quill.root.innerHTML;
-                console.log('Quill content being saved:', htmlContent.substring(0, 100));
-                if (contentInput) {
-                    contentInput.value = htmlContent;
-                    console.log('Hidden input value set to:', contentInput.value.substring(0, 100));
-                }
+            const format = textFormat ? textFormat.value : 'html';
+            let source = '';
+            if (format === 'markdown') {
+                source = markdownSource.value;
+            } else if (typeof quill !== 'undefined' && quill) {
+                source = quill.root.innerHTML;
             } else {
                 console.warn('Quill instance not found!');
             }
+            if (contentInput) contentInput.value = JSON.stringify({ format, source });
         } else if (blockType === 'Video') {
             const vidInput = document.getElementById('video-url-input');
             if (contentInput && vidInput) {
                 contentInput.value = vidInput.value;
                 console.log('Video URL saved:', vidInput.value);
             }
+        } else if (blockType === 'Embed') {
+            const embedInput = document.getElementById('embed-url-input');
+            if (contentInput && embedInput) {
+                contentInput.value = embedInput.value.trim();
+            }
+        } else if (blockType === 'HostedVideo') {
+            if (contentInput) {
+                contentInput.value = JSON.stringify({
+                    media_id: videoSelect && videoSelect.value ? videoSelect.value : null,
+                    captions
+                });
+            }
+        } else if (blockType === 'Quote') {
+            contentInput.value = JSON.stringify({
+                text: document.getElementById('quote-text').value,
+                attribution: document.getElementById('quote-attribution').value,
+                source: document.getElementById('quote-source').value
+            });
+        } else if (blockType === 'Map') {
+            contentInput.value = JSON.stringify(rows.map(row => ({
+                ...row,
+                lat: parseCoordinate(row.lat),
+                lng: parseCoordinate(row.lng)
+            })));
+        } else if (blockType === 'Credits' || blockType === 'LinkList') {
+            contentInput.value = JSON.stringify(rows);
+        } else if (blockType === 'Columns') {
+            contentInput.value = JSON.stringify({
+                columns: columns.map(column => column.map(child => ({
+                    block_type: child.block_type,
+                    content: childTextToContent(child.block_type, child.text, child.format)
+                })))
+            });
         } else {
             // Serialize files
             let data;
             if (blockType === 'Gallery') {
                 data = uploadedFiles.map(f => f.url);
+            } else if (blockType === 'Audio') {
+                data = uploadedFiles;
             } else {
-                data = uploadedFiles.map(f => [f.url, f.title]);
+                // File: size, type and pages are filled in from the media library.
+                data = uploadedFiles.map(f => ({ url: f.url, description: f.title, media_id: f.media_id || null }));
             }
             const jsonData = JSON.stringify(data);
             console.log('File data being saved:', jsonData);
@@ -0,0 +1,251 @@
+// Minimal slippy map for Map blocks and the /map page.
+//
+// Tiles are plain <img> elements laid out in Web Mercator, so the only thing
+// the Content-Security-Policy has to allow is the tile host under img-src
+// (or nothing at all when tiles are served from /tiles).
+
+const TILE_SIZE = 256;
+const MIN_ZOOM = 1;
+
+function project(lat, lng, zoom) {
+    const scale = TILE_SIZE * Math.pow(2, zoom);
+    const sin = Math.min(Math.max(Math.sin(lat * Math.PI / 180), -0.9999), 0.9999);
+    return {
+        x: (lng + 180) / 360 * scale,
+        y: (0.5 - Math.log((1 + sin) / (1 - sin)) / (4 * Math.PI)) * scale
+    };
+}
+
+function unproject(x, y, zoom) {
+    const scale = TILE_SIZE * Math.pow(2, zoom);
+    const n = Math.PI - 2 * Math.PI * y / scale;
+    return {
+        lat: 180 / Math.PI * Math.atan(0.5 * (Math.exp(n) - Math.exp(-n))),
+        lng: x / scale * 360 - 180
+    };
+}
+
+function fitView(markers, width, height, maxZoom) {
+    if (markers.length === 1) {
+        return { center: markers[0], zoom: Math.min(14, maxZoom) };
+    }
+    const lats = markers.map(m => m.lat);
+    const lngs = markers.map(m => m.lng);
+    const bounds = {
+        north: Math.max(...lats), south: Math.min(...lats),
+        east: Math.max(...lngs), west: Math.min(...lngs)
+    };
+    let zoom = maxZoom;
+    for (; zoom > MIN_ZOOM; zoom--) {
+        const ne = project(bounds.north, bounds.east, zoom);
+        const sw = project(bounds.south, bounds.west, zoom);
+        // Leave room around the outermost markers.
+        if (ne.x - sw.x <= width - 80 && sw.y - ne.y <= height - 80) break;
+    }
+    return {
+        center: { lat: (bounds.north + bounds.south) / 2, lng: (bounds.east + bounds.west) / 2 },
+        zoom
+    };
+}
+
+function tileUrl(template, z, x, y) {
+    return template.replace('{z}', z).replace('{x}', x).replace('{y}', y);
+}
+
+function initMap(container) {
+    if (container.dataset.initialized === 'true') return;
+
+    let markers;
+    try {
+        markers = JSON.parse(container.dataset.markers || '[]');
+    } catch (e) {
+        return;
+    }
+    const template = container.dataset.tileUrl;
+    if (!template || markers.length === 0) return;
+    container.dataset.initialized = 'true';
+
+    const maxZoom = parseInt(container.dataset.maxZoom, 10) || 18;
+    const viewport = document.createElement('div');
+    viewport.className = 'map-viewport';
+    const tileLayer = document.createElement('div');
+    tileLayer.className = 'map-tiles';
+    const markerLayer = document.createElement('div');
+    markerLayer.className = 'map-markers';
+    const popup = document.createElement('div');
+    popup.className = 'map-popup';
+    popup.hidden = true;
+    viewport.append(tileLayer, markerLayer, popup);
+
+    const controls = document.createElement('div');
+    controls.className = 'map-controls';
+    const zoomIn = document.createElement('button');
+    zoomIn.type = 'button';
+    zoomIn.textContent = '+';
+    zoomIn.setAttribute('aria-label', 'Zoom in');
+    const zoomOut = document.createElement('button');
+    zoomOut.type = 'button';
+    zoomOut.textContent = '−';
+    zoomOut.setAttribute('aria-label', 'Zoom out');
+    controls.append(zoomIn, zoomOut);
+
+    container.prepend(viewport, controls);
+    container.classList.add('is-interactive');
+
+    const view = fitView(markers, container.clientWidth || 600, container.clientHeight || 400, maxZoom);
+    let zoom = view.zoom;
+    let center = project(view.center.lat, view.center.lng, zoom);
+    let openMarker = null;
+
+    function render() {
+        const width = container.clientWidth;
+        const height = container.clientHeight;
+        const originX = center.x - width / 2;
+        const originY = center.y - height / 2;
+        const count = Math.pow(2, zoom);
+
+        tileLayer.replaceChildren();
+        const firstX = Math.floor(originX / TILE_SIZE);
+        const firstY = Math.floor(originY / TILE_SIZE);
+        const lastX = Math.floor((originX + width) / TILE_SIZE);
+        const lastY = Math.floor((originY + height) / TILE_SIZE);
+        for (let ty = Math.max(firstY, 0); ty <= Math.min(lastY, count - 1); ty++) {
+            for (let tx = firstX; tx <= lastX; tx++) {
+                const img = document.createElement('img');
+                img.alt = '';
+                img.draggable = false;
+                // Wrap horizontally so panning past the antimeridian keeps going.
+                img.src = tileUrl(template, zoom, ((tx % count) + count) % count, ty);
+                img.style.left = `${tx * TILE_SIZE - originX}px`;
+                img.style.top = `${ty * TILE_SIZE - originY}px`;
+                tileLayer.appendChild(img);
+            }
+        }
+
+        markerLayer.replaceChildren();
+        markers.forEach((marker, index) => {
+            const point = project(marker.lat, marker.lng, zoom);
+            const pin = document.createElement('button');
+            pin.type = 'button';
+            pin.className = 'map-pin';
+            pin.setAttribute('aria-label', marker.label);
+            pin.style.left = `${point.x - originX}px`;
+            pin.style.top = `${point.y - originY}px`;
+            pin.addEventListener('click', (e) => {
+                e.stopPropagation();
+                openMarker = openMarker === index ? null : index;
+                render();
+            });
+            markerLayer.appendChild(pin);
+        });
+
+        if (openMarker === null) {
+            popup.hidden = true;
+        } else {
+            const marker = markers[openMarker];
+            const point = project(marker.lat, marker.lng, zoom);
+            popup.replaceChildren();
+            if (marker.url) {
+                const link = document.createElement('a');
+                link.href = marker.url;
+                link.textContent = marker.label;
+                popup.appendChild(link);
+            } else {
+                popup.textContent = marker.label;
+            }
+            popup.style.left = `${point.x - originX}px`;
+            popup.style.top = `${point.y - originY}px`;
+            popup.hidden = false;
+        }
+
+        zoomIn.disabled = zoom >= maxZoom;
+        zoomOut.disabled = zoom <= MIN_ZOOM;
+    }
+
+    // Zoom around a point in container coordinates, keeping it fixed on screen.
+    function setZoom(next, anchorX, anchorY) {
+        next = Math.min(Math.max(next, MIN_ZOOM), maxZoom);
+        if (next === zoom) return;
+        const width = container.clientWidth;
+        const height = container.clientHeight;
+        const ax = anchorX ?? width / 2;
+        const ay = anchorY ?? height / 2;
+        const anchor = unproject(center.x - width / 2 + ax, center.y - height / 2 + ay, zoom);
+        const moved = project(anchor.lat, anchor.lng, next);
+        center = { x: moved.x - ax + width / 2, y: moved.y - ay + height / 2 };
+        zoom = next;
+        render();
+    }
+
+    zoomIn.addEventListener('click', () => setZoom(zoom + 1));
+    zoomOut.addEventListener('click', () => setZoom(zoom - 1));
+
+    let wheelLock = false;
+    viewport.addEventListener('wheel', (e) => {
+        e.preventDefault();
+        if (wheelLock) return;
+        wheelLock = true;
+        setTimeout(() => { wheelLock = false; }, 250);
+        const rect = container.getBoundingClientRect();
+        setZoom(zoom + (e.deltaY < 0 ? 1 : -1), e.clientX - rect.left, e.clientY - rect.top);
+    }, { passive: false });
+
+    let drag = null;
+    viewport.addEventListener('pointerdown', (e) => {
+        if (e.target.closest('.map-pin, .map-popup')) return;
+        drag = { x: e.clientX, y: e.clientY, center: { ...center }, moved: false };
+        viewport.setPointerCapture(e.pointerId);
+        viewport.classList.add('is-dragging');
+    });
+    viewport.addEventListener('pointermove', (e) => {
+        if (!drag) return;
+        const dx = e.clientX - drag.x;
+        const dy = e.clientY - drag.y;
+        if (Math.abs(dx) + Math.abs(dy) > 3) drag.moved = true;
+        center = { x: drag.center.x - dx, y: drag.center.y - dy };
+        render();
+    });
+    const endDrag = () => {
+        if (drag && !drag.moved && openMarker !== null) {
+            openMarker = null;
+            render();
+        }
+        drag = null;
+        viewport.classList.remove('is-dragging');
+    };
+    viewport.addEventListener('pointerup', endDrag);
+    viewport.addEventListener('pointercancel', endDrag);
+
+    container.addEventListener('keydown', (e) => {
+        const step = 64;
+        const moves = {
+            ArrowLeft: [-step, 0], ArrowRight: [step, 0],
+            ArrowUp: [0, -step], ArrowDown: [0, step]
+        };
+        if (moves[e.key]) {
+            e.preventDefault();
+            center = { x: center.x + moves[e.key][0], y: center.y + moves[e.key][1] };
+            render();
+        } else if (e.key === '+' || e.key === '=') {
+            setZoom(zoom + 1);
+        } else if (e.key === '-') {
+            setZoom(zoom - 1);
+        } else if (e.key === 'Escape' && openMarker !== null) {
+            openMarker = null;
+            render();
+        }
+    });
+
+    window.addEventListener('resize', render);
+    render();
+}
+
+function initMaps() {
+    document.querySelectorAll('.slippy-map').forEach(initMap);
+}
+
+// Initialize on initial load
+document.addEventListener('DOMContentLoaded', initMaps);
+
+// Initialize after Router navigation
+document.addEventListener('router:load', initMaps);
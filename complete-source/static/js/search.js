@@ -0,0 +1,187 @@
+// Search on the static site, which has no server to ask. `gen_static` writes
+// an index as a manifest listing JSON chunks; they're fetched together and
+// the results are updated as each one arrives.
+//
+// Only runs on a search form with a data-search-index attribute: the server
+// renders its own results.
+
+(function () {
+    const MAX_RESULTS = 50;
+    const SNIPPET_CHARS = 160;
+    const TYPING_DELAY = 150;
+
+    const loaded = new Map();
+
+    // Lowercase without accents, so "Genève" is found by "geneve". `map`
+    // holds the index in the original text of each folded character.
+    function fold(text) {
+        let folded = '';
+        const map = [];
+        Array.from(text).forEach((c, i) => {
+            const f = c.normalize('NFD').replace(/[\u0300-\u036f]/g, '').toLowerCase();
+            for (let k = 0; k < f.length; k++) map.push(i);
+            folded += f;
+        });
+        return { folded, map, chars: Array.from(text) };
+    }
+
+    function prepare(entry) {
+        entry.folded = {
+            title: fold(entry.title).folded,
+            tags: fold((entry.tags || []).join(' ')).folded,
+            text: fold(entry.text || '')
+        };
+        return entry;
+    }
+
+    // The index at `url`, with `onChunk` called with every entry loaded so
+    // far as each chunk comes in. The index is kept for the next visit to
+    // the page; only the latest page is told about new chunks.
+    function loadIndex(url, onChunk) {
+        if (!loaded.has(url)) {
+            const index = { entries: [], onChunk: null };
+            index.done = fetch(url)
+                .then(r => r.json())
+                .then(manifest => Promise.all(manifest.chunks.map(chunk =>
+                    fetch(chunk).then(r => r.json()).then(entries => {
+                        index.entries.push(...entries.map(prepare));
+                        if (index.onChunk) index.onChunk(index.entries);
+                    })
+                )));
+            loaded.set(url, index);
+        }
+        const index = loaded.get(url);
+        index.onChunk = onChunk;
+        if (index.entries.length) onChunk(index.entries);
+        return index;
+    }
+
+    function terms(query) {
+        return fold(query).folded.split(/\s+/).filter(Boolean);
+    }
+
+    // Every word has to appear somewhere; title matches count most.
+    function score(entry, words) {
+        let total = 0;
+        for (const word of words) {
+            const s = (entry.folded.title.includes(word) ? 4 : 0)
+                + (entry.folded.tags.includes(word) ? 2 : 0)
+                + (entry.folded.text.folded.includes(word) ? 1 : 0);
+            if (!s) return 0;
+            total += s;
+        }
+        return total;
+    }
+
+    // The text around the first match, with each match in a <mark>.
+    function snippet(entry, words) {
+        const { folded, map, chars } = entry.folded.text;
+        if (!chars.length) return null;
+        const ranges = [];
+        for (const word of words) {
+            let at = folded.indexOf(word);
+            while (at !== -1) {
+                ranges.push([map[at], map[at + word.length - 1] + 1]);
+                at = folded.indexOf(word, at + word.length);
+            }
+        }
+        ranges.sort((a, b) => a[0] - b[0]);
+
+        const first = ranges.length ? ranges[0][0] : 0;
+        const start = Math.max(0, first - SNIPPET_CHARS / 4);
+        const end = Math.min(chars.length, start + SNIPPET_CHARS);
+
+        const p = document.createElement('p');
+        p.className = 'search-snippet';
+        let pos = start;
+        if (start > 0) p.append('… ');
+        for (const [from, to] of ranges) {
+            if (to <= pos || from >= end) continue;
+            p.append(chars.slice(pos, Math.max(pos, from)).join(''));
+            const mark = document.createElement('mark');
+            mark.textContent = chars.slice(Math.max(pos, from), Math.min(to, end)).join('');
+            p.append(mark);
+            pos = Math.min(to, end);
+        }
+        p.append(chars.slice(pos, end).join(''));
+        if (end < chars.length) p.append(' …');
+        return p;
+    }
+
+    function render(output, entries, query) {
+        const words = terms(query);
+        output.replaceChildren();
+        if (!words.length) return;
+
+        const hits = entries
+            .map(entry => ({ entry, score: score(entry, words) }))
+            .filter(hit => hit.score > 0)
+            .sort((a, b) => b.score - a.score || (b.entry.year || 0) - (a.entry.year || 0))
+            .slice(0, MAX_RESULTS);
+
+        if (!hits.length) {
+            const empty = document.createElement('p');
+            empty.className = 'search-empty';
+            empty.textContent = `Nothing found for “${query.trim()}”.`;
+            output.append(empty);
+            return;
+        }
+
+        const list = document.createElement('ol');
+        list.className = 'search-results';
+        for (const { entry } of hits) {
+            const li = document.createElement('li');
+            const link = document.createElement('a');
+            link.href = entry.url;
+            link.textContent = entry.title;
+            li.append(link);
+            if (entry.year) {
+                const year = document.createElement('span');
+                year.className = 'item-meta';
+                year.textContent = entry.year;
+                li.append(year);
+            }
+            const text = snippet(entry, words);
+            if (text) li.append(text);
+            list.append(li);
+        }
+        output.append(list);
+    }
+
+    function initSearch() {
+        const form = document.querySelector('form[data-search-index]');
+        if (!form || form.dataset.searchReady) return;
+        form.dataset.searchReady = 'true';
+
+        const input = form.querySelector('input[name="q"]');
+        const output = document.querySelector('.search-output');
+        input.value = new URLSearchParams(window.location.search).get('q') || input.value;
+
+        let entries = [];
+        const update = () => render(output, entries, input.value);
+        const index = loadIndex(form.dataset.searchIndex, loadedEntries => {
+            entries = loadedEntries;
+            update();
+        });
+        index.done.catch(error => {
+            console.error('Search index failed to load:', error);
+            output.textContent = 'Search is unavailable right now.';
+        });
+
+        let typing;
+        input.addEventListener('input', () => {
+            clearTimeout(typing);
+            typing = setTimeout(update, TYPING_DELAY);
+        });
+        form.addEventListener('submit', e => {
+            e.preventDefault();
+            const url = new URL(window.location.href);
+            url.searchParams.set('q', input.value.trim());
+            window.history.replaceState(window.history.state, '', url);
+            update();
+        });
+    }
+
+    document.addEventListener('DOMContentLoaded', initSearch);
+    document.addEventListener('router:load', initSearch);
+})();
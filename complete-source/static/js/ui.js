@@ -1,5 +1,48 @@
 console.log("UI Script loaded");
 
+// The message of a failed admin API call, which answers {"error": "..."}.
+window.apiError = function (body) {
+    try {
+        return JSON.parse(body).error || body;
+    } catch (e) {
+        return body;
+    }
+};
+
+// Shows flash messages that came with a partial rather than a page, in the
+// same list the admin templates render, e.g. [{level: 'success', text: 'Block saved'}].
+window.showFlash = function (messages) {
+    const main = document.querySelector('main');
+    if (!main || !messages || !messages.length) return;
+    let list = main.querySelector('.flash-messages');
+    if (!list) {
+        list = document.createElement('div');
+        list.className = 'flash-messages';
+        list.setAttribute('role', 'status');
+        (main.firstElementChild || main).prepend(list);
+    }
+    messages.forEach(({ level, text }) => {
+        const item = document.createElement('p');
+        item.className = `flash flash-${level}`;
+        item.textContent = text + '.';
+        const close = document.createElement('button');
+        close.type = 'button';
+        close.className = 'flash-close';
+        close.setAttribute('aria-label', 'Dismiss');
+        close.textContent = '\u00d7';
+        item.appendChild(close);
+        list.appendChild(item);
+    });
+};
+
+// HTMX raises this from a partial's HX-Trigger header.
+document.addEventListener('flash', (e) => window.showFlash(e.detail.messages));
+
+document.addEventListener('click', (e) => {
+    const close = e.target.closest('.flash-close');
+    if (close) close.closest('.flash').remove();
+});
+
 function initUI() {
     // --- Modals Logic ---
     const confirmModal = document.getElementById('confirmModal');
@@ -29,7 +29,8 @@ class UnifiedPlayer {
         this.isPlaying = false;
         this.isMuted = false;
         this.volume = 100;
-        this.duration = 0;
+        // Known up front for uploads analysed by the server.
+        this.duration = parseFloat(container.dataset.duration) || 0;
         this.currentTime = 0;
         this.type = this.detectType(this.url);
 
@@ -73,7 +74,11 @@ class UnifiedPlayer {
     }
 
     initNative() {
-        this.audio = new Audio(this.url);
+        this.audio = new Audio();
+        // With the duration already on the page there's nothing to gain from
+        // fetching the file before the visitor presses play.
+        this.audio.preload = this.duration ? 'none' : 'metadata';
+        this.audio.src = this.url;
 
         // Set Title only if empty
         if (!this.trackTitle.textContent.trim()) {